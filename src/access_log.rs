@@ -0,0 +1,351 @@
+use crate::curl_parser::CurlCommand;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+lazy_static::lazy_static! {
+    /// nginx / Apache combined（或 common）格式：`host ident user [time] "request" status bytes ...`
+    static ref COMBINED_REGEX: Regex =
+        Regex::new(r#"^\S+ \S+ \S+ \[([^\]]+)\] "([^"]*)""#).unwrap();
+}
+
+/// JSON 日志中时间、方法和路径可能使用的字段名
+const TIME_FIELDS: &[&str] = &[
+    "time",
+    "timestamp",
+    "@timestamp",
+    "time_iso8601",
+    "time_local",
+    "msec",
+    "ts",
+];
+const METHOD_FIELDS: &[&str] = &["method", "request_method", "verb"];
+const PATH_FIELDS: &[&str] = &["path", "uri", "request_uri", "url"];
+
+/// 访问日志格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// nginx / Apache combined 或 common 格式
+    Combined,
+    /// 每行一个 JSON 对象
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "combined" | "common" | "nginx" | "apache" => Ok(Self::Combined),
+            "json" | "jsonl" => Ok(Self::Json),
+            _ => Err(anyhow!(
+                "Unknown log format: {} (expected combined or json)",
+                s
+            )),
+        }
+    }
+}
+
+/// 日志回放方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayMode {
+    /// 按日志中的时间间隔发送每一条请求
+    Timing,
+    /// 只保留路径分布：每个路径一个端点，按出现次数加权
+    Mix,
+}
+
+impl FromStr for ReplayMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "timing" => Ok(Self::Timing),
+            "mix" => Ok(Self::Mix),
+            _ => Err(anyhow!(
+                "Unknown replay mode: {} (expected timing or mix)",
+                s
+            )),
+        }
+    }
+}
+
+/// 访问日志回放选项
+#[derive(Debug, Clone)]
+pub struct LogReplayOptions {
+    pub format: LogFormat,
+    pub mode: ReplayMode,
+    /// 请求发往的基础 URL
+    pub target: String,
+    /// 回放速度倍数（2 表示两倍速）
+    pub speed: f64,
+}
+
+#[derive(Debug)]
+struct LogEntry {
+    time: DateTime<FixedOffset>,
+    method: String,
+    path: String,
+}
+
+/// 从访问日志生成请求
+///
+/// 只使用方法、路径和时间：查询字符串、请求头和客户端信息都不会被回放。
+/// `Timing` 模式下每条日志一条命令，`offset` 是按速度缩放后的相对时间；
+/// `Mix` 模式下每个 `方法 路径` 一条命令，`weight` 是出现次数。
+pub fn parse_access_log_file(path: &Path, options: &LogReplayOptions) -> Result<Vec<CurlCommand>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read access log {}: {}", path.display(), e))?;
+    let (commands, skipped) = parse_access_log(&content, options)?;
+    if skipped > 0 {
+        eprintln!(
+            "Warning: skipped {} unparseable lines in {}",
+            skipped,
+            path.display()
+        );
+    }
+    Ok(commands)
+}
+
+fn parse_access_log(
+    content: &str,
+    options: &LogReplayOptions,
+) -> Result<(Vec<CurlCommand>, usize)> {
+    if !(options.speed > 0.0 && options.speed.is_finite()) {
+        return Err(anyhow!("Replay speed must be a positive number"));
+    }
+    let target = options.target.trim_end_matches('/');
+    url::Url::parse(target).map_err(|e| anyhow!("Invalid --target {}: {}", target, e))?;
+
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let entry = match options.format {
+            LogFormat::Combined => parse_combined_line(line),
+            LogFormat::Json => parse_json_line(line),
+        };
+        match entry {
+            Some(entry) => entries.push(entry),
+            None => skipped += 1,
+        }
+    }
+    if entries.is_empty() {
+        return Err(anyhow!(
+            "No requests found in access log ({} unparseable lines)",
+            skipped
+        ));
+    }
+
+    let commands = match options.mode {
+        ReplayMode::Timing => {
+            // 日志大体有序，稳定排序保留同一时刻请求的原始顺序
+            entries.sort_by_key(|entry| entry.time);
+            let first = entries[0].time;
+            entries
+                .into_iter()
+                .map(|entry| {
+                    let offset = (entry.time - first).to_std().unwrap_or_default();
+                    let mut cmd = to_command(target, &entry.method, &entry.path);
+                    cmd.offset = Some(offset.div_f64(options.speed));
+                    cmd
+                })
+                .collect()
+        }
+        ReplayMode::Mix => {
+            let mut index: HashMap<(String, String), usize> = HashMap::new();
+            let mut commands: Vec<CurlCommand> = Vec::new();
+            for entry in entries {
+                let key = (entry.method, entry.path);
+                match index.get(&key) {
+                    Some(&i) => commands[i].weight += 1,
+                    None => {
+                        index.insert(key.clone(), commands.len());
+                        commands.push(to_command(target, &key.0, &key.1));
+                    }
+                }
+            }
+            commands.sort_by_key(|cmd| std::cmp::Reverse(cmd.weight));
+            commands
+        }
+    };
+
+    Ok((commands, skipped))
+}
+
+fn to_command(target: &str, method: &str, path: &str) -> CurlCommand {
+    let mut cmd = CurlCommand::new(format!("{}{}", target, path));
+    cmd.method = method.to_string();
+    cmd.name = Some(format!("{} {}", method, path));
+    cmd
+}
+
+fn parse_combined_line(line: &str) -> Option<LogEntry> {
+    let caps = COMBINED_REGEX.captures(line)?;
+    let time = DateTime::parse_from_str(&caps[1], "%d/%b/%Y:%H:%M:%S %z").ok()?;
+    // 请求行：`GET /path HTTP/1.1`
+    let mut parts = caps[2].split_whitespace();
+    let method = parts.next()?;
+    let path = parts.next()?;
+    entry(time, method, path)
+}
+
+fn parse_json_line(line: &str) -> Option<LogEntry> {
+    let value: Value = serde_json::from_str(line).ok()?;
+    let field = |names: &[&str]| names.iter().find_map(|name| value.get(*name));
+
+    let time = parse_time(field(TIME_FIELDS)?)?;
+    let (method, path) = match (field(METHOD_FIELDS), field(PATH_FIELDS)) {
+        (Some(method), Some(path)) => (method.as_str()?, path.as_str()?),
+        // 只有完整的请求行（nginx 的 $request）
+        _ => {
+            let mut parts = value.get("request")?.as_str()?.split_whitespace();
+            (parts.next()?, parts.next()?)
+        }
+    };
+    entry(time, method, path)
+}
+
+/// RFC 3339、nginx `$time_local` 或 Unix 时间戳（秒，可带小数；或毫秒）
+fn parse_time(value: &Value) -> Option<DateTime<FixedOffset>> {
+    let seconds = match value {
+        Value::Number(n) => n.as_f64()?,
+        Value::String(s) => {
+            if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+                return Some(time);
+            }
+            if let Ok(time) = DateTime::parse_from_str(s, "%d/%b/%Y:%H:%M:%S %z") {
+                return Some(time);
+            }
+            s.parse().ok()?
+        }
+        _ => return None,
+    };
+    // 大于 1e11 的数值按毫秒处理
+    let millis = if seconds > 1e11 {
+        seconds
+    } else {
+        seconds * 1000.0
+    };
+    let time = Utc.timestamp_millis_opt(millis as i64).single()?;
+    Some(time.fixed_offset())
+}
+
+/// 只保留路径，去掉查询字符串和片段
+fn entry(time: DateTime<FixedOffset>, method: &str, target: &str) -> Option<LogEntry> {
+    if !method.bytes().all(|b| b.is_ascii_uppercase()) || method.is_empty() {
+        return None;
+    }
+    // 代理日志中的绝对 URI
+    let path = match target.find("://") {
+        Some(scheme_end) => {
+            let rest = &target[scheme_end + 3..];
+            &rest[rest.find('/').unwrap_or(rest.len())..]
+        }
+        None => target,
+    };
+    let path = path.split(['?', '#']).next().unwrap_or("");
+    if !path.starts_with('/') {
+        return None;
+    }
+    Some(LogEntry {
+        time,
+        method: method.to_string(),
+        path: path.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const COMBINED: &str = r#"203.0.113.7 - - [10/Oct/2024:13:55:36 +0000] "GET /products?id=42&email=a%40b.c HTTP/1.1" 200 2326 "https://shop.example.com/" "Mozilla/5.0"
+203.0.113.8 - alice [10/Oct/2024:13:55:38 +0000] "POST /cart HTTP/2.0" 201 512 "-" "curl/8.4.0"
+203.0.113.9 - - [10/Oct/2024:15:55:37 +0200] "GET /products HTTP/1.1" 200 2326
+198.51.100.1 - - [10/Oct/2024:13:55:40 +0000] "-" 400 0 "-" "-"
+garbage line
+203.0.113.7 - - [10/Oct/2024:13:55:41 +0000] "GET http://shop.example.com/products/ HTTP/1.1" 200 10 "-" "-"
+"#;
+
+    fn options(format: LogFormat, mode: ReplayMode, speed: f64) -> LogReplayOptions {
+        LogReplayOptions {
+            format,
+            mode,
+            target: "http://localhost:8080/".to_string(),
+            speed,
+        }
+    }
+
+    #[test]
+    fn test_combined_timing() {
+        let (commands, skipped) = parse_access_log(
+            COMBINED,
+            &options(LogFormat::Combined, ReplayMode::Timing, 2.0),
+        )
+        .unwrap();
+        assert_eq!(skipped, 2);
+
+        let requests: Vec<_> = commands
+            .iter()
+            .map(|cmd| (cmd.method.as_str(), cmd.url.as_str(), cmd.offset.unwrap()))
+            .collect();
+        // 查询字符串被丢弃，时区统一，偏移按两倍速缩放
+        assert_eq!(
+            requests,
+            [
+                ("GET", "http://localhost:8080/products", Duration::ZERO),
+                (
+                    "GET",
+                    "http://localhost:8080/products",
+                    Duration::from_millis(500)
+                ),
+                ("POST", "http://localhost:8080/cart", Duration::from_secs(1)),
+                (
+                    "GET",
+                    "http://localhost:8080/products/",
+                    Duration::from_millis(2500)
+                ),
+            ]
+        );
+        assert_eq!(commands[2].name.as_deref(), Some("POST /cart"));
+        assert!(commands[0].headers.is_empty());
+    }
+
+    #[test]
+    fn test_json_mix() {
+        let log = r#"{"time": "2024-10-10T13:55:36.120Z", "method": "GET", "path": "/a?x=1"}
+{"@timestamp": "2024-10-10T13:55:36.500+00:00", "request": "GET /b HTTP/1.1", "status": 200}
+{"msec": 1728568536.9, "request_method": "GET", "request_uri": "/a"}
+{"ts": 1728568537000, "verb": "DELETE", "uri": "/a"}
+{"time_local": "10/Oct/2024:13:55:37 +0000", "method": "GET", "path": "/a"}
+{"method": "GET", "path": "/no-time"}
+not json
+"#;
+        let (commands, skipped) =
+            parse_access_log(log, &options(LogFormat::Json, ReplayMode::Mix, 1.0)).unwrap();
+        assert_eq!(skipped, 2);
+        let mix: Vec<_> = commands
+            .iter()
+            .map(|cmd| (cmd.name.as_deref().unwrap(), cmd.weight))
+            .collect();
+        assert_eq!(mix, [("GET /a", 3), ("GET /b", 1), ("DELETE /a", 1)]);
+        assert!(commands.iter().all(|cmd| cmd.offset.is_none()));
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let opts = options(LogFormat::Combined, ReplayMode::Timing, 1.0);
+        assert!(parse_access_log("nothing here\n", &opts).is_err());
+        assert!(parse_access_log(
+            COMBINED,
+            &options(LogFormat::Combined, ReplayMode::Mix, 0.0)
+        )
+        .is_err());
+        assert!("xml".parse::<LogFormat>().is_err());
+        assert_eq!("mix".parse::<ReplayMode>().unwrap(), ReplayMode::Mix);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::cli::Args;
-use crate::curl_parser::parse_curl_command;
-use crate::engine::run_benchmark;
-use anyhow::Result;
+use crate::curl_parser::{parse_curl_command, CurlCommand};
+use crate::engine::run_benchmark_with;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::time::Instant;
@@ -15,7 +15,16 @@ struct BatchConfig {
 #[derive(Debug, Deserialize, Serialize, Clone)]
 struct TestConfig {
     name: String,
-    curl: String,
+    /// 单个请求；与 endpoints 二选一
+    #[serde(default)]
+    curl: Option<String>,
+    /// 多个端点，每个可以带名称和权重
+    #[serde(default)]
+    endpoints: Vec<EndpointConfig>,
+    #[serde(default = "default_load_strategy")]
+    load_strategy: String,
+    #[serde(default)]
+    seed: Option<u64>,
     #[serde(default = "default_connections")]
     connections: usize,
     #[serde(default = "default_duration")]
@@ -30,8 +39,35 @@ struct TestConfig {
     verbose: bool,
     #[serde(default)]
     use_nethttp: bool,
+    #[serde(default)]
+    tcp_nodelay: bool,
+    #[serde(default)]
+    so_rcvbuf: Option<String>,
+    #[serde(default)]
+    so_sndbuf: Option<String>,
+    #[serde(default)]
+    tcp_keepalive: Option<String>,
+    #[serde(default)]
+    so_linger_zero: bool,
+    #[serde(default)]
+    tcp_fastopen: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct EndpointConfig {
+    curl: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default = "default_weight")]
+    weight: u32,
 }
 
+fn default_load_strategy() -> String {
+    "random".to_string()
+}
+fn default_weight() -> u32 {
+    1
+}
 fn default_connections() -> usize {
     10
 }
@@ -107,8 +143,8 @@ async fn run_single_test(test: TestConfig) -> TestResult {
     let start = Instant::now();
 
     // Parse curl command
-    let curl_cmd = match parse_curl_command(&test.curl) {
-        Ok(cmd) => cmd,
+    let commands = match parse_test_commands(&test) {
+        Ok(commands) => commands,
         Err(e) => {
             return TestResult {
                 name: test.name,
@@ -119,6 +155,8 @@ async fn run_single_test(test: TestConfig) -> TestResult {
         }
     };
 
+    let curl_cmd = &commands[0];
+
     // Create args for this test
     let args = Args {
         url: Some(curl_cmd.url.clone()),
@@ -137,11 +175,41 @@ async fn run_single_test(test: TestConfig) -> TestResult {
         verbose: test.verbose,
         use_nethttp: test.use_nethttp,
         http2: false, // 默认使用 HTTP/1.1
+        ws_message: None,
+        grpc_method: None,
+        grpc_descriptor: None,
+        stream: None,
+        bind_address: Vec::new(),
+        tcp_nodelay: test.tcp_nodelay,
+        so_rcvbuf: test.so_rcvbuf.clone(),
+        so_sndbuf: test.so_sndbuf.clone(),
+        tcp_keepalive: test.tcp_keepalive.clone(),
+        so_linger_zero: test.so_linger_zero,
+        tcp_fastopen: test.tcp_fastopen,
         latency: false,
         live_ui: false,
-        parse_curl: None,
+        // 交给引擎重新解析，保留 curl 的传输参数（-k、-x、--resolve 等）
+        parse_curl: test.curl.clone(),
         parse_curl_file: None,
-        load_strategy: "random".to_string(),
+        http_file: None,
+        har: None,
+        har_host: Vec::new(),
+        har_method: Vec::new(),
+        har_mime: Vec::new(),
+        openapi: None,
+        openapi_server: None,
+        openapi_tag: Vec::new(),
+        openapi_operation: Vec::new(),
+        postman: None,
+        postman_env: None,
+        replay_log: None,
+        log_format: "combined".to_string(),
+        target: None,
+        replay_mode: "timing".to_string(),
+        replay_speed: 1.0,
+        scenario: None,
+        load_strategy: Some(test.load_strategy.clone()),
+        seed: test.seed,
         content_type: None,
         mock_server: false,
         mock_port: 8080,
@@ -156,9 +224,10 @@ async fn run_single_test(test: TestConfig) -> TestResult {
         vars: Vec::new(),
         help_templates: false,
     };
+    let endpoints = (!test.endpoints.is_empty()).then_some(commands);
 
     // Run the benchmark
-    match run_benchmark(args).await {
+    match run_benchmark_with(args, endpoints).await {
         Ok(_) => TestResult {
             name: test.name,
             duration: start.elapsed(),
@@ -174,6 +243,27 @@ async fn run_single_test(test: TestConfig) -> TestResult {
     }
 }
 
+/// 解析测试的 `curl` 或 `endpoints`
+fn parse_test_commands(test: &TestConfig) -> Result<Vec<CurlCommand>> {
+    match (&test.curl, test.endpoints.is_empty()) {
+        (Some(curl), true) => Ok(vec![parse_curl_command(curl)?]),
+        (None, false) => test
+            .endpoints
+            .iter()
+            .map(|endpoint| {
+                if endpoint.weight == 0 {
+                    return Err(anyhow!("weight must be at least 1: {}", endpoint.curl));
+                }
+                let mut cmd = parse_curl_command(&endpoint.curl)?;
+                cmd.name = endpoint.name.clone().or(cmd.name);
+                cmd.weight = endpoint.weight;
+                Ok(cmd)
+            })
+            .collect(),
+        _ => Err(anyhow!("a test needs either `curl` or `endpoints`")),
+    }
+}
+
 fn load_config(path: &Path) -> Result<BatchConfig> {
     let content = std::fs::read_to_string(path)?;
 
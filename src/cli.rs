@@ -1,13 +1,15 @@
-use clap::Parser;
+use crate::net::SocketOptions;
+use clap::{ArgGroup, Parser};
 use std::path::PathBuf;
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "quickurl")]
 #[command(about = "A modern, high-performance HTTP benchmarking tool", long_about = None)]
 #[command(version)]
+#[command(group(ArgGroup::new("input").multiple(false)))]
 pub struct Args {
     /// Target URL to benchmark
-    #[arg(value_name = "URL")]
+    #[arg(value_name = "URL", group = "input")]
     pub url: Option<String>,
 
     /// Number of HTTP connections to keep open
@@ -30,23 +32,95 @@ pub struct Args {
     #[arg(long = "timeout", default_value = "30s")]
     pub timeout: String,
 
-    /// Parse curl command and use it for benchmarking
-    #[arg(long = "parse-curl")]
+    /// Parse curl command (or a fetch, PowerShell or HTTPie snippet) and use it for benchmarking
+    #[arg(long = "parse-curl", group = "input")]
     pub parse_curl: Option<String>,
 
-    /// Parse multiple curl commands from file (one per line)
-    #[arg(long = "parse-curl-file")]
+    /// Parse multiple curl commands from file (multi-line commands and devtools exports, including fetch and PowerShell, supported)
+    #[arg(long = "parse-curl-file", group = "input")]
     pub parse_curl_file: Option<PathBuf>,
 
-    /// Load distribution strategy: random, round-robin
-    #[arg(long = "load-strategy", default_value = "random")]
-    pub load_strategy: String,
+    /// Read requests from a .http file (VS Code REST Client / JetBrains HTTP Client format)
+    #[arg(long = "http-file", group = "input")]
+    pub http_file: Option<PathBuf>,
+
+    /// Import requests from a HAR file captured in the browser
+    #[arg(long = "har", group = "input")]
+    pub har: Option<PathBuf>,
+
+    /// Only import HAR entries for these hosts (`*.example.com` allowed)
+    #[arg(long = "har-host", value_delimiter = ',')]
+    pub har_host: Vec<String>,
+
+    /// Only import HAR entries with these HTTP methods
+    #[arg(long = "har-method", value_delimiter = ',')]
+    pub har_method: Vec<String>,
+
+    /// Only import HAR entries whose response has these MIME types (`image/*` allowed)
+    #[arg(long = "har-mime", value_delimiter = ',')]
+    pub har_mime: Vec<String>,
+
+    /// Generate endpoints from an OpenAPI 3 document (YAML or JSON)
+    #[arg(long = "openapi", group = "input")]
+    pub openapi: Option<PathBuf>,
+
+    /// Base URL for OpenAPI operations (overrides the document's servers)
+    #[arg(long = "openapi-server")]
+    pub openapi_server: Option<String>,
+
+    /// Only import OpenAPI operations with these tags
+    #[arg(long = "openapi-tag", value_delimiter = ',')]
+    pub openapi_tag: Vec<String>,
+
+    /// Only import OpenAPI operations with these operationIds
+    #[arg(long = "openapi-operation", value_delimiter = ',')]
+    pub openapi_operation: Vec<String>,
+
+    /// Import requests from a Postman collection (v2.0 / v2.1)
+    #[arg(long = "postman", group = "input")]
+    pub postman: Option<PathBuf>,
+
+    /// Postman environment file providing variable values
+    #[arg(long = "postman-env", requires = "postman")]
+    pub postman_env: Option<PathBuf>,
+
+    /// Replay an access log against --target (nginx/Apache combined or JSON lines)
+    #[arg(long = "replay-log", requires = "target", group = "input")]
+    pub replay_log: Option<PathBuf>,
+
+    /// Access log format: combined, json
+    #[arg(long = "log-format", default_value = "combined")]
+    pub log_format: String,
+
+    /// Base URL that replayed log paths are sent to
+    #[arg(long = "target")]
+    pub target: Option<String>,
+
+    /// Log playback: timing (original timing) or mix (weighted path mix only)
+    #[arg(long = "replay-mode", default_value = "timing")]
+    pub replay_mode: String,
+
+    /// Speed multiplier for timing playback (2 = twice as fast)
+    #[arg(long = "replay-speed", default_value = "1.0")]
+    pub replay_speed: f64,
+
+    /// Run a multi-step scenario file (YAML/JSON): ordered steps with response extraction and think times
+    #[arg(long = "scenario", group = "input")]
+    pub scenario: Option<PathBuf>,
+
+    /// Load distribution strategy: random (default), round-robin, weighted (per-command weights), replay (original order and timing)
+    #[arg(long = "load-strategy")]
+    pub load_strategy: Option<String>,
+
+    /// Seed for random template values and endpoint choices, so a run can be repeated (random when omitted; printed in the report)
+    #[arg(long = "seed")]
+    pub seed: Option<u64>,
 
     /// HTTP method
     #[arg(short = 'X', long = "method", default_value = "GET")]
     pub method: String,
 
-    /// HTTP header to add to request (can be used multiple times)
+    /// HTTP header to add to request (can be used multiple times; `Name:` removes, `Name;` sends an empty value)
     #[arg(short = 'H', long = "header")]
     pub headers: Vec<String>,
 
@@ -78,6 +152,56 @@ pub struct Args {
     #[arg(long = "http2")]
     pub http2: bool,
 
+    /// Message sent on each WebSocket round trip for ws:// and wss:// targets
+    /// (supports template variables, defaults to the request body or "ping")
+    #[arg(long = "ws-message")]
+    pub ws_message: Option<String>,
+
+    /// Benchmark a gRPC unary method (e.g. package.Service/Method); the
+    /// request is the JSON given with --data
+    #[arg(long = "grpc-method")]
+    pub grpc_method: Option<String>,
+
+    /// Message types for --grpc-method: a .proto source (imports are resolved
+    /// from its directory) or a compiled descriptor set (protoc
+    /// --include_imports --descriptor_set_out). Server reflection is used when
+    /// omitted
+    #[arg(long = "grpc-descriptor")]
+    pub grpc_descriptor: Option<PathBuf>,
+
+    /// Benchmark streaming responses: sse (text/event-stream, default) or
+    /// lines (one event per line); reports time to first event and event gaps
+    #[arg(long = "stream", num_args = 0..=1, default_missing_value = "sse")]
+    pub stream: Option<String>,
+
+    /// Local source IP or interface name for new connections (repeatable or comma-separated)
+    #[arg(long = "bind-address", value_delimiter = ',')]
+    pub bind_address: Vec<String>,
+
+    /// Set TCP_NODELAY on client sockets (disable Nagle's algorithm)
+    #[arg(long = "tcp-nodelay")]
+    pub tcp_nodelay: bool,
+
+    /// Socket receive buffer size SO_RCVBUF (e.g. 65536, 256k, 4m)
+    #[arg(long = "so-rcvbuf")]
+    pub so_rcvbuf: Option<String>,
+
+    /// Socket send buffer size SO_SNDBUF (e.g. 65536, 256k, 4m)
+    #[arg(long = "so-sndbuf")]
+    pub so_sndbuf: Option<String>,
+
+    /// Enable TCP keepalive with the given idle time before probing (e.g. 30s)
+    #[arg(long = "tcp-keepalive")]
+    pub tcp_keepalive: Option<String>,
+
+    /// Set SO_LINGER to 0 so closed connections are reset instead of entering TIME_WAIT
+    #[arg(long = "so-linger-zero")]
+    pub so_linger_zero: bool,
+
+    /// Enable TCP Fast Open for client connections (Linux only)
+    #[arg(long = "tcp-fastopen")]
+    pub tcp_fastopen: bool,
+
     /// Start mock HTTP server
     #[arg(long = "mock-server")]
     pub mock_server: bool,
@@ -135,22 +259,72 @@ impl Args {
     pub fn parse_timeout(&self) -> anyhow::Result<std::time::Duration> {
         parse_duration_string(&self.timeout)
     }
+
+    pub fn socket_options(&self) -> anyhow::Result<SocketOptions> {
+        Ok(SocketOptions {
+            nodelay: self.tcp_nodelay,
+            recv_buffer: self
+                .so_rcvbuf
+                .as_deref()
+                .map(|s| parse_buffer_size("--so-rcvbuf", s))
+                .transpose()?,
+            send_buffer: self
+                .so_sndbuf
+                .as_deref()
+                .map(|s| parse_buffer_size("--so-sndbuf", s))
+                .transpose()?,
+            keepalive: self
+                .tcp_keepalive
+                .as_deref()
+                .map(parse_duration_string)
+                .transpose()?,
+            linger_zero: self.so_linger_zero,
+            fast_open: self.tcp_fastopen,
+        })
+    }
+}
+
+/// 解析字节数，支持 k/m 后缀（1024 进制）
+pub fn parse_size_string(s: &str) -> anyhow::Result<usize> {
+    let s = s.trim().to_lowercase();
+    let (num, multiplier) = if let Some(num) = s.strip_suffix('k') {
+        (num, 1024)
+    } else if let Some(num) = s.strip_suffix('m') {
+        (num, 1024 * 1024)
+    } else {
+        (s.as_str(), 1)
+    };
+
+    let num: usize = num
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid size: {}", s))?;
+    num.checked_mul(multiplier)
+        .ok_or_else(|| anyhow::anyhow!("Size too large: {}", s))
 }
 
-fn parse_duration_string(s: &str) -> anyhow::Result<std::time::Duration> {
+/// 套接字缓冲区大小，setsockopt 接受的是 C int，上限为 i32::MAX
+fn parse_buffer_size(flag: &str, s: &str) -> anyhow::Result<u32> {
+    let size = parse_size_string(s)?;
+    if size > i32::MAX as usize {
+        anyhow::bail!("{} {} exceeds the maximum of {} bytes", flag, s, i32::MAX);
+    }
+    Ok(size as u32)
+}
+
+pub fn parse_duration_string(s: &str) -> anyhow::Result<std::time::Duration> {
     let s = s.trim();
 
-    if s.ends_with("ms") {
-        let num: u64 = s[..s.len() - 2].parse()?;
+    if let Some(num) = s.strip_suffix("ms") {
+        let num: u64 = num.parse()?;
         Ok(std::time::Duration::from_millis(num))
-    } else if s.ends_with('s') {
-        let num: u64 = s[..s.len() - 1].parse()?;
+    } else if let Some(num) = s.strip_suffix('s') {
+        let num: u64 = num.parse()?;
         Ok(std::time::Duration::from_secs(num))
-    } else if s.ends_with('m') {
-        let num: u64 = s[..s.len() - 1].parse()?;
+    } else if let Some(num) = s.strip_suffix('m') {
+        let num: u64 = num.parse()?;
         Ok(std::time::Duration::from_secs(num * 60))
-    } else if s.ends_with('h') {
-        let num: u64 = s[..s.len() - 1].parse()?;
+    } else if let Some(num) = s.strip_suffix('h') {
+        let num: u64 = num.parse()?;
         Ok(std::time::Duration::from_secs(num * 3600))
     } else {
         // Default to seconds
@@ -182,4 +356,48 @@ mod tests {
             std::time::Duration::from_millis(100)
         );
     }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size_string("65536").unwrap(), 65536);
+        assert_eq!(parse_size_string("256k").unwrap(), 256 * 1024);
+        assert_eq!(parse_size_string("4M").unwrap(), 4 * 1024 * 1024);
+        assert!(parse_size_string("lots").is_err());
+        assert!(parse_size_string(&format!("{}m", usize::MAX / 2)).is_err());
+    }
+
+    #[test]
+    fn test_socket_buffer_sizes() {
+        let args = |buffer: &str| {
+            Args::try_parse_from([
+                "quickurl",
+                "--so-rcvbuf",
+                buffer,
+                "--so-sndbuf",
+                "64k",
+                "http://x",
+            ])
+            .unwrap()
+            .socket_options()
+        };
+        let options = args("2047m").unwrap();
+        assert_eq!(options.recv_buffer, Some(2047 * 1024 * 1024));
+        assert_eq!(options.send_buffer, Some(64 * 1024));
+        assert!(args(&i32::MAX.to_string()).is_ok());
+        assert!(args("2048m").is_err());
+        assert!(args("4095m").is_err());
+        assert!(args(&format!("{}k", usize::MAX)).is_err());
+    }
+
+    #[test]
+    fn test_input_sources_conflict() {
+        let err = Args::try_parse_from(["quickurl", "--har", "a.har", "--parse-curl", "curl x"])
+            .unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+        assert!(Args::try_parse_from(["quickurl", "http://x", "--parse-curl-file", "f"]).is_err());
+        assert!(
+            Args::try_parse_from(["quickurl", "--openapi", "api.yaml", "--har", "a.har"]).is_err()
+        );
+        assert!(Args::try_parse_from(["quickurl", "--har", "a.har"]).is_ok());
+    }
 }
@@ -1,31 +1,444 @@
+use crate::access_log::{self, LogReplayOptions, ReplayMode};
 use crate::cli::Args;
 use crate::curl_parser::{parse_curl_command, parse_curl_file, CurlCommand};
-use crate::http_client::{ClientState, ConnectionPool};
+use crate::grpc;
+use crate::har::{self, HarFilter};
+use crate::http_client::{authority_of, ClientOptions, ClientState, ConnectionPool};
+use crate::http_file;
+use crate::net::{self, SocketOptions};
+use crate::openapi::{self, OpenApiOptions};
+use crate::postman;
+use crate::scenario::{self, Scenario};
 use crate::stats::{
     create_shared_stats, RequestResult, SharedStats, Statistics, StatisticsSnapshot,
 };
-use crate::template::TemplateEngine;
+use crate::streaming::StreamFormat;
+use crate::template::{CommandTemplate, TemplateEngine, TemplateState};
 use crate::ui::LiveUI;
-use anyhow::Result;
-use rand::Rng;
+use crate::websocket;
+use anyhow::{anyhow, Result};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
+/// 一次压测的公共参数（所有 worker 共享）
+#[derive(Debug, Clone)]
+pub struct BenchmarkConfig {
+    pub connections: usize,
+    pub threads: usize,
+    pub duration: Duration,
+    pub rate: u32,
+    pub timeout: Duration,
+    pub load_strategy: String,
+    pub enable_http2: bool,
+    /// 流式响应模式（按事件统计）
+    pub stream: Option<StreamFormat>,
+    /// 新连接轮流绑定的本地源地址
+    pub bind_addresses: Vec<IpAddr>,
+    /// 客户端套接字参数
+    pub socket: SocketOptions,
+    /// 随机数种子（`--seed`，未指定时随机选取），每个连接由它导出自己的种子
+    pub seed: u64,
+}
+
+/// `--load-strategy` 可选的策略
+const LOAD_STRATEGIES: &[&str] = &["random", "round-robin", "weighted", "replay"];
+
+impl BenchmarkConfig {
+    pub fn from_args(args: &Args) -> Result<Self> {
+        if let Some(strategy) = &args.load_strategy {
+            if !LOAD_STRATEGIES.contains(&strategy.as_str()) {
+                return Err(anyhow!(
+                    "unknown load strategy `{}` (expected one of: {})",
+                    strategy,
+                    LOAD_STRATEGIES.join(", ")
+                ));
+            }
+        }
+        let replay_mode = match &args.replay_log {
+            Some(_) => Some(args.replay_mode.parse::<ReplayMode>()?),
+            None => None,
+        };
+        let load_strategy = match (replay_mode, &args.load_strategy) {
+            // 访问日志按时间回放时，所有连接共同重现一条时间线
+            (Some(ReplayMode::Timing), Some(strategy)) => anyhow::bail!(
+                "--load-strategy {} can't be combined with --replay-mode timing (use --replay-mode mix)",
+                strategy
+            ),
+            (Some(ReplayMode::Timing), None) => "timeline".to_string(),
+            // 路径混合模式默认按日志中的请求数加权
+            (Some(ReplayMode::Mix), None) => "weighted".to_string(),
+            (_, Some(strategy)) => strategy.clone(),
+            (None, None) => "random".to_string(),
+        };
+
+        Ok(Self {
+            connections: args.connections,
+            threads: args.threads,
+            duration: args.parse_duration()?,
+            rate: args.rate,
+            timeout: args.parse_timeout()?,
+            load_strategy,
+            enable_http2: args.http2,
+            stream: args.stream.as_deref().map(str::parse).transpose()?,
+            bind_addresses: net::resolve_bind_addresses(&args.bind_address)?,
+            socket: args.socket_options()?,
+            seed: args.seed.unwrap_or_else(rand::random),
+        })
+    }
+
+    /// 参考 oha：使用物理 CPU 核心数
+    pub fn worker_threads(&self) -> usize {
+        let num_physical_cpus = num_cpus::get_physical();
+        if self.threads == 0 {
+            num_physical_cpus
+        } else {
+            self.threads.min(num_physical_cpus * 2)
+        }
+    }
+
+    /// 单线程、默认参数的短时压测（测试用）
+    #[cfg(test)]
+    pub fn for_test(connections: usize, duration: Duration) -> Self {
+        Self {
+            connections,
+            threads: 1,
+            duration,
+            rate: 0,
+            timeout: Duration::from_secs(1),
+            load_strategy: "random".to_string(),
+            enable_http2: false,
+            stream: None,
+            bind_addresses: Vec::new(),
+            socket: SocketOptions::default(),
+            seed: 0,
+        }
+    }
+}
+
+/// `weighted` 策略按命令权重选择端点，其他策略返回 None
+fn endpoint_weights(
+    commands: &[CurlCommand],
+    load_strategy: &str,
+) -> Result<Option<Arc<WeightedIndex<u32>>>> {
+    if load_strategy != "weighted" {
+        return Ok(None);
+    }
+    let weights = WeightedIndex::new(commands.iter().map(|cmd| cmd.weight))?;
+    Ok(Some(Arc::new(weights)))
+}
+
+/// 每个端点预期的流量占比：`weighted` 按权重，其他策略每条命令相同
+fn intended_shares(commands: &[CurlCommand], load_strategy: &str) -> HashMap<String, f64> {
+    let weight = |cmd: &CurlCommand| {
+        if load_strategy == "weighted" {
+            cmd.weight as f64
+        } else {
+            1.0
+        }
+    };
+    let total: f64 = commands.iter().map(weight).sum();
+    let mut shares = HashMap::new();
+    for cmd in commands {
+        *shares.entry(cmd.label().to_string()).or_insert(0.0) += weight(cmd) / total;
+    }
+    shares
+}
+
+/// 时间线回放的周期：最后一个请求的偏移再加一个平均间隔
+fn timeline_period(commands: &[CurlCommand]) -> Duration {
+    let last = commands
+        .iter()
+        .filter_map(|cmd| cmd.offset)
+        .max()
+        .unwrap_or_default();
+    let gap = if commands.len() > 1 {
+        last / (commands.len() - 1) as u32
+    } else {
+        Duration::ZERO
+    };
+    last + gap.max(Duration::from_millis(1))
+}
+
+/// 传给每个连接任务的上下文
+pub struct WorkerContext {
+    /// 连接编号（从 0 开始，在本次压测内唯一）
+    pub connection_id: usize,
+    /// 实际运行的连接任务总数（线程数 × 每线程连接数）
+    pub connections: usize,
+    /// 统计结果发送端（kanal 通道，无锁）
+    pub tx: kanal::Sender<RequestResult>,
+    /// 压测结束时间
+    pub end_time: Instant,
+    /// 本连接的随机数种子（由 `--seed` 和连接编号导出）
+    pub seed: u64,
+}
+
+/// 按负载策略选择端点，在启动连接前构建一次，所有连接共用
+#[derive(Clone)]
+pub struct EndpointPicker {
+    load_strategy: String,
+    weights: Option<Arc<WeightedIndex<u32>>>,
+    /// 每条命令在会话中的发送时间（`replay` / `timeline`）
+    offsets: Arc<Vec<Option<Duration>>>,
+    period: Duration,
+}
+
+impl EndpointPicker {
+    pub fn new(commands: &[CurlCommand], load_strategy: &str) -> Result<Self> {
+        Ok(Self {
+            load_strategy: load_strategy.to_string(),
+            weights: endpoint_weights(commands, load_strategy)?,
+            offsets: Arc::new(commands.iter().map(|cmd| cmd.offset).collect()),
+            period: timeline_period(commands),
+        })
+    }
+
+    /// 为一个连接创建端点序列和模板状态
+    ///
+    /// 端点选择和模板各用一个随机数生成器，由连接的种子导出，
+    /// 所以相同的 `--seed` 下每个连接选到的端点序列相同。
+    pub fn connection(&self, ctx: &WorkerContext) -> (EndpointSequence, TemplateState) {
+        let mut rng = StdRng::seed_from_u64(ctx.seed);
+        let template_state = TemplateState::new(rng.gen());
+        let sequence = EndpointSequence {
+            picker: self.clone(),
+            rng,
+            connection_id: ctx.connection_id,
+            connections: ctx.connections,
+            count: 0,
+            session_start: Instant::now(),
+        };
+        (sequence, template_state)
+    }
+}
+
+/// 一个连接依次选择的端点
+pub struct EndpointSequence {
+    picker: EndpointPicker,
+    rng: StdRng,
+    connection_id: usize,
+    connections: usize,
+    count: usize,
+    session_start: Instant,
+}
+
+impl EndpointSequence {
+    /// 下一个端点的下标，以及回放策略下它的发送时间（None 表示立即发送）
+    pub fn next(&mut self) -> (usize, Option<Instant>) {
+        let offsets = &self.picker.offsets;
+        let count = self.count;
+        self.count += 1;
+        match self.picker.load_strategy.as_str() {
+            // 各连接从不同的端点开始轮询
+            "round-robin" => ((self.connection_id + count) % offsets.len(), None),
+            // 按原始顺序回放，等到请求在会话中的发送时间；最后一个请求后开始新会话
+            "replay" => {
+                let idx = count % offsets.len();
+                if idx == 0 {
+                    self.session_start = Instant::now();
+                }
+                (idx, offsets[idx].map(|offset| self.session_start + offset))
+            }
+            // 所有连接共用一条按周期重复的时间线，连接 k 负责第 k、k+C、k+2C... 个请求
+            "timeline" => {
+                let slot = self.connection_id + count * self.connections;
+                let round = (slot / offsets.len()) as u32;
+                let idx = slot % offsets.len();
+                let at = self.picker.period * round + offsets[idx].unwrap_or_default();
+                (idx, Some(self.session_start + at))
+            }
+            "weighted" => (
+                self.picker.weights.as_ref().unwrap().sample(&mut self.rng),
+                None,
+            ),
+            // random (default)
+            _ => (self.rng.gen_range(0..offsets.len()), None),
+        }
+    }
+}
+
+/// 连接的随机数种子：相同的 `--seed` 下，同一编号的连接生成相同的随机序列
+fn connection_seed(seed: u64, connection_id: usize) -> u64 {
+    seed.wrapping_add((connection_id as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// 建立连接失败后的重试间隔：从 10ms 开始每次翻倍，最长 1s，连接成功后重置
+///
+/// 目标拒绝连接时，不等待就重试会让每个连接任务空转，占满 CPU 并刷屏连接错误。
+#[derive(Default)]
+pub struct ReconnectBackoff {
+    failures: u32,
+}
+
+impl ReconnectBackoff {
+    const INITIAL: Duration = Duration::from_millis(10);
+    const MAX: Duration = Duration::from_secs(1);
+
+    /// 当前的重试间隔，并记录一次失败
+    fn next_delay(&mut self) -> Duration {
+        let delay = Self::INITIAL
+            .saturating_mul(1 << self.failures.min(16))
+            .min(Self::MAX);
+        self.failures += 1;
+        delay
+    }
+
+    /// 连接失败后等待，最晚等到压测结束
+    pub async fn wait(&mut self, end_time: Instant) {
+        let due = (Instant::now() + self.next_delay()).min(end_time);
+        tokio::time::sleep_until(due.into()).await;
+    }
+
+    pub fn reset(&mut self) {
+        self.failures = 0;
+    }
+}
+
+/// 启动时列出无法解析的 `{{...}}` 占位符（它们会原样发送），每个只提示一次；
+/// 取值无效的模板（如 `{{random:10-1}}`）直接报错
+fn warn_unresolved(
+    template_engine: &TemplateEngine,
+    commands: &[CurlCommand],
+    scenario: Option<&Scenario>,
+    args: &Args,
+) -> Result<()> {
+    // 场景中提取的变量在运行时才有值
+    let extracted: Vec<&str> = scenario
+        .iter()
+        .flat_map(|scenario| &scenario.steps)
+        .flat_map(|step| step.extract.iter().map(|(name, _)| name.as_str()))
+        .collect();
+    let texts = commands
+        .iter()
+        .flat_map(|cmd| {
+            [cmd.method.as_str(), cmd.url.as_str()]
+                .into_iter()
+                .chain(cmd.headers.iter().map(|(_, value)| value))
+                .chain(cmd.body.as_deref())
+        })
+        .chain(args.ws_message.as_deref());
+
+    let mut unresolved: Vec<String> = Vec::new();
+    for text in texts {
+        for template in template_engine.unresolved(text)? {
+            if !extracted.contains(&template.as_str()) && !unresolved.contains(&template) {
+                unresolved.push(template);
+            }
+        }
+    }
+    if !unresolved.is_empty() {
+        let list: Vec<String> = unresolved
+            .iter()
+            .map(|template| format!("{{{{{}}}}}", template))
+            .collect();
+        eprintln!(
+            "Warning: unresolved template placeholders are sent as-is: {}",
+            list.join(", ")
+        );
+    }
+    Ok(())
+}
+
 pub async fn run_benchmark(args: Args) -> Result<()> {
+    run_benchmark_with(args, None).await
+}
+
+/// 与 `run_benchmark` 相同，`endpoints` 是预先解析好的命令（批量测试的 endpoints），
+/// 优先于 args 中的请求来源
+pub async fn run_benchmark_with(args: Args, endpoints: Option<Vec<CurlCommand>>) -> Result<()> {
+    // 导入的集合变量，作为模板变量注册（--var 可覆盖）
+    let mut imported_variables = Vec::new();
+    let mut scenario: Option<Scenario> = None;
+
     // Parse curl commands if provided
-    let commands = if let Some(curl_cmd) = &args.parse_curl {
+    let commands = if let Some(endpoints) = endpoints {
+        endpoints
+    } else if let Some(curl_cmd) = &args.parse_curl {
         vec![parse_curl_command(curl_cmd)?]
     } else if let Some(curl_file) = &args.parse_curl_file {
         parse_curl_file(curl_file)?
+    } else if let Some(http_file) = &args.http_file {
+        let file = http_file::parse_http_file(http_file)?;
+        imported_variables = file.variables;
+        file.commands
+    } else if let Some(har_file) = &args.har {
+        let filter = HarFilter {
+            hosts: args.har_host.clone(),
+            methods: args.har_method.clone(),
+            mime_types: args.har_mime.clone(),
+        };
+        har::parse_har_file(har_file, &filter)?
+    } else if let Some(spec) = &args.openapi {
+        let options = OpenApiOptions {
+            server: args.openapi_server.clone(),
+            tags: args.openapi_tag.clone(),
+            operations: args.openapi_operation.clone(),
+        };
+        let mut commands = openapi::parse_openapi_file(spec, &options)?;
+        // 文档不包含认证等请求头，由 -H 统一添加
+        for cmd in &mut commands {
+            for header in &args.headers {
+                cmd.headers.apply_curl(header);
+            }
+        }
+        commands
+    } else if let Some(collection) = &args.postman {
+        let import = postman::parse_postman_file(collection, args.postman_env.as_deref())?;
+        imported_variables = import.variables;
+        import.commands
+    } else if let Some(log) = &args.replay_log {
+        let options = LogReplayOptions {
+            format: args.log_format.parse()?,
+            mode: args.replay_mode.parse()?,
+            target: args.target.clone().unwrap_or_default(),
+            speed: args.replay_speed,
+        };
+        access_log::parse_access_log_file(log, &options)?
+    } else if let Some(path) = &args.scenario {
+        let loaded = scenario::parse_scenario_file(path)?;
+        imported_variables = loaded.variables.clone();
+        let commands = loaded
+            .steps
+            .iter()
+            .map(|step| step.command.clone())
+            .collect();
+        scenario = Some(loaded);
+        commands
     } else if let Some(url) = &args.url {
         vec![create_command_from_args(&args, url.clone())]
     } else {
         anyhow::bail!("No URL or curl command provided");
     };
 
+    // 列出 curl 命令中被忽略的参数，避免悄悄发出与 curl 不同的请求
+    let mut ignored_options: Vec<&str> = Vec::new();
+    for option in commands.iter().flat_map(|cmd| &cmd.ignored_options) {
+        if !ignored_options.contains(&option.as_str()) {
+            ignored_options.push(option);
+        }
+    }
+    if !ignored_options.is_empty() {
+        eprintln!(
+            "Warning: ignoring unsupported curl options: {}",
+            ignored_options.join(", ")
+        );
+    }
+
+    let config = BenchmarkConfig::from_args(&args)?;
+
     // Setup template engine
     let mut template_engine = TemplateEngine::new();
+    template_engine.set_seed(config.seed);
+    for (name, value) in imported_variables {
+        template_engine.add_static(name, value);
+    }
     for var in &args.vars {
         if let Some(pos) = var.find('=') {
             let name = var[..pos].to_string();
@@ -33,11 +446,90 @@ pub async fn run_benchmark(args: Args) -> Result<()> {
             template_engine.add_variable(name, definition)?;
         }
     }
-    let template_engine = Arc::new(template_engine);
+    warn_unresolved(&template_engine, &commands, scenario.as_ref(), &args)?;
+
+    if config.load_strategy != "weighted" && commands.iter().any(|cmd| cmd.weight != 1) {
+        eprintln!(
+            "Warning: endpoint weights only apply to --load-strategy weighted and are ignored"
+        );
+    }
+    let shares = intended_shares(&commands, &config.load_strategy);
+    let seed = config.seed;
+
+    let websocket_mode = commands
+        .iter()
+        .all(|cmd| websocket::is_websocket_url(&cmd.url));
+    if !websocket_mode
+        && commands
+            .iter()
+            .any(|cmd| websocket::is_websocket_url(&cmd.url))
+    {
+        anyhow::bail!("Cannot mix ws:// and http:// endpoints in one benchmark");
+    }
+
+    // 按请求偏移回放和时间线回放只在 HTTP 模式中实现
+    let protocol = if args.grpc_method.is_some() {
+        Some("gRPC")
+    } else if websocket_mode {
+        Some("WebSocket")
+    } else {
+        None
+    };
+    if let Some(protocol) = protocol {
+        match config.load_strategy.as_str() {
+            "replay" => anyhow::bail!(
+                "--load-strategy replay is not supported for {} benchmarks",
+                protocol
+            ),
+            "timeline" => anyhow::bail!(
+                "--replay-mode timing is not supported for {} benchmarks",
+                protocol
+            ),
+            _ => {}
+        }
+    }
+
+    // gRPC 模式：先解析方法描述符（描述符文件或服务端反射）
+    let grpc_method = match &args.grpc_method {
+        Some(method) => Some(
+            grpc::resolve_method(method, args.grpc_descriptor.as_deref(), &commands[0].url).await?,
+        ),
+        None => None,
+    };
+
+    if scenario.is_some() && (websocket_mode || grpc_method.is_some() || config.stream.is_some()) {
+        anyhow::bail!("Scenarios only support plain HTTP requests");
+    }
+
+    if !config.bind_addresses.is_empty() && (websocket_mode || grpc_method.is_some()) {
+        eprintln!("Warning: --bind-address only applies to HTTP benchmarks and is ignored");
+    }
+    if !config.socket.is_default() && (websocket_mode || grpc_method.is_some()) {
+        eprintln!("Warning: socket options only apply to HTTP benchmarks and are ignored");
+    }
+    if (websocket_mode || grpc_method.is_some())
+        && commands
+            .iter()
+            .any(|cmd| cmd.transport != Default::default())
+    {
+        eprintln!("Warning: curl transport options only apply to HTTP benchmarks and are ignored");
+    }
+    if config.socket.fast_open && !cfg!(target_os = "linux") {
+        eprintln!("Warning: --tcp-fastopen is only supported on Linux and is ignored");
+    }
 
     // Print test configuration
-    let duration = args.parse_duration()?;
-    let target_desc = if commands.len() == 1 {
+    let target_desc = if let Some(scenario) = &scenario {
+        format!(
+            "scenario {}({} steps)",
+            scenario
+                .name
+                .as_ref()
+                .map(|name| format!("{} ", name))
+                .unwrap_or_default(),
+            scenario.steps.len()
+        )
+    } else if commands.len() == 1 {
         commands[0].url.clone()
     } else {
         format!("{} endpoints", commands.len())
@@ -45,47 +537,89 @@ pub async fn run_benchmark(args: Args) -> Result<()> {
 
     // If live-ui is enabled, don't print initial messages (UI will handle it)
     if !args.live_ui {
-        println!("Running {}s test @ {}", duration.as_secs(), target_desc);
+        println!(
+            "Running {}s {}test @ {}",
+            config.duration.as_secs(),
+            if grpc_method.is_some() {
+                "gRPC "
+            } else if websocket_mode {
+                "WebSocket "
+            } else if config.stream.is_some() {
+                "streaming "
+            } else {
+                ""
+            },
+            target_desc
+        );
         println!(
             "  {} threads and {} connections",
             args.threads, args.connections
         );
+        if !config.bind_addresses.is_empty() {
+            println!("  {} source addresses", config.bind_addresses.len());
+        }
+        if !config.socket.is_default() {
+            println!("  socket options: {}", config.socket.describe());
+        }
+        if config.load_strategy == "replay" {
+            let session = commands.iter().filter_map(|cmd| cmd.offset).max();
+            println!(
+                "  replaying {} requests per session ({:.2}s)",
+                commands.len(),
+                session.unwrap_or_default().as_secs_f64()
+            );
+        }
+        if config.load_strategy == "timeline" {
+            println!(
+                "  replaying {} log entries over {:.2}s ({}x speed), spread across connections",
+                commands.len(),
+                timeline_period(&commands).as_secs_f64(),
+                args.replay_speed
+            );
+        } else if args.replay_log.is_some() && config.load_strategy == "weighted" {
+            let total: u64 = commands.iter().map(|cmd| cmd.weight as u64).sum();
+            println!(
+                "  {} endpoints weighted by {} log entries",
+                commands.len(),
+                total
+            );
+        }
     }
 
-    // Create shared statistics (removed unused variable)
-
     // Run the benchmark（使用 kanal 通道收集统计）
-    let final_stats = if args.live_ui {
-        // Run with Live-UI
-        run_benchmark_with_ui(
-            commands,
-            args.connections,
-            args.threads,
-            duration,
-            args.rate,
-            args.parse_timeout()?,
-            args.load_strategy.clone(),
-            template_engine,
-            args.http2,
-        )
+    let step_names: Vec<String> = scenario
+        .iter()
+        .flat_map(|scenario| scenario.steps.iter().map(|step| step.name.clone()))
+        .collect();
+    let mut final_stats = if let Some(scenario) = scenario {
+        execute(config, args.live_ui, move |config, shared_stats| {
+            scenario::run_workers(scenario, &config, &template_engine, shared_stats)
+        })
+        .await?
+    } else if let Some(method) = grpc_method {
+        execute(config, args.live_ui, move |config, shared_stats| {
+            grpc::run_workers(commands, method, &config, &template_engine, shared_stats)
+        })
+        .await?
+    } else if websocket_mode {
+        let message = args.ws_message.clone();
+        execute(config, args.live_ui, move |config, shared_stats| {
+            websocket::run_workers(commands, message, &config, &template_engine, shared_stats)
+        })
         .await?
     } else {
-        // Run without UI
-        tokio::task::block_in_place(|| {
-            run_workers(
-                commands,
-                args.connections,
-                args.threads,
-                duration,
-                args.rate,
-                args.parse_timeout()?,
-                &args.load_strategy,
-                template_engine,
-                args.http2,
-            )
-        })?
+        execute(config, args.live_ui, move |config, shared_stats| {
+            run_workers(commands, &config, &template_engine, shared_stats)
+        })
+        .await?
     };
 
+    if step_names.is_empty() {
+        final_stats.intended_shares = shares;
+    }
+    final_stats.step_names = step_names;
+    final_stats.seed = Some(seed);
+
     // Print results (only if not using live-ui, as UI already shows final stats)
     if !args.live_ui {
         final_stats.print_summary(args.latency);
@@ -98,21 +632,30 @@ pub async fn run_benchmark(args: Args) -> Result<()> {
     Ok(())
 }
 
+/// 运行 worker，根据是否启用 Live-UI 选择执行方式
+async fn execute<F>(config: BenchmarkConfig, live_ui: bool, run: F) -> Result<Statistics>
+where
+    F: FnOnce(BenchmarkConfig, Option<SharedStats>) -> Result<Statistics> + Send + 'static,
+{
+    if live_ui {
+        // Run with Live-UI
+        run_benchmark_with_ui(config, run).await
+    } else {
+        // Run without UI
+        tokio::task::block_in_place(|| run(config, None))
+    }
+}
+
 fn create_command_from_args(args: &Args, url: String) -> CurlCommand {
     let mut cmd = CurlCommand::new(url);
     cmd.method = args.method.clone();
 
     for header in &args.headers {
-        if let Some(pos) = header.find(':') {
-            let key = header[..pos].trim().to_string();
-            let value = header[pos + 1..].trim().to_string();
-            cmd.headers.insert(key, value);
-        }
+        cmd.headers.apply_curl(header);
     }
 
     if let Some(content_type) = &args.content_type {
-        cmd.headers
-            .insert("Content-Type".to_string(), content_type.clone());
+        cmd.headers.set("Content-Type", content_type.clone());
     }
 
     cmd.body = args.data.clone();
@@ -122,49 +665,208 @@ fn create_command_from_args(args: &Args, url: String) -> CurlCommand {
 
 fn run_workers(
     commands: Vec<CurlCommand>,
-    connections: usize,
-    threads: usize,
-    duration: Duration,
-    rate: u32,
-    timeout: Duration,
-    load_strategy: &str,
-    template_engine: Arc<TemplateEngine>,
-    enable_http2: bool,
+    config: &BenchmarkConfig,
+    template_engine: &TemplateEngine,
+    shared_stats: Option<SharedStats>,
 ) -> Result<Statistics> {
+    // 模板在启动时编译一次
+    let templates: Arc<Vec<CommandTemplate>> = Arc::new(
+        commands
+            .iter()
+            .map(|cmd| template_engine.compile_command(cmd))
+            .collect::<Result<_>>()?,
+    );
     let commands = Arc::new(commands);
-    let load_strategy = load_strategy.to_string();
-    let end_time = Instant::now() + duration;
+    let picker = EndpointPicker::new(&commands, &config.load_strategy)?;
+    let rate = config.rate;
+    let stream_format = config.stream;
+
+    let (pools, pool_index) = connection_pools(&commands, config)?;
+    let pools = Arc::new(pools);
+    let (state_index, state_count) = client_state_index(&commands, &pool_index);
+    let pool_index = Arc::new(pool_index);
+    let state_index = Arc::new(state_index);
+
+    run_connections(config, shared_stats, move |ctx| {
+        let commands = commands.clone();
+        let templates = templates.clone();
+        let pool_index = pool_index.clone();
+        let state_index = state_index.clone();
+        let (mut endpoints, mut template_state) = picker.connection(&ctx);
+        let clients: Vec<_> = pools.iter().map(|pool| pool.get_client()).collect();
+
+        async move {
+            // 创建客户端状态用于连接复用（每个来源一个）
+            let mut client_states: Vec<ClientState> =
+                (0..state_count).map(|_| ClientState::new()).collect();
+
+            while Instant::now() < ctx.end_time {
+                // Select command based on load strategy
+                let (idx, due) = endpoints.next();
+                if let Some(due) = due {
+                    tokio::time::sleep_until(due.min(ctx.end_time).into()).await;
+                    if Instant::now() >= ctx.end_time {
+                        break;
+                    }
+                }
+                let cmd = &commands[idx];
+                let client = &clients[pool_index[idx]];
+                let client_state = &mut client_states[state_index[idx]];
+
+                // Apply template processing（预编译模板，没有占位符时不复制）
+                template_state.next_request();
+                let template = &templates[idx];
+                let method = template.method.render(&mut template_state);
+                let url = template.url.render(&mut template_state);
+                let headers = template.headers(&mut template_state);
+                let body = template
+                    .body
+                    .as_ref()
+                    .map(|b| b.render(&mut template_state));
+                if template_state.exhausted {
+                    break;
+                }
 
-    // 参考 oha：使用物理 CPU 核心数
-    let num_physical_cpus = num_cpus::get_physical();
-    let actual_threads = if threads == 0 {
-        num_physical_cpus
-    } else {
-        threads.min(num_physical_cpus * 2)
-    };
+                // Make request
+                let start = Instant::now();
+                let (result, stream) = match stream_format {
+                    Some(format) => match client
+                        .request_stream(
+                            client_state,
+                            &method,
+                            &url,
+                            &headers,
+                            body.as_deref(),
+                            format,
+                        )
+                        .await
+                    {
+                        Ok((status, len_bytes, timing)) => (Ok((status, len_bytes)), Some(timing)),
+                        Err(e) => (Err(e), None),
+                    },
+                    None => (
+                        client
+                            .request(client_state, &method, &url, &headers, body.as_deref())
+                            .await,
+                        None,
+                    ),
+                };
+                let duration = start.elapsed();
+
+                // Record result（通过 kanal 通道发送，无锁）
+                let request_result = RequestResult {
+                    duration,
+                    status_code: result.as_ref().ok().map(|r| r.0),
+                    bytes_read: result.as_ref().ok().map(|r| r.1).unwrap_or(0),
+                    error: result.err().map(|e| e.to_string()),
+                    endpoint: if commands.len() > 1 {
+                        Some(cmd.label().to_string())
+                    } else {
+                        None
+                    },
+                    stream,
+                    source_connects: std::mem::take(&mut client_state.source_connects),
+                    ..Default::default()
+                };
+
+                let _ = ctx.tx.send(request_result);
+
+                // Rate limiting
+                if rate > 0 {
+                    let delay = Duration::from_secs_f64(1.0 / rate as f64);
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    })
+}
+
+/// 创建连接池：curl 传输层参数相同的命令共用一个连接池
+///
+/// 返回连接池和每条命令使用的连接池下标。
+pub fn connection_pools(
+    commands: &[CurlCommand],
+    config: &BenchmarkConfig,
+) -> Result<(Vec<ConnectionPool>, Vec<usize>)> {
+    let pool_size = config.worker_threads().min(20);
+    let mut transports = Vec::new();
+    let mut pool_index = Vec::with_capacity(commands.len());
+    for cmd in commands {
+        let index = match transports.iter().position(|t| t == &cmd.transport) {
+            Some(index) => index,
+            None => {
+                transports.push(cmd.transport.clone());
+                transports.len() - 1
+            }
+        };
+        pool_index.push(index);
+    }
+    let pools = transports
+        .into_iter()
+        .map(|transport| {
+            let options = ClientOptions {
+                timeout: config.timeout,
+                enable_http2: config.enable_http2,
+                bind_addresses: config.bind_addresses.clone(),
+                socket: config.socket.clone(),
+                transport,
+            };
+            ConnectionPool::new(pool_size, &options)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((pools, pool_index))
+}
+
+/// 每个 worker 为连接池中的每个来源（scheme + authority）保留一个 `ClientState`，
+/// 不同主机的命令不会共用连接
+///
+/// 返回每条命令使用的 `ClientState` 下标和 `ClientState` 的数量。
+pub fn client_state_index(commands: &[CurlCommand], pool_index: &[usize]) -> (Vec<usize>, usize) {
+    let mut origins = Vec::new();
+    let mut state_index = Vec::with_capacity(commands.len());
+    for (cmd, &pool) in commands.iter().zip(pool_index) {
+        let origin = (pool, authority_of(&cmd.url));
+        let index = match origins.iter().position(|o| o == &origin) {
+            Some(index) => index,
+            None => {
+                origins.push(origin);
+                origins.len() - 1
+            }
+        };
+        state_index.push(index);
+    }
+    (state_index, origins.len())
+}
+
+/// 按配置启动工作线程和连接任务，并收集统计数据
+///
+/// `worker` 为每个连接创建一个任务；任务在 `LocalSet` 中运行，不要求 `Send`。
+/// 如果提供了 `shared_stats`，收集线程会同时更新它，供 Live-UI 读取。
+pub fn run_connections<F, Fut>(
+    config: &BenchmarkConfig,
+    shared_stats: Option<SharedStats>,
+    worker: F,
+) -> Result<Statistics>
+where
+    F: Fn(WorkerContext) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + 'static,
+{
+    let worker = Arc::new(worker);
+    let end_time = Instant::now() + config.duration;
+    let seed = config.seed;
+    let actual_threads = config.worker_threads();
 
     // 计算每个线程的连接数
-    let connections_per_thread = (connections / actual_threads).max(1);
-
-    // 创建连接池
-    let pool_size = actual_threads.min(20);
-    let connections_per_client = (connections / pool_size).max(1);
-    let pool = Arc::new(
-        ConnectionPool::new(pool_size, timeout, connections_per_client, enable_http2)
-            .expect("Failed to create connection pool"),
-    );
+    let connections_per_thread = (config.connections / actual_threads).max(1);
 
     // 创建 kanal 通道收集统计数据（关键优化：避免 Mutex）
     let (tx, rx) = kanal::unbounded();
 
     // 使用 LocalSet 架构：每个物理线程独立运行
     let handles: Vec<_> = (0..actual_threads)
-        .map(|_| {
-            let commands = commands.clone();
+        .map(|thread_idx| {
             let tx = tx.clone();
-            let load_strategy = load_strategy.clone();
-            let template_engine = template_engine.clone();
-            let pool = pool.clone();
+            let worker = worker.clone();
 
             // 为每个线程创建独立的 tokio 运行时
             std::thread::spawn(move || {
@@ -176,69 +878,15 @@ fn run_workers(
                 let local = tokio::task::LocalSet::new();
 
                 // 在 LocalSet 中创建多个任务（每个线程处理多个连接）
-                for _ in 0..connections_per_thread {
-                    let commands = commands.clone();
-                    let tx = tx.clone();
-                    let load_strategy = load_strategy.clone();
-                    let template_engine = template_engine.clone();
-                    let client = pool.get_client().clone();
-
-                    local.spawn_local(async move {
-                        // 创建客户端状态用于连接复用
-                        let mut client_state = ClientState::new();
-                        let mut request_count = 0u64;
-
-                        while Instant::now() < end_time {
-                            // Select command based on load strategy
-                            let cmd = match load_strategy.as_str() {
-                                "round-robin" => &commands[request_count as usize % commands.len()],
-                                _ => {
-                                    // random (default)
-                                    let idx = rand::thread_rng().gen_range(0..commands.len());
-                                    &commands[idx]
-                                }
-                            };
-
-                            // Apply template processing (优化：减少字符串分配)
-                            let url = template_engine.process(&cmd.url);
-                            let body = cmd.body.as_ref().map(|b| template_engine.process(b));
-
-                            // Make request
-                            let start = Instant::now();
-                            let result = client
-                                .request(
-                                    &mut client_state,
-                                    &cmd.method,
-                                    &url,
-                                    &cmd.headers,
-                                    body.as_deref(),
-                                )
-                                .await;
-                            let duration = start.elapsed();
-
-                            // Record result（通过 kanal 通道发送，无锁）
-                            let request_result = RequestResult {
-                                duration,
-                                status_code: result.as_ref().ok().and_then(|r| Some(r.0)),
-                                bytes_read: result.as_ref().ok().map(|r| r.1).unwrap_or(0),
-                                error: result.err().map(|e| e.to_string()),
-                                endpoint: if commands.len() > 1 {
-                                    Some(cmd.url.clone())
-                                } else {
-                                    None
-                                },
-                            };
-
-                            let _ = tx.send(request_result);
-                            request_count += 1;
-
-                            // Rate limiting
-                            if rate > 0 {
-                                let delay = Duration::from_secs_f64(1.0 / rate as f64);
-                                tokio::time::sleep(delay).await;
-                            }
-                        }
-                    });
+                for i in 0..connections_per_thread {
+                    let connection_id = thread_idx * connections_per_thread + i;
+                    local.spawn_local(worker(WorkerContext {
+                        connection_id,
+                        connections: actual_threads * connections_per_thread,
+                        tx: tx.clone(),
+                        end_time,
+                        seed: connection_seed(seed, connection_id),
+                    }));
                 }
 
                 // 运行 LocalSet
@@ -251,14 +899,19 @@ fn run_workers(
     drop(tx);
 
     // 在后台线程收集统计数据
-    let collector_handle = std::thread::spawn(move || {
-        let mut stats = Statistics::new();
-        while let Ok(result) = rx.recv() {
-            stats.record(result);
+    let collector_handle = match shared_stats {
+        None => std::thread::spawn(move || {
+            let mut stats = Statistics::new();
+            while let Ok(result) = rx.recv() {
+                stats.record(result);
+            }
+            stats.finish();
+            stats
+        }),
+        Some(shared_stats) => {
+            std::thread::spawn(move || collect_with_shared_stats(rx, end_time, shared_stats))
         }
-        stats.finish();
-        stats
-    });
+    };
 
     // 等待所有工作线程完成
     for handle in handles {
@@ -271,18 +924,70 @@ fn run_workers(
     Ok(final_stats)
 }
 
-/// Run benchmark with Live-UI
-async fn run_benchmark_with_ui(
-    commands: Vec<CurlCommand>,
-    connections: usize,
-    threads: usize,
-    duration: Duration,
-    rate: u32,
+/// 超时控制（timeout 为 0 表示不限制）
+pub async fn with_timeout<T>(
     timeout: Duration,
-    load_strategy: String,
-    template_engine: Arc<TemplateEngine>,
-    enable_http2: bool,
-) -> Result<Statistics> {
+    fut: impl std::future::Future<Output = Result<T>>,
+) -> Result<T> {
+    if timeout.is_zero() {
+        return fut.await;
+    }
+    tokio::time::timeout(timeout, fut)
+        .await
+        .map_err(|_| anyhow!("Request timeout"))?
+}
+
+/// 收集统计数据并同步更新共享统计（Live-UI 使用）
+fn collect_with_shared_stats(
+    rx: kanal::Receiver<RequestResult>,
+    end_time: Instant,
+    shared_stats: SharedStats,
+) -> Statistics {
+    let mut stats = Statistics::new();
+
+    loop {
+        // Try to receive result with timeout
+        match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(result) => {
+                stats.record(result.clone());
+
+                // Update shared stats
+                {
+                    let mut shared = shared_stats.lock().unwrap();
+                    shared.record(result);
+                }
+            }
+            Err(_) => {
+                // Timeout or closed - check if all workers are done
+                if Instant::now() >= end_time || rx.is_disconnected() {
+                    // Try to drain remaining messages
+                    while let Ok(Some(result)) = rx.try_recv() {
+                        stats.record(result.clone());
+                        let mut shared = shared_stats.lock().unwrap();
+                        shared.record(result);
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    stats.finish();
+    {
+        let mut shared = shared_stats.lock().unwrap();
+        shared.finish();
+    }
+
+    stats
+}
+
+/// Run benchmark with Live-UI
+async fn run_benchmark_with_ui<F>(config: BenchmarkConfig, run: F) -> Result<Statistics>
+where
+    F: FnOnce(BenchmarkConfig, Option<SharedStats>) -> Result<Statistics> + Send + 'static,
+{
+    let duration = config.duration;
+
     // Create shared statistics for UI updates
     let shared_stats = create_shared_stats();
     let shared_stats_for_ui = shared_stats.clone();
@@ -313,7 +1018,7 @@ async fn run_benchmark_with_ui(
                     // Create snapshot from shared stats
                     let snapshot = {
                         let stats = shared_stats.lock().unwrap();
-                        StatisticsSnapshot::from_statistics(&*stats)
+                        StatisticsSnapshot::from_statistics(&stats)
                     };
 
                     if ui_tx.send(snapshot).await.is_err() {
@@ -327,21 +1032,8 @@ async fn run_benchmark_with_ui(
     };
 
     // Run workers with UI updates
-    let final_stats = tokio::task::spawn_blocking(move || {
-        run_workers_with_ui_updates(
-            commands,
-            connections,
-            threads,
-            duration,
-            rate,
-            timeout,
-            load_strategy,
-            template_engine,
-            enable_http2,
-            shared_stats,
-        )
-    })
-    .await??;
+    let final_stats =
+        tokio::task::spawn_blocking(move || run(config, Some(shared_stats))).await??;
 
     // Stop stats updater
     stats_updater_handle.abort();
@@ -359,197 +1051,220 @@ async fn run_benchmark_with_ui(
     Ok(final_stats)
 }
 
-fn run_workers_with_ui_updates(
-    commands: Vec<CurlCommand>,
-    connections: usize,
-    threads: usize,
-    duration: Duration,
-    rate: u32,
-    timeout: Duration,
-    load_strategy: String,
-    template_engine: Arc<TemplateEngine>,
-    enable_http2: bool,
-    shared_stats: SharedStats,
-) -> Result<Statistics> {
-    let commands = Arc::new(commands);
-    let end_time = Instant::now() + duration;
-
-    // 参考 oha：使用物理 CPU 核心数
-    let num_physical_cpus = num_cpus::get_physical();
-    let actual_threads = if threads == 0 {
-        num_physical_cpus
-    } else {
-        threads.min(num_physical_cpus * 2)
-    };
-
-    // 计算每个线程的连接数
-    let connections_per_thread = (connections / actual_threads).max(1);
-
-    // 创建连接池
-    let pool_size = actual_threads.min(20);
-    let connections_per_client = (connections / pool_size).max(1);
-    let pool = Arc::new(
-        ConnectionPool::new(pool_size, timeout, connections_per_client, enable_http2)
-            .expect("Failed to create connection pool"),
-    );
-
-    // 创建 kanal 通道收集统计数据（关键优化：避免 Mutex）
-    let (tx, rx) = kanal::unbounded();
-
-    // 使用 LocalSet 架构：每个物理线程独立运行
-    let handles: Vec<_> = (0..actual_threads)
-        .map(|_| {
-            let commands = commands.clone();
-            let tx = tx.clone();
-            let load_strategy = load_strategy.clone();
-            let template_engine = template_engine.clone();
-            let pool = pool.clone();
+// make_request 函数已被移除，现在直接使用 HttpClient::request 方法
 
-            // 为每个线程创建独立的 tokio 运行时
-            std::thread::spawn(move || {
-                let rt = tokio::runtime::Builder::new_current_thread()
-                    .enable_all()
-                    .build()
-                    .unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_server::{spawn_local_with, RouteConfig};
+    use clap::Parser;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_commands_on_separate_hosts() {
+        // 两个 mock server 各有一个只在本机存在的路由，发到另一台的请求会得到默认的 200 回显
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let route = |path: &str, status: u16| -> RouteConfig {
+            serde_json::from_value(serde_json::json!({"path": path, "status_code": status}))
+                .unwrap()
+        };
+        let a = rt
+            .block_on(spawn_local_with(vec![route("/a", 201)]))
+            .unwrap();
+        let b = rt
+            .block_on(spawn_local_with(vec![route("/b", 202)]))
+            .unwrap();
+
+        let mut config = BenchmarkConfig::for_test(2, Duration::from_millis(300));
+        config.load_strategy = "round-robin".to_string();
+        let commands = vec![
+            CurlCommand::new(format!("http://{}/a", a)),
+            CurlCommand::new(format!("http://{}/b", b)),
+        ];
+        let stats = run_workers(commands, &config, &TemplateEngine::new(), None).unwrap();
+
+        assert!(stats.status_codes.get(&201).is_some_and(|&n| n > 0));
+        assert!(stats.status_codes.get(&202).is_some_and(|&n| n > 0));
+        assert_eq!(stats.status_codes.len(), 2, "{:?}", stats.status_codes);
+    }
 
-                let local = tokio::task::LocalSet::new();
+    fn at(url: &str, offset_ms: u64) -> CurlCommand {
+        let mut cmd = CurlCommand::new(url.to_string());
+        cmd.offset = Some(Duration::from_millis(offset_ms));
+        cmd
+    }
 
-                // 在 LocalSet 中创建多个任务（每个线程处理多个连接）
-                for _ in 0..connections_per_thread {
-                    let commands = commands.clone();
-                    let tx = tx.clone();
-                    let load_strategy = load_strategy.clone();
-                    let template_engine = template_engine.clone();
-                    let client = pool.get_client().clone();
-
-                    local.spawn_local(async move {
-                        // 创建客户端状态用于连接复用
-                        let mut client_state = ClientState::new();
-                        let mut request_count = 0u64;
-
-                        while Instant::now() < end_time {
-                            // Select command based on load strategy
-                            let cmd = match load_strategy.as_str() {
-                                "round-robin" => &commands[request_count as usize % commands.len()],
-                                _ => {
-                                    // random (default)
-                                    let idx = rand::thread_rng().gen_range(0..commands.len());
-                                    &commands[idx]
-                                }
-                            };
-
-                            // Apply template processing (优化：减少字符串分配)
-                            let url = template_engine.process(&cmd.url);
-                            let body = cmd.body.as_ref().map(|b| template_engine.process(b));
-
-                            // Make request
-                            let start = Instant::now();
-                            let result = client
-                                .request(
-                                    &mut client_state,
-                                    &cmd.method,
-                                    &url,
-                                    &cmd.headers,
-                                    body.as_deref(),
-                                )
-                                .await;
-                            let duration = start.elapsed();
-
-                            // Record result（通过 kanal 通道发送，无锁）
-                            let request_result = RequestResult {
-                                duration,
-                                status_code: result.as_ref().ok().and_then(|r| Some(r.0)),
-                                bytes_read: result.as_ref().ok().map(|r| r.1).unwrap_or(0),
-                                error: result.err().map(|e| e.to_string()),
-                                endpoint: if commands.len() > 1 {
-                                    Some(cmd.url.clone())
-                                } else {
-                                    None
-                                },
-                            };
-
-                            let _ = tx.send(request_result);
-                            request_count += 1;
-
-                            // Rate limiting
-                            if rate > 0 {
-                                let delay = Duration::from_secs_f64(1.0 / rate as f64);
-                                tokio::time::sleep(delay).await;
-                            }
-                        }
-                    });
-                }
+    fn weighted(url: &str, name: Option<&str>, weight: u32) -> CurlCommand {
+        let mut cmd = CurlCommand::new(url.to_string());
+        cmd.name = name.map(str::to_string);
+        cmd.weight = weight;
+        cmd
+    }
 
-                // 运行 LocalSet
-                rt.block_on(local);
-            })
-        })
-        .collect();
+    #[test]
+    fn test_intended_shares() {
+        let commands = vec![
+            weighted("/a", None, 6),
+            weighted("/b", None, 3),
+            weighted("/c", None, 1),
+        ];
+        let shares = intended_shares(&commands, "weighted");
+        assert!((shares["/a"] - 0.6).abs() < 1e-9);
+        assert!((shares["/b"] - 0.3).abs() < 1e-9);
+        assert!((shares["/c"] - 0.1).abs() < 1e-9);
+
+        // 其他策略忽略权重，每条命令相同
+        let shares = intended_shares(&commands, "round-robin");
+        assert!(shares
+            .values()
+            .all(|share| (share - 1.0 / 3.0).abs() < 1e-9));
+
+        // 同名端点的占比合并
+        let commands = vec![
+            weighted("/users/1", Some("users"), 1),
+            weighted("/users/2", Some("users"), 1),
+            weighted("/items", None, 2),
+        ];
+        let shares = intended_shares(&commands, "weighted");
+        assert_eq!(shares.len(), 2);
+        assert!((shares["users"] - 0.5).abs() < 1e-9);
+        assert!((shares["/items"] - 0.5).abs() < 1e-9);
+    }
 
-    // 关闭发送端
-    drop(tx);
+    #[test]
+    fn test_endpoint_weights() {
+        let commands = vec![weighted("/a", None, 9), weighted("/b", None, 1)];
+        assert!(endpoint_weights(&commands, "random").unwrap().is_none());
+        assert!(endpoint_weights(&commands, "round-robin")
+            .unwrap()
+            .is_none());
+
+        let weights = endpoint_weights(&commands, "weighted").unwrap().unwrap();
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut counts = [0; 2];
+        for _ in 0..10_000 {
+            counts[weights.sample(&mut rng)] += 1;
+        }
+        assert!((8_700..9_300).contains(&counts[0]), "{:?}", counts);
 
-    // 在后台线程收集统计数据并更新共享统计
-    let shared_stats_clone = shared_stats.clone();
-    let collector_handle = std::thread::spawn(move || {
-        let mut stats = Statistics::new();
+        // 权重全为 0 时无法选择端点
+        let zero = vec![weighted("/a", None, 0), weighted("/b", None, 0)];
+        assert!(endpoint_weights(&zero, "weighted").is_err());
+    }
 
-        loop {
-            // Try to receive result with timeout
-            match rx.recv_timeout(Duration::from_millis(100)) {
-                Ok(result) => {
-                    stats.record(result.clone());
+    #[test]
+    fn test_replay_timing_strategy() {
+        let config = |extra: &[&str]| {
+            let mut argv = vec![
+                "quickurl",
+                "--replay-log",
+                "access.log",
+                "--target",
+                "http://x",
+            ];
+            argv.extend_from_slice(extra);
+            BenchmarkConfig::from_args(&Args::try_parse_from(argv).unwrap())
+        };
+
+        assert_eq!(config(&[]).unwrap().load_strategy, "timeline");
+        // 按时间回放时不能再指定负载策略
+        let err = config(&["--load-strategy", "round-robin"]).unwrap_err();
+        assert!(err.to_string().contains("--replay-mode timing"), "{}", err);
+        let mix = config(&["--replay-mode", "mix", "--load-strategy", "round-robin"]).unwrap();
+        assert_eq!(mix.load_strategy, "round-robin");
+        // 路径混合模式默认按请求数加权，显式指定的策略优先
+        let mix = config(&["--replay-mode", "mix"]).unwrap();
+        assert_eq!(mix.load_strategy, "weighted");
+        let mix = config(&["--replay-mode", "mix", "--load-strategy", "random"]).unwrap();
+        assert_eq!(mix.load_strategy, "random");
+    }
 
-                    // Update shared stats
-                    {
-                        let mut shared = shared_stats_clone.lock().unwrap();
-                        shared.record(result);
-                    }
-                }
-                Err(_) => {
-                    // Timeout or closed - check if all workers are done
-                    if Instant::now() >= end_time {
-                        // Try to drain remaining messages
-                        while let Ok(Some(result)) = rx.try_recv() {
-                            stats.record(result.clone());
-                            let mut shared = shared_stats_clone.lock().unwrap();
-                            shared.record(result);
-                        }
-                        break;
-                    }
+    #[test]
+    fn test_timeline_period() {
+        // 最后偏移 300ms，3 个间隔平均 100ms
+        let commands = vec![at("/a", 0), at("/b", 100), at("/c", 150), at("/d", 300)];
+        assert_eq!(timeline_period(&commands), Duration::from_millis(400));
+
+        // 单条命令或偏移全为 0 时周期至少 1ms，时间线不会停在原地
+        assert_eq!(timeline_period(&[at("/a", 0)]), Duration::from_millis(1));
+        assert_eq!(
+            timeline_period(&[at("/a", 0), at("/b", 0)]),
+            Duration::from_millis(1)
+        );
+        assert_eq!(
+            timeline_period(&[CurlCommand::new("/a".to_string())]),
+            Duration::from_millis(1)
+        );
+    }
 
-                    // Check if channel is closed
-                    if rx.is_disconnected() {
-                        // All senders closed, drain remaining messages
-                        while let Ok(Some(result)) = rx.try_recv() {
-                            stats.record(result.clone());
-                            let mut shared = shared_stats_clone.lock().unwrap();
-                            shared.record(result);
-                        }
-                        break;
-                    }
-                }
-            }
-        }
+    /// 连接 `connection_id`（共 `connections` 个）选出的前 `n` 个端点，
+    /// 回放策略的发送时间换成相对会话开始的偏移
+    fn sequence(
+        commands: &[CurlCommand],
+        load_strategy: &str,
+        seed: u64,
+        (connection_id, connections): (usize, usize),
+        n: usize,
+    ) -> Vec<(usize, Option<Duration>)> {
+        let picker = EndpointPicker::new(commands, load_strategy).unwrap();
+        let ctx = WorkerContext {
+            connection_id,
+            connections,
+            tx: kanal::unbounded().0,
+            end_time: Instant::now(),
+            seed: connection_seed(seed, connection_id),
+        };
+        let (mut endpoints, _) = picker.connection(&ctx);
+        (0..n)
+            .map(|_| {
+                let (idx, due) = endpoints.next();
+                (idx, due.map(|due| due - endpoints.session_start))
+            })
+            .collect()
+    }
 
-        stats.finish();
-        {
-            let mut shared = shared_stats_clone.lock().unwrap();
-            shared.finish();
+    #[test]
+    fn test_endpoint_sequence_seed() {
+        let commands = vec![
+            weighted("/a", None, 5),
+            weighted("/b", None, 3),
+            weighted("/c", None, 2),
+        ];
+        for strategy in ["random", "weighted"] {
+            let first = sequence(&commands, strategy, 42, (3, 8), 50);
+            assert_eq!(first, sequence(&commands, strategy, 42, (3, 8), 50));
+            assert_ne!(first, sequence(&commands, strategy, 42, (4, 8), 50));
+            assert_ne!(first, sequence(&commands, strategy, 43, (3, 8), 50));
         }
 
-        stats
-    });
-
-    // 等待所有工作线程完成
-    for handle in handles {
-        let _ = handle.join();
+        // 轮询与种子无关，各连接从自己的编号开始
+        let indexes = |connection_id| -> Vec<usize> {
+            sequence(&commands, "round-robin", 42, (connection_id, 2), 4)
+                .into_iter()
+                .map(|(idx, _)| idx)
+                .collect()
+        };
+        assert_eq!(indexes(0), [0, 1, 2, 0]);
+        assert_eq!(indexes(1), [1, 2, 0, 1]);
     }
 
-    // 等待统计收集完成
-    let final_stats = collector_handle.join().unwrap();
+    #[test]
+    fn test_timeline_sequence() {
+        let commands = vec![at("/a", 0), at("/b", 100), at("/c", 250)];
+        assert_eq!(timeline_period(&commands), Duration::from_millis(375));
 
-    Ok(final_stats)
-}
+        // 2 个连接中的第二个负责时间线上的第 1、3、5、7 个请求，第二轮晚一个周期
+        let ms = |ms| Some(Duration::from_millis(ms));
+        assert_eq!(
+            sequence(&commands, "timeline", 0, (1, 2), 4),
+            [(1, ms(100)), (0, ms(375)), (2, ms(625)), (1, ms(850))]
+        );
 
-// make_request 函数已被移除，现在直接使用 HttpClient::request 方法
+        // replay 每个连接从头按偏移回放
+        assert_eq!(
+            sequence(&commands, "replay", 0, (1, 2), 3),
+            [(0, ms(0)), (1, ms(100)), (2, ms(250))]
+        );
+    }
+}
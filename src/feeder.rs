@@ -0,0 +1,353 @@
+use anyhow::{anyhow, Result};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// 数据文件中行的取用顺序
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeedOrder {
+    /// 按文件顺序，所有连接共用一个游标
+    Sequential,
+    /// 每次随机取一行（可能重复）
+    Random,
+    /// 打乱顺序，每行只用一次
+    Unique,
+}
+
+/// 数据文件的内容
+enum Rows {
+    Csv {
+        columns: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+    Jsonl(Vec<Value>),
+}
+
+/// `--var user=csv:users.csv` / `--var user=jsonl:users.jsonl` 定义的数据源
+///
+/// `{{user.id}}` 取当前行的字段（JSONL 可以用 `a.b` 取嵌套字段），`{{user}}` 取指定的列，
+/// 没有指定列时是 CSV 的第一列或 JSONL 的整行。
+pub struct Feeder {
+    /// 文件路径（用于提示）
+    source: String,
+    rows: Rows,
+    column: Option<String>,
+    order: FeedOrder,
+    /// 用完所有行后重新开始（否则停止发送请求）
+    recycle: bool,
+    cursor: AtomicU64,
+    /// unique 模式下打乱后的行顺序
+    permutation: Vec<usize>,
+    exhausted_reported: AtomicBool,
+}
+
+impl Feeder {
+    /// 解析 `PATH[:COLUMN][:order=sequential|random|unique][:exhausted=stop|recycle]`
+    ///
+    /// `jsonl` 为 true 时按 JSON Lines 读取，否则按带表头的 CSV 读取；
+    /// `seed` 决定 unique 顺序的打乱结果。
+    pub fn load(definition: &str, jsonl: bool, seed: u64) -> Result<Self> {
+        let mut parts = definition.split(':');
+        let source = parts.next().unwrap_or_default().to_string();
+        let mut column = None;
+        let mut order = FeedOrder::Sequential;
+        let mut recycle = None;
+        for part in parts {
+            match part.split_once('=') {
+                Some(("order", "sequential")) => order = FeedOrder::Sequential,
+                Some(("order", "random")) => order = FeedOrder::Random,
+                Some(("order", "unique")) => order = FeedOrder::Unique,
+                Some(("exhausted", "stop")) => recycle = Some(false),
+                Some(("exhausted", "recycle")) => recycle = Some(true),
+                Some(_) => return Err(anyhow!("Unknown data file option: {}", part)),
+                None => column = Some(part.to_string()),
+            }
+        }
+
+        let content = std::fs::read_to_string(&source)
+            .map_err(|e| anyhow!("Failed to read data file {}: {}", source, e))?;
+        let rows = if jsonl {
+            parse_jsonl(&content)?
+        } else {
+            let mut records = parse_csv(&content)?.into_iter();
+            let columns = records
+                .next()
+                .ok_or_else(|| anyhow!("CSV file {} is empty", source))?;
+            Rows::Csv {
+                columns,
+                rows: records.collect(),
+            }
+        };
+        let len = match &rows {
+            Rows::Csv { rows, .. } => rows.len(),
+            Rows::Jsonl(rows) => rows.len(),
+        };
+        if len == 0 {
+            return Err(anyhow!("Data file {} has no rows", source));
+        }
+        if let (Rows::Csv { columns, .. }, Some(column)) = (&rows, &column) {
+            if !columns.contains(column) {
+                return Err(anyhow!("CSV file {} has no column `{}`", source, column));
+            }
+        }
+
+        let mut permutation: Vec<usize> = (0..len).collect();
+        if order == FeedOrder::Unique {
+            permutation.shuffle(&mut StdRng::seed_from_u64(seed));
+        }
+
+        Ok(Self {
+            source,
+            rows,
+            column,
+            order,
+            // 默认 unique 用完即停止，sequential 循环使用
+            recycle: recycle.unwrap_or(order != FeedOrder::Unique),
+            cursor: AtomicU64::new(0),
+            permutation,
+            exhausted_reported: AtomicBool::new(false),
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.permutation.len()
+    }
+
+    /// 取下一行的下标（random 顺序使用调用方连接的 `rng`）；所有行都已用完且不循环时返回 None
+    pub fn next_row(&self, rng: &mut impl Rng) -> Option<usize> {
+        if self.order == FeedOrder::Random {
+            return Some(rng.gen_range(0..self.len()));
+        }
+        let n = self.cursor.fetch_add(1, Ordering::Relaxed) as usize;
+        if n >= self.len() && !self.recycle {
+            if !self.exhausted_reported.swap(true, Ordering::Relaxed) {
+                eprintln!(
+                    "Warning: all {} rows of {} have been used; connections stop sending requests",
+                    self.len(),
+                    self.source
+                );
+            }
+            return None;
+        }
+        Some(self.permutation[n % self.len()])
+    }
+
+    /// 某一行的字段；`field` 为 None 时取默认列
+    pub fn field(&self, row: usize, field: Option<&str>) -> Option<String> {
+        let field = field.or(self.column.as_deref());
+        match &self.rows {
+            Rows::Csv { columns, rows } => {
+                let index = match field {
+                    Some(field) => columns.iter().position(|c| c == field)?,
+                    None => 0,
+                };
+                Some(rows[row].get(index).cloned().unwrap_or_default())
+            }
+            Rows::Jsonl(rows) => {
+                let value = match field {
+                    Some(field) => field
+                        .split('.')
+                        .try_fold(&rows[row], |value, key| match key.parse::<usize>() {
+                            Ok(index) if value.is_array() => value.get(index),
+                            _ => value.get(key),
+                        })?,
+                    None => &rows[row],
+                };
+                Some(match value {
+                    Value::String(text) => text.clone(),
+                    Value::Null => String::new(),
+                    other => other.to_string(),
+                })
+            }
+        }
+    }
+}
+
+/// 解析 CSV（RFC 4180：双引号包围的字段可以包含逗号、换行和 `""`），跳过空行
+fn parse_csv(content: &str) -> Result<Vec<Vec<String>>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut chars = content.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => quoted = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                line += 1;
+                record.push(std::mem::take(&mut field));
+                if !(record.len() == 1 && record[0].is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                }
+                record.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(anyhow!("CSV line {}: unterminated quoted field", line));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    if let Some(width) = records.first().map(Vec::len) {
+        if let Some(index) = records.iter().position(|r| r.len() > width) {
+            return Err(anyhow!(
+                "CSV record {} has more fields than the header",
+                index + 1
+            ));
+        }
+    }
+    Ok(records)
+}
+
+/// 每个非空行是一个 JSON 对象
+fn parse_jsonl(content: &str) -> Result<Rows> {
+    let rows = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| anyhow!("JSONL line {}: {}", index + 1, e))
+        })
+        .collect::<Result<Vec<Value>>>()?;
+    Ok(Rows::Jsonl(rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp(name: &str, content: &str) -> String {
+        let path = std::env::temp_dir().join(format!("quickurl-{}-{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let records =
+            parse_csv("id,name\r\n1,\"Doe, \"\"J\"\"\"\n\n2,\"multi\nline\"\n3,\n").unwrap();
+        assert_eq!(
+            records,
+            [
+                vec!["id", "name"],
+                vec!["1", "Doe, \"J\""],
+                vec!["2", "multi\nline"],
+                vec!["3", ""],
+            ]
+        );
+        assert!(parse_csv("a\n\"open").is_err());
+        assert!(parse_csv("a,b\n1,2,3\n").is_err());
+    }
+
+    #[test]
+    fn test_csv_orders() {
+        let path = write_temp("users.csv", "id,token\n1,a\n2,b\n3,c\n");
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let sequential = Feeder::load(&format!("{}:token", path), false, 7).unwrap();
+        let values: Vec<_> = (0..4)
+            .map(|_| {
+                let row = sequential.next_row(&mut rng).unwrap();
+                (
+                    sequential.field(row, None).unwrap(),
+                    sequential.field(row, Some("id")).unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            values,
+            [("a", "1"), ("b", "2"), ("c", "3"), ("a", "1")]
+                .map(|(t, i)| (t.to_string(), i.to_string()))
+        );
+
+        let unique = Feeder::load(&format!("{}:order=unique", path), false, 7).unwrap();
+        let mut ids: Vec<_> = (0..3)
+            .map(|_| {
+                unique
+                    .field(unique.next_row(&mut rng).unwrap(), None)
+                    .unwrap()
+            })
+            .collect();
+        ids.sort();
+        assert_eq!(ids, ["1", "2", "3"]);
+        assert_eq!(unique.next_row(&mut rng), None);
+
+        let recycled = Feeder::load(
+            &format!("{}:order=unique:exhausted=recycle", path),
+            false,
+            7,
+        )
+        .unwrap();
+        assert!((0..7).all(|_| recycled.next_row(&mut rng).is_some()));
+
+        // 相同的种子得到相同的 unique 和 random 顺序
+        let shuffled = |seed| {
+            let feeder = Feeder::load(&format!("{}:order=unique", path), false, seed).unwrap();
+            (0..3)
+                .map(|_| feeder.next_row(&mut StdRng::seed_from_u64(0)).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(shuffled(42), shuffled(42));
+        let random = Feeder::load(&format!("{}:order=random", path), false, 7).unwrap();
+        let draw = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..10)
+                .map(|_| random.next_row(&mut rng).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(draw(3), draw(3));
+
+        assert!(Feeder::load(&format!("{}:missing", path), false, 7).is_err());
+        assert!(Feeder::load(&format!("{}:order=backwards", path), false, 7).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_jsonl_fields() {
+        let path = write_temp(
+            "users.jsonl",
+            "{\"id\":7,\"name\":\"ann\",\"address\":{\"city\":\"Oslo\"},\"tags\":[\"x\",\"y\"]}\n\n",
+        );
+        let feeder = Feeder::load(&path, true, 7).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        let row = feeder.next_row(&mut rng).unwrap();
+        assert_eq!(feeder.field(row, Some("id")).as_deref(), Some("7"));
+        assert_eq!(
+            feeder.field(row, Some("address.city")).as_deref(),
+            Some("Oslo")
+        );
+        assert_eq!(feeder.field(row, Some("tags.1")).as_deref(), Some("y"));
+        assert_eq!(feeder.field(row, Some("missing")), None);
+        assert!(feeder.field(row, None).unwrap().starts_with("{\"id\":7"));
+
+        std::fs::write(&path, "{\"id\":1}\nnot json\n").unwrap();
+        let err = Feeder::load(&path, true, 7).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+    }
+}
@@ -0,0 +1,477 @@
+use crate::curl_parser::CurlCommand;
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+
+/// 解析一条 `fetch(url, options)` 调用（devtools "Copy as fetch"）
+pub fn parse_fetch(text: &str) -> Result<CurlCommand> {
+    let mut calls = parse_fetch_calls(text)?;
+    match calls.len() {
+        0 => Err(anyhow!("No fetch() call found")),
+        1 => calls.remove(0).1,
+        n => Err(anyhow!(
+            "Expected a single fetch() call but found {} (use --parse-curl-file for several)",
+            n
+        )),
+    }
+}
+
+/// 解析文本中的所有 `fetch(...)` 调用（"Copy all as fetch"），返回所在行号和结果
+///
+/// 参数按 JavaScript 字面量解析：单双引号和反引号字符串、不带引号的键、
+/// 末尾逗号、`null` / `undefined`，以及 `JSON.stringify({...})` 形式的请求体。
+pub fn parse_fetch_calls(text: &str) -> Result<Vec<(usize, Result<CurlCommand>)>> {
+    let mut parser = Parser::new(text);
+    let mut calls = Vec::new();
+    while let Some(start) = parser.find_call("fetch") {
+        let line = parser.line_at(start);
+        let result = parser.call_arguments().and_then(to_command);
+        // 解析失败时跳过这次调用继续寻找下一个
+        if result.is_err() {
+            parser.pos = start + "fetch".len();
+        }
+        calls.push((line, result));
+    }
+    Ok(calls)
+}
+
+fn to_command(args: Vec<Value>) -> Result<CurlCommand> {
+    let mut args = args.into_iter();
+    let url = match args.next() {
+        Some(Value::String(url)) => url,
+        _ => return Err(anyhow!("fetch() needs a URL string as first argument")),
+    };
+    let options = match args.next() {
+        Some(Value::Object(options)) => options,
+        None | Some(Value::Null) => Map::new(),
+        Some(_) => return Err(anyhow!("fetch() options must be an object literal")),
+    };
+
+    let mut cmd = CurlCommand::new(url);
+    if let Some(method) = options.get("method").and_then(Value::as_str) {
+        cmd.method = method.to_uppercase();
+    }
+
+    // headers 可以是对象，也可以是 `[[name, value], ...]`
+    match options.get("headers") {
+        Some(Value::Object(headers)) => {
+            for (name, value) in headers {
+                cmd.headers.append(name.as_str(), text(value));
+            }
+        }
+        Some(Value::Array(pairs)) => {
+            for pair in pairs {
+                if let Some([name, value]) = pair.as_array().map(Vec::as_slice) {
+                    cmd.headers.append(text(name), text(value));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    // Chrome 把 Referer 放在 referrer 选项里
+    if let Some(referrer) = options.get("referrer").and_then(Value::as_str) {
+        if referrer.starts_with("http") {
+            cmd.headers.set_default("Referer", referrer);
+        }
+    }
+
+    match options.get("body") {
+        Some(Value::String(body)) => {
+            // 与浏览器一样，字符串请求体默认是 text/plain
+            cmd.headers
+                .set_default("Content-Type", "text/plain;charset=UTF-8");
+            cmd.body = Some(body.clone());
+        }
+        None | Some(Value::Null) => {}
+        Some(_) => return Err(anyhow!("fetch() body must be a string")),
+    }
+
+    Ok(cmd)
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// JavaScript 字面量的简单解析器
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(text: &str) -> Self {
+        Self {
+            chars: text.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn line_at(&self, pos: usize) -> usize {
+        self.chars[..pos].iter().filter(|&&c| c == '\n').count() + 1
+    }
+
+    /// 找到下一个 `name(` 调用，位置停在 `(` 之后，返回调用名的起始位置
+    ///
+    /// 跳过字符串和注释，避免匹配到请求体中的文本。
+    fn find_call(&mut self, name: &str) -> Option<usize> {
+        let name: Vec<char> = name.chars().collect();
+        while self.pos < self.chars.len() {
+            match self.chars[self.pos] {
+                '"' | '\'' | '`' => {
+                    if self.string().is_err() {
+                        return None;
+                    }
+                    continue;
+                }
+                '/' if self.peek(1) == Some('/') || self.peek(1) == Some('*') => {
+                    self.skip_ws();
+                    continue;
+                }
+                _ => {}
+            }
+            let start = self.pos;
+            let is_word_start = start == 0 || !is_ident(self.chars[start - 1]);
+            if is_word_start && self.chars[start..].starts_with(&name) {
+                self.pos += name.len();
+                self.skip_ws();
+                if self.eat('(') {
+                    return Some(start);
+                }
+                continue;
+            }
+            self.pos += 1;
+        }
+        None
+    }
+
+    /// 解析调用的参数列表，位置在 `(` 之后
+    fn call_arguments(&mut self) -> Result<Vec<Value>> {
+        let mut args = Vec::new();
+        loop {
+            self.skip_ws();
+            if self.eat(')') {
+                return Ok(args);
+            }
+            args.push(self.value()?);
+            self.skip_ws();
+            if !self.eat(',') && self.peek(0) != Some(')') {
+                return Err(anyhow!("expected `,` or `)` at {}", self.describe()));
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        self.skip_ws();
+        match self.peek(0) {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"' | '\'' | '`') => Ok(Value::String(self.string()?)),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) if is_ident(c) => {
+                let word = self.identifier();
+                match word.as_str() {
+                    "null" | "undefined" => Ok(Value::Null),
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "JSON.stringify" => {
+                        self.skip_ws();
+                        if !self.eat('(') {
+                            return Err(anyhow!("expected `(` after JSON.stringify"));
+                        }
+                        let args = self.call_arguments()?;
+                        let value = args.into_iter().next().unwrap_or(Value::Null);
+                        Ok(Value::String(value.to_string()))
+                    }
+                    _ => Err(anyhow!("unsupported expression `{}`", word)),
+                }
+            }
+            _ => Err(anyhow!("unexpected input at {}", self.describe())),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value> {
+        self.pos += 1;
+        let mut map = Map::new();
+        loop {
+            self.skip_ws();
+            if self.eat('}') {
+                return Ok(Value::Object(map));
+            }
+            let key = match self.peek(0) {
+                Some('"' | '\'' | '`') => self.string()?,
+                Some(c) if is_ident(c) || c.is_ascii_digit() => self.identifier(),
+                _ => return Err(anyhow!("expected object key at {}", self.describe())),
+            };
+            self.skip_ws();
+            if !self.eat(':') {
+                return Err(anyhow!("expected `:` after key `{}`", key));
+            }
+            let value = self.value()?;
+            map.insert(key, value);
+            self.skip_ws();
+            if !self.eat(',') && self.peek(0) != Some('}') {
+                return Err(anyhow!("expected `,` or `}}` at {}", self.describe()));
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_ws();
+            if self.eat(']') {
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_ws();
+            if !self.eat(',') && self.peek(0) != Some(']') {
+                return Err(anyhow!("expected `,` or `]` at {}", self.describe()));
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value> {
+        let start = self.pos;
+        while self
+            .peek(0)
+            .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.'))
+        {
+            self.pos += 1;
+        }
+        let literal: String = self.chars[start..self.pos].iter().collect();
+        serde_json::from_str(&literal).map_err(|_| anyhow!("invalid number `{}`", literal))
+    }
+
+    /// 标识符，允许 `.` 连接的成员访问（如 `JSON.stringify`）
+    fn identifier(&mut self) -> String {
+        let start = self.pos;
+        while self.peek(0).is_some_and(|c| is_ident(c) || c == '.') {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// 字符串字面量，处理 JavaScript 的转义序列；模板字符串不支持 `${}` 插值
+    fn string(&mut self) -> Result<String> {
+        let quote = self.chars[self.pos];
+        self.pos += 1;
+        let mut result = String::new();
+        while let Some(c) = self.peek(0) {
+            self.pos += 1;
+            match c {
+                c if c == quote => return Ok(result),
+                '$' if quote == '`' && self.peek(0) == Some('{') => {
+                    return Err(anyhow!("template literal interpolation is not supported"));
+                }
+                '\\' => {
+                    let Some(escaped) = self.peek(0) else { break };
+                    self.pos += 1;
+                    match escaped {
+                        'n' => result.push('\n'),
+                        'r' => result.push('\r'),
+                        't' => result.push('\t'),
+                        'b' => result.push('\u{8}'),
+                        'f' => result.push('\u{c}'),
+                        'v' => result.push('\u{b}'),
+                        '0' => result.push('\0'),
+                        'x' => result.push(self.hex_char(2)?),
+                        'u' if self.peek(0) == Some('{') => {
+                            self.pos += 1;
+                            let end = self.chars[self.pos..]
+                                .iter()
+                                .position(|&c| c == '}')
+                                .ok_or_else(|| anyhow!("unterminated \\u{{...}} escape"))?;
+                            let hex: String = self.chars[self.pos..self.pos + end].iter().collect();
+                            self.pos += end + 1;
+                            result.push(code_point(&hex)?);
+                        }
+                        'u' => {
+                            let unit = self.hex_unit()?;
+                            // UTF-16 代理对
+                            if (0xD800..0xDC00).contains(&unit)
+                                && self.peek(0) == Some('\\')
+                                && self.peek(1) == Some('u')
+                            {
+                                self.pos += 2;
+                                let low = self.hex_unit()?;
+                                let c = char::decode_utf16([unit, low])
+                                    .next()
+                                    .and_then(|r| r.ok())
+                                    .unwrap_or('\u{FFFD}');
+                                result.push(c);
+                            } else {
+                                result.push(char::from_u32(unit as u32).unwrap_or('\u{FFFD}'));
+                            }
+                        }
+                        // 行尾的反斜杠是续行
+                        '\n' => {}
+                        other => result.push(other),
+                    }
+                }
+                other => result.push(other),
+            }
+        }
+        Err(anyhow!("unterminated string literal"))
+    }
+
+    fn hex_unit(&mut self) -> Result<u16> {
+        let hex: String = self
+            .chars
+            .get(self.pos..self.pos + 4)
+            .unwrap_or(&[])
+            .iter()
+            .collect();
+        self.pos += 4;
+        u16::from_str_radix(&hex, 16).map_err(|_| anyhow!("invalid \\u escape `{}`", hex))
+    }
+
+    fn hex_char(&mut self, len: usize) -> Result<char> {
+        let hex: String = self
+            .chars
+            .get(self.pos..self.pos + len)
+            .unwrap_or(&[])
+            .iter()
+            .collect();
+        self.pos += len;
+        code_point(&hex)
+    }
+
+    fn skip_ws(&mut self) {
+        while let Some(c) = self.peek(0) {
+            if c.is_whitespace() {
+                self.pos += 1;
+            } else if c == '/' && self.peek(1) == Some('/') {
+                while self.peek(0).is_some_and(|c| c != '\n') {
+                    self.pos += 1;
+                }
+            } else if c == '/' && self.peek(1) == Some('*') {
+                self.pos += 2;
+                while self.pos < self.chars.len()
+                    && !(self.peek(0) == Some('*') && self.peek(1) == Some('/'))
+                {
+                    self.pos += 1;
+                }
+                self.pos = (self.pos + 2).min(self.chars.len());
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&self, ahead: usize) -> Option<char> {
+        self.chars.get(self.pos + ahead).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek(0) == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self.peek(0) {
+            Some(c) => format!("line {} (`{}`)", self.line_at(self.pos), c),
+            None => "end of input".to_string(),
+        }
+    }
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+fn code_point(hex: &str) -> Result<char> {
+    u32::from_str_radix(hex, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or_else(|| anyhow!("invalid escape `{}`", hex))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chrome_copy_as_fetch() {
+        let snippet = r#"fetch("https://api.example.com/v1/items?q=a%20b", {
+  "headers": {
+    "accept": "application/json",
+    "content-type": "application/json",
+    "x-note": "café 😀",
+  },
+  "referrer": "https://app.example.com/",
+  "referrerPolicy": "strict-origin-when-cross-origin",
+  "body": "{\"name\":\"it's \\\"fine\\\"\"}",
+  "method": "POST",
+  "mode": "cors",
+  "credentials": "include"
+});"#;
+        let cmd = parse_fetch(snippet).unwrap();
+        assert_eq!(cmd.url, "https://api.example.com/v1/items?q=a%20b");
+        assert_eq!(cmd.method, "POST");
+        assert_eq!(cmd.body.as_deref(), Some(r#"{"name":"it's \"fine\""}"#));
+        assert_eq!(
+            cmd.headers.iter().collect::<Vec<_>>(),
+            [
+                ("accept", "application/json"),
+                ("content-type", "application/json"),
+                ("x-note", "café 😀"),
+                ("Referer", "https://app.example.com/"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multiple_calls_and_js_literals() {
+        let snippets = r#"// Copy all as fetch
+await fetch('https://example.com/', {method: 'GET', headers: [['Accept', 'text/html']], body: null});
+fetch(`https://example.com/log`, {
+  method: "post",
+  /* JSON body */
+  body: JSON.stringify({ id: 1, tags: ["a", 'b'], ok: true, }),
+});
+fetch(someVariable);
+fetch("https://example.com/text", {"body": "plain", "method": "PUT"});
+"#;
+        let calls = parse_fetch_calls(snippets).unwrap();
+        assert_eq!(calls.len(), 4);
+        assert_eq!(calls[0].0, 2);
+
+        let get = calls[0].1.as_ref().unwrap();
+        assert_eq!(get.method, "GET");
+        assert_eq!(get.headers.get("accept"), Some("text/html"));
+        assert_eq!(get.body, None);
+
+        let post = calls[1].1.as_ref().unwrap();
+        assert_eq!(post.url, "https://example.com/log");
+        assert_eq!(post.method, "POST");
+        assert_eq!(
+            post.body.as_deref(),
+            Some(r#"{"id":1,"tags":["a","b"],"ok":true}"#)
+        );
+
+        assert!(calls[2].1.is_err());
+        assert_eq!(calls[2].0, 8);
+
+        let put = calls[3].1.as_ref().unwrap();
+        assert_eq!(
+            put.headers.get("Content-Type"),
+            Some("text/plain;charset=UTF-8")
+        );
+    }
+
+    #[test]
+    fn test_single_call_required() {
+        assert!(parse_fetch("fetch('http://a/'); fetch('http://b/');").is_err());
+        assert!(parse_fetch("console.log('fetch(\"http://a/\")')").is_err());
+        assert!(parse_fetch("fetch(`http://a/${id}`)").is_err());
+    }
+}
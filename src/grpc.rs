@@ -0,0 +1,805 @@
+use crate::curl_parser::CurlCommand;
+use crate::engine::{
+    run_connections, with_timeout, BenchmarkConfig, EndpointPicker, ReconnectBackoff,
+};
+use crate::headers::Headers;
+use crate::stats::{RequestResult, SharedStats, Statistics};
+use crate::template::{CommandTemplate, TemplateEngine};
+use anyhow::{anyhow, Result};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use http_body_util::{BodyExt, Full};
+use hyper::client::conn::http2;
+use hyper::{Request, Uri};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::rt::TokioExecutor;
+use prost::Message;
+use prost_reflect::prost_types::FileDescriptorProto;
+use prost_reflect::{DescriptorPool, DynamicMessage, MethodDescriptor};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+type HttpsConn = HttpsConnector<HttpConnector>;
+
+/// 未指定请求体时发送的 JSON
+const DEFAULT_REQUEST: &str = "{}";
+
+/// gRPC 状态码名称
+pub fn status_name(code: u32) -> &'static str {
+    match code {
+        0 => "OK",
+        1 => "CANCELLED",
+        2 => "UNKNOWN",
+        3 => "INVALID_ARGUMENT",
+        4 => "DEADLINE_EXCEEDED",
+        5 => "NOT_FOUND",
+        6 => "ALREADY_EXISTS",
+        7 => "PERMISSION_DENIED",
+        8 => "RESOURCE_EXHAUSTED",
+        9 => "FAILED_PRECONDITION",
+        10 => "ABORTED",
+        11 => "OUT_OF_RANGE",
+        12 => "UNIMPLEMENTED",
+        13 => "INTERNAL",
+        14 => "UNAVAILABLE",
+        15 => "DATA_LOSS",
+        16 => "UNAUTHENTICATED",
+        _ => "UNKNOWN_STATUS",
+    }
+}
+
+/// 一次 unary 调用的结果
+#[derive(Debug)]
+pub struct UnaryResponse {
+    pub status: u32,
+    pub message: Option<String>,
+    pub body: Bytes,
+}
+
+/// HTTP/2 上的 gRPC 客户端（每个 worker 持有一个连接）
+pub struct GrpcClient {
+    connector: HttpsConn,
+}
+
+impl GrpcClient {
+    pub fn new() -> Result<Self> {
+        // 初始化 rustls crypto provider（只需要初始化一次）
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        // 只协商 HTTP/2；http:// 目标使用 h2c（prior knowledge）
+        let connector = HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .map_err(|e| anyhow!("Failed to load native certs: {}", e))?
+            .https_or_http()
+            .enable_http2()
+            .build();
+
+        Ok(Self { connector })
+    }
+
+    /// 建立 HTTP/2 连接
+    pub async fn connect(&self, uri: &Uri) -> Result<http2::SendRequest<Full<Bytes>>> {
+        use tower::Service;
+        let mut connector = self.connector.clone();
+        let stream = connector
+            .call(uri.clone())
+            .await
+            .map_err(|e| anyhow!("Failed to connect: {}", e))?;
+
+        let (send_request, conn) = http2::handshake(TokioExecutor::new(), stream)
+            .await
+            .map_err(|e| anyhow!("Failed to handshake: {}", e))?;
+
+        // 在后台运行连接
+        tokio::spawn(async move {
+            if let Err(_e) = conn.await {
+                // 连接错误，静默处理
+            }
+        });
+
+        Ok(send_request)
+    }
+
+    /// 发送一次 unary 调用
+    ///
+    /// # 参数
+    /// - `sender`: HTTP/2 连接
+    /// - `uri`: 完整的调用地址（包含 `/package.Service/Method` 路径）
+    /// - `metadata`: 附加的 gRPC metadata
+    /// - `message`: 已编码的 protobuf 请求消息
+    pub async fn unary(
+        sender: &mut http2::SendRequest<Full<Bytes>>,
+        uri: Uri,
+        metadata: &Headers,
+        message: &[u8],
+    ) -> Result<UnaryResponse> {
+        let mut request = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/grpc")
+            .header("te", "trailers");
+        for (key, value) in metadata {
+            request = request.header(key.to_lowercase(), value);
+        }
+        let request = request
+            .body(Full::new(encode_frame(message)))
+            .map_err(|e| anyhow!("Failed to build request: {}", e))?;
+
+        sender
+            .ready()
+            .await
+            .map_err(|e| anyhow!("Connection closed: {}", e))?;
+        let response = sender
+            .send_request(request)
+            .await
+            .map_err(|e| anyhow!("Request failed: {}", e))?;
+
+        let (parts, body) = response.into_parts();
+        if !parts.status.is_success() {
+            return Err(anyhow!("HTTP status {}", parts.status.as_u16()));
+        }
+
+        // Trailers-Only 响应会把状态放在响应头中
+        let header_status = grpc_status(&parts.headers);
+        let collected = body
+            .collect()
+            .await
+            .map_err(|e| anyhow!("Failed to read response: {}", e))?;
+        let trailer_status = collected.trailers().and_then(grpc_status);
+        let (status, message) = trailer_status
+            .or(header_status)
+            .ok_or_else(|| anyhow!("Response missing grpc-status"))?;
+
+        Ok(UnaryResponse {
+            status,
+            message,
+            body: collected.to_bytes(),
+        })
+    }
+}
+
+/// 读取 grpc-status 与 grpc-message
+fn grpc_status(headers: &hyper::HeaderMap) -> Option<(u32, Option<String>)> {
+    let status = headers.get("grpc-status")?.to_str().ok()?.parse().ok()?;
+    let message = headers
+        .get("grpc-message")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    Some((status, message))
+}
+
+/// 按 gRPC 长度前缀格式封装消息（1 字节压缩标志 + 4 字节大端长度）
+pub fn encode_frame(message: &[u8]) -> Bytes {
+    let mut buf = BytesMut::with_capacity(5 + message.len());
+    buf.put_u8(0);
+    buf.put_u32(message.len() as u32);
+    buf.put_slice(message);
+    buf.freeze()
+}
+
+/// 解析长度前缀格式的消息序列
+pub fn decode_frames(mut data: Bytes) -> Result<Vec<Bytes>> {
+    let mut messages = Vec::new();
+    while data.has_remaining() {
+        if data.remaining() < 5 {
+            return Err(anyhow!("Truncated gRPC frame header"));
+        }
+        let compressed = data.get_u8();
+        let len = data.get_u32() as usize;
+        if compressed != 0 {
+            return Err(anyhow!("Compressed gRPC messages are not supported"));
+        }
+        if data.remaining() < len {
+            return Err(anyhow!("Truncated gRPC message"));
+        }
+        messages.push(data.split_to(len));
+    }
+    Ok(messages)
+}
+
+/// 拆分 `package.Service/Method` 或 `package.Service.Method`
+fn split_method_name(name: &str) -> Result<(&str, &str)> {
+    let name = name.trim_start_matches('/');
+    let pos = name.rfind('/').or_else(|| name.rfind('.')).ok_or_else(|| {
+        anyhow!(
+            "Invalid gRPC method {}, expected package.Service/Method",
+            name
+        )
+    })?;
+    Ok((&name[..pos], &name[pos + 1..]))
+}
+
+/// 从描述符文件或服务端反射中解析要调用的方法
+///
+/// # 参数
+/// - `method`: `package.Service/Method`
+/// - `descriptor`: `.proto` 源文件，或 `protoc --include_imports --descriptor_set_out` 生成的描述符集；
+///   未指定时使用服务端反射
+/// - `target`: 目标地址（反射时使用）
+pub async fn resolve_method(
+    method: &str,
+    descriptor: Option<&Path>,
+    target: &str,
+) -> Result<MethodDescriptor> {
+    let (service_name, method_name) = split_method_name(method)?;
+
+    let pool = match descriptor {
+        Some(path) => load_descriptor_set(path)?,
+        None => reflect_service(target, service_name).await?,
+    };
+
+    let service = pool
+        .get_service_by_name(service_name)
+        .ok_or_else(|| anyhow!("Service {} not found in descriptors", service_name))?;
+    let method = service
+        .methods()
+        .find(|m| m.name() == method_name)
+        .ok_or_else(|| anyhow!("Method {} not found in {}", method_name, service_name))?;
+
+    if method.is_client_streaming() || method.is_server_streaming() {
+        return Err(anyhow!(
+            "{} is a streaming method, only unary calls are supported",
+            method.full_name()
+        ));
+    }
+
+    Ok(method)
+}
+
+fn load_descriptor_set(path: &Path) -> Result<DescriptorPool> {
+    if path.extension().and_then(|s| s.to_str()) == Some("proto") {
+        return parse_proto_file(path);
+    }
+
+    let bytes = std::fs::read(path)?;
+    DescriptorPool::decode(bytes.as_slice())
+        .map_err(|e| anyhow!("Invalid descriptor set {}: {}", path.display(), e))
+}
+
+/// 解析 `.proto` 源文件（纯 Rust 解析器，不需要 protoc）
+///
+/// import 相对于文件所在目录查找，`google/protobuf/*.proto` 等标准类型已内置。
+fn parse_proto_file(path: &Path) -> Result<DescriptorPool> {
+    use protobuf::Message as _;
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let parsed = protobuf_parse::Parser::new()
+        .pure()
+        .include(dir)
+        .input(path)
+        .parse_and_typecheck()
+        .map_err(|e| anyhow!("Failed to parse {}: {:#}", path.display(), e))?;
+
+    // 依赖在前，连同 import 的文件一起交给描述符池
+    let mut set = protobuf::descriptor::FileDescriptorSet::new();
+    set.file = parsed.file_descriptors;
+    let bytes = set.write_to_bytes()?;
+    DescriptorPool::decode(bytes.as_slice())
+        .map_err(|e| anyhow!("Invalid descriptors in {}: {}", path.display(), e))
+}
+
+/// grpc.reflection ServerReflectionRequest（oneof 字段按 optional 编码）
+#[derive(Clone, PartialEq, prost::Message)]
+struct ReflectionRequest {
+    #[prost(string, tag = "1")]
+    host: String,
+    #[prost(string, optional, tag = "3")]
+    file_by_filename: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    file_containing_symbol: Option<String>,
+}
+
+/// grpc.reflection ServerReflectionResponse
+#[derive(Clone, PartialEq, prost::Message)]
+struct ReflectionResponse {
+    #[prost(message, optional, tag = "4")]
+    file_descriptor_response: Option<FileDescriptorResponse>,
+    #[prost(message, optional, tag = "7")]
+    error_response: Option<ReflectionError>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct FileDescriptorResponse {
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    file_descriptor_proto: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ReflectionError {
+    #[prost(int32, tag = "1")]
+    error_code: i32,
+    #[prost(string, tag = "2")]
+    error_message: String,
+}
+
+/// 通过服务端反射获取服务及其依赖的描述符
+async fn reflect_service(target: &str, service: &str) -> Result<DescriptorPool> {
+    let base: Uri = target.parse().map_err(|e| anyhow!("Invalid URL: {}", e))?;
+    let client = GrpcClient::new()?;
+    let mut sender = client.connect(&base).await?;
+
+    let mut resolver = DescriptorResolver::default();
+    let mut pending = vec![ReflectionRequest {
+        file_containing_symbol: Some(service.to_string()),
+        ..Default::default()
+    }];
+    while let Some(request) = pending.pop() {
+        let reply = reflection_call(&mut sender, &base, &request).await?;
+        pending.extend(resolver.add(&request, reply)?);
+    }
+
+    let mut pool = DescriptorPool::new();
+    pool.add_file_descriptor_protos(resolver.files.into_values())
+        .map_err(|e| anyhow!("Invalid descriptors from server reflection: {}", e))?;
+    Ok(pool)
+}
+
+/// 反射得到的文件及已经请求过的依赖
+#[derive(Default)]
+struct DescriptorResolver {
+    files: HashMap<String, FileDescriptorProto>,
+    requested: HashSet<String>,
+}
+
+impl DescriptorResolver {
+    /// 记录一次反射请求的回复，返回还需要请求的依赖文件（每个文件只请求一次）
+    fn add(
+        &mut self,
+        request: &ReflectionRequest,
+        reply: Vec<FileDescriptorProto>,
+    ) -> Result<Vec<ReflectionRequest>> {
+        for file in reply {
+            self.files.entry(file.name().to_string()).or_insert(file);
+        }
+        if let Some(name) = &request.file_by_filename {
+            if !self.files.contains_key(name) {
+                return Err(anyhow!("Server reflection did not return {}", name));
+            }
+        }
+
+        let mut missing: Vec<String> = self
+            .files
+            .values()
+            .flat_map(|f| f.dependency.iter())
+            .filter(|dep| !self.files.contains_key(*dep))
+            .cloned()
+            .collect();
+        missing.sort();
+        missing.retain(|dep| self.requested.insert(dep.clone()));
+        Ok(missing
+            .into_iter()
+            .map(|dep| ReflectionRequest {
+                file_by_filename: Some(dep),
+                ..Default::default()
+            })
+            .collect())
+    }
+}
+
+/// 发送一次反射请求，优先使用 v1，服务端未实现时回退到 v1alpha
+async fn reflection_call(
+    sender: &mut http2::SendRequest<Full<Bytes>>,
+    base: &Uri,
+    request: &ReflectionRequest,
+) -> Result<Vec<FileDescriptorProto>> {
+    let payload = request.encode_to_vec();
+
+    for version in ["v1", "v1alpha"] {
+        let path = format!(
+            "/grpc.reflection.{}.ServerReflection/ServerReflectionInfo",
+            version
+        );
+        let response =
+            GrpcClient::unary(sender, method_uri(base, &path)?, &Headers::new(), &payload).await?;
+        if response.status == 12 {
+            continue; // UNIMPLEMENTED
+        }
+        if response.status != 0 {
+            return Err(anyhow!(
+                "Server reflection failed: {} {}",
+                status_name(response.status),
+                response.message.unwrap_or_default()
+            ));
+        }
+
+        let mut files = Vec::new();
+        for frame in decode_frames(response.body)? {
+            let message = ReflectionResponse::decode(frame)?;
+            if let Some(err) = message.error_response {
+                return Err(anyhow!("Server reflection error: {}", err.error_message));
+            }
+            if let Some(fds) = message.file_descriptor_response {
+                for bytes in fds.file_descriptor_proto {
+                    files.push(FileDescriptorProto::decode(bytes.as_slice())?);
+                }
+            }
+        }
+        return Ok(files);
+    }
+
+    Err(anyhow!("Server does not support gRPC reflection"))
+}
+
+/// 拼接调用地址：目标的 scheme/authority + 方法路径
+fn method_uri(base: &Uri, path: &str) -> Result<Uri> {
+    let mut parts = base.clone().into_parts();
+    parts.path_and_query = Some(path.parse()?);
+    Uri::from_parts(parts).map_err(|e| anyhow!("Invalid URL: {}", e))
+}
+
+/// gRPC 压测：每个 worker 复用一个 HTTP/2 连接，循环发送 unary 调用
+///
+/// # 参数
+/// - `commands`: 目标端点（URL、metadata 与 JSON 请求体）
+/// - `method`: 已解析的方法描述符
+/// - `config`: 压测参数
+/// - `template_engine`: 模板引擎（作用于 JSON 请求体和 metadata）
+/// - `shared_stats`: Live-UI 使用的共享统计
+pub fn run_workers(
+    commands: Vec<CurlCommand>,
+    method: MethodDescriptor,
+    config: &BenchmarkConfig,
+    template_engine: &TemplateEngine,
+    shared_stats: Option<SharedStats>,
+) -> Result<Statistics> {
+    let client = Arc::new(GrpcClient::new()?);
+    // 模板在启动时编译一次
+    let templates: Arc<Vec<CommandTemplate>> = Arc::new(
+        commands
+            .iter()
+            .map(|cmd| template_engine.compile_command(cmd))
+            .collect::<Result<_>>()?,
+    );
+    let path = format!("/{}/{}", method.parent_service().full_name(), method.name());
+    let targets = commands
+        .iter()
+        .map(|cmd| -> Result<Uri> { method_uri(&cmd.url.parse()?, &path) })
+        .collect::<Result<Vec<_>>>()?;
+    let targets = Arc::new(targets);
+    let commands = Arc::new(commands);
+    let picker = EndpointPicker::new(&commands, &config.load_strategy)?;
+    let rate = config.rate;
+    let timeout = config.timeout;
+
+    run_connections(config, shared_stats, move |ctx| {
+        let client = client.clone();
+        let targets = targets.clone();
+        let commands = commands.clone();
+        let method = method.clone();
+        let templates = templates.clone();
+        let (mut endpoints, mut template_state) = picker.connection(&ctx);
+
+        async move {
+            let mut connection: Option<(usize, http2::SendRequest<Full<Bytes>>)> = None;
+            let mut backoff = ReconnectBackoff::default();
+
+            while Instant::now() < ctx.end_time {
+                // 回放策略在 run_benchmark 中已被拒绝，不会有发送时间
+                let (idx, _) = endpoints.next();
+                let cmd = &commands[idx];
+                let uri = &targets[idx];
+                let endpoint = if commands.len() > 1 {
+                    Some(cmd.label().to_string())
+                } else {
+                    None
+                };
+
+                // 目标不同或连接已断开时重连
+                let reusable = matches!(&connection, Some((conn_idx, sender))
+                    if targets[*conn_idx].authority() == uri.authority() && !sender.is_closed());
+                if !reusable {
+                    let start = Instant::now();
+                    match with_timeout(timeout, client.connect(uri)).await {
+                        Ok(sender) => {
+                            backoff.reset();
+                            connection = Some((idx, sender));
+                        }
+                        Err(e) => {
+                            connection = None;
+                            let _ = ctx.tx.send(RequestResult {
+                                duration: start.elapsed(),
+                                error: Some(e.to_string()),
+                                endpoint,
+                                connect_error: true,
+                                ..Default::default()
+                            });
+                            backoff.wait(ctx.end_time).await;
+                            continue;
+                        }
+                    }
+                }
+                let Some((_, sender)) = connection.as_mut() else {
+                    continue;
+                };
+
+                // JSON 请求体 → protobuf
+                template_state.next_request();
+                let template = &templates[idx];
+                let json = match &template.body {
+                    Some(body) => body.render(&mut template_state),
+                    None => DEFAULT_REQUEST.into(),
+                };
+                let headers = template.headers(&mut template_state);
+                if template_state.exhausted {
+                    break;
+                }
+                let message = match encode_request(&method, &json) {
+                    Ok(message) => message,
+                    Err(e) => {
+                        // 请求没有发出，不计入延迟；模板多半每次都渲染出同样的错误，同样退避
+                        let _ = ctx.tx.send(RequestResult {
+                            error: Some(e.to_string()),
+                            endpoint,
+                            not_sent: true,
+                            ..Default::default()
+                        });
+                        backoff.wait(ctx.end_time).await;
+                        continue;
+                    }
+                };
+                backoff.reset();
+
+                let start = Instant::now();
+                let result = with_timeout(
+                    timeout,
+                    GrpcClient::unary(sender, uri.clone(), &headers, &message),
+                )
+                .await;
+                let duration = start.elapsed();
+
+                let _ = ctx.tx.send(RequestResult {
+                    duration,
+                    grpc_status: result.as_ref().ok().map(|r| r.status),
+                    bytes_read: result.as_ref().map(|r| r.body.len()).unwrap_or(0),
+                    error: result.err().map(|e| e.to_string()),
+                    endpoint,
+                    ..Default::default()
+                });
+
+                // Rate limiting
+                if rate > 0 {
+                    let delay = Duration::from_secs_f64(1.0 / rate as f64);
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    })
+}
+
+/// 按方法的输入类型把 JSON 编码为 protobuf
+fn encode_request(method: &MethodDescriptor, json: &str) -> Result<Vec<u8>> {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let message = DynamicMessage::deserialize(method.input(), &mut deserializer)
+        .map_err(|e| anyhow!("Invalid request for {}: {}", method.input().full_name(), e))?;
+    deserializer.end()?;
+    Ok(message.encode_to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost_reflect::prost_types::{
+        field_descriptor_proto, DescriptorProto, FieldDescriptorProto, MethodDescriptorProto,
+        ServiceDescriptorProto,
+    };
+
+    fn echo_pool() -> DescriptorPool {
+        let file = FileDescriptorProto {
+            name: Some("echo.proto".to_string()),
+            package: Some("test".to_string()),
+            message_type: vec![DescriptorProto {
+                name: Some("EchoRequest".to_string()),
+                field: vec![FieldDescriptorProto {
+                    name: Some("text".to_string()),
+                    number: Some(1),
+                    r#type: Some(field_descriptor_proto::Type::String as i32),
+                    label: Some(field_descriptor_proto::Label::Optional as i32),
+                    json_name: Some("text".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            service: vec![ServiceDescriptorProto {
+                name: Some("Echo".to_string()),
+                method: vec![MethodDescriptorProto {
+                    name: Some("Say".to_string()),
+                    input_type: Some(".test.EchoRequest".to_string()),
+                    output_type: Some(".test.EchoRequest".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            syntax: Some("proto3".to_string()),
+            ..Default::default()
+        };
+
+        let mut pool = DescriptorPool::new();
+        pool.add_file_descriptor_proto(file).unwrap();
+        pool
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        let frame = encode_frame(b"hello");
+        assert_eq!(&frame[..5], &[0, 0, 0, 0, 5]);
+        let messages = decode_frames(frame).unwrap();
+        assert_eq!(messages, vec![Bytes::from_static(b"hello")]);
+    }
+
+    #[test]
+    fn test_reflection_dependencies() {
+        let file = |name: &str, deps: &[&str]| FileDescriptorProto {
+            name: Some(name.to_string()),
+            dependency: deps.iter().map(|d| d.to_string()).collect(),
+            ..Default::default()
+        };
+        let by_name = |name: &str| ReflectionRequest {
+            file_by_filename: Some(name.to_string()),
+            ..Default::default()
+        };
+        let symbol = ReflectionRequest {
+            file_containing_symbol: Some("pkg.Service".to_string()),
+            ..Default::default()
+        };
+
+        // a 依赖 b 和 c，b 也依赖 c：c 只请求一次，已随回复返回的文件不再请求
+        let mut resolver = DescriptorResolver::default();
+        let next = resolver
+            .add(
+                &symbol,
+                vec![
+                    file("a.proto", &["b.proto", "c.proto", "d.proto"]),
+                    file("d.proto", &[]),
+                ],
+            )
+            .unwrap();
+        assert_eq!(next, [by_name("b.proto"), by_name("c.proto")]);
+        let next = resolver
+            .add(&by_name("b.proto"), vec![file("b.proto", &["c.proto"])])
+            .unwrap();
+        assert!(next.is_empty());
+        let next = resolver
+            .add(&by_name("c.proto"), vec![file("c.proto", &[])])
+            .unwrap();
+        assert!(next.is_empty());
+        assert_eq!(resolver.files.len(), 4);
+
+        // 服务端没有返回请求的文件（空回复或其他文件）时报错，而不是一直重试
+        let mut resolver = DescriptorResolver::default();
+        resolver
+            .add(&symbol, vec![file("a.proto", &["b.proto"])])
+            .unwrap();
+        assert!(resolver.add(&by_name("b.proto"), Vec::new()).is_err());
+        assert!(resolver
+            .add(&by_name("b.proto"), vec![file("other.proto", &[])])
+            .is_err());
+    }
+
+    #[test]
+    fn test_split_method_name() {
+        assert_eq!(
+            split_method_name("test.Echo/Say").unwrap(),
+            ("test.Echo", "Say")
+        );
+        assert_eq!(
+            split_method_name("/test.Echo.Say").unwrap(),
+            ("test.Echo", "Say")
+        );
+        assert!(split_method_name("Say").is_err());
+    }
+
+    #[test]
+    fn test_proto_source() {
+        let dir = std::env::temp_dir().join(format!("quickurl-proto-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("common")).unwrap();
+        std::fs::write(
+            dir.join("common/types.proto"),
+            "syntax = \"proto3\";\npackage common;\nmessage Page { int32 size = 1; }\n",
+        )
+        .unwrap();
+        let api = dir.join("api.proto");
+        std::fs::write(
+            &api,
+            r#"syntax = "proto3";
+package shop.v1;
+import "common/types.proto";
+import "google/protobuf/empty.proto";
+message ListRequest { common.Page page = 1; repeated string tags = 2; }
+message ListReply { map<string, int64> counts = 1; }
+service Items {
+  rpc List(ListRequest) returns (ListReply);
+  rpc Ping(google.protobuf.Empty) returns (google.protobuf.Empty);
+}
+"#,
+        )
+        .unwrap();
+
+        let pool = load_descriptor_set(&api).unwrap();
+        let service = pool.get_service_by_name("shop.v1.Items").unwrap();
+        let list = service.methods().find(|m| m.name() == "List").unwrap();
+        assert_eq!(list.input().full_name(), "shop.v1.ListRequest");
+        let message = encode_request(&list, r#"{"page": {"size": 10}, "tags": ["a"]}"#).unwrap();
+        assert!(!message.is_empty());
+
+        std::fs::write(dir.join("broken.proto"), "syntax = \"proto3\";\nmessage {").unwrap();
+        let err = load_descriptor_set(&dir.join("broken.proto")).unwrap_err();
+        assert!(err.to_string().starts_with("Failed to parse"), "{}", err);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_failure_backoff() {
+        let pool = echo_pool();
+        let method = pool
+            .get_service_by_name("test.Echo")
+            .unwrap()
+            .methods()
+            .next()
+            .unwrap();
+        let config = BenchmarkConfig::for_test(1, Duration::from_millis(500));
+        let run = |url: String, body: &str| {
+            let mut cmd = CurlCommand::new(url);
+            cmd.body = Some(body.to_string());
+            run_workers(
+                vec![cmd],
+                method.clone(),
+                &config,
+                &TemplateEngine::new(),
+                None,
+            )
+            .unwrap()
+        };
+
+        // 拒绝连接的端口：重连按间隔退避
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let stats = run(format!("http://127.0.0.1:{}", port), "{}");
+        assert!(stats.connect_errors > 0);
+        assert!(stats.connect_errors <= 10, "{}", stats.connect_errors);
+
+        // 无法编码的请求没有发出：同样退避，也不产生延迟样本
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let addr = rt.block_on(crate::mock_server::spawn_local()).unwrap();
+        let stats = run(format!("http://{}", addr), "{not json");
+        assert!(stats.failed_requests > 0);
+        assert!(stats.failed_requests <= 10, "{}", stats.failed_requests);
+        assert_eq!(stats.latency_histogram.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unary_against_mock_echo() {
+        let addr = crate::mock_server::spawn_local().await.unwrap();
+        let pool = echo_pool();
+        let method = pool
+            .get_service_by_name("test.Echo")
+            .unwrap()
+            .methods()
+            .next()
+            .unwrap();
+        let message = encode_request(&method, r#"{"text": "hi"}"#).unwrap();
+
+        let base: Uri = format!("http://{}", addr).parse().unwrap();
+        let client = GrpcClient::new().unwrap();
+        let mut sender = client.connect(&base).await.unwrap();
+        let uri = method_uri(&base, "/test.Echo/Say").unwrap();
+        let response = GrpcClient::unary(&mut sender, uri, &Headers::new(), &message)
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, 0);
+        let frames = decode_frames(response.body).unwrap();
+        let reply = DynamicMessage::decode(method.output(), frames[0].clone()).unwrap();
+        assert_eq!(
+            reply.get_field_by_name("text").unwrap().as_str(),
+            Some("hi")
+        );
+    }
+}
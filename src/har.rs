@@ -0,0 +1,350 @@
+use crate::curl_parser::CurlCommand;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, FixedOffset};
+use serde::Deserialize;
+use std::path::Path;
+use std::time::Duration;
+
+/// 浏览器自己管理、不应原样重放的请求头
+const SKIPPED_HEADERS: &[&str] = &[
+    "content-length",
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+    "te",
+];
+
+/// HAR 条目过滤条件，空列表表示不过滤
+#[derive(Debug, Clone, Default)]
+pub struct HarFilter {
+    /// 主机名，支持 `*.example.com` 通配
+    pub hosts: Vec<String>,
+    /// HTTP 方法（不区分大小写）
+    pub methods: Vec<String>,
+    /// 响应的 MIME 类型，支持 `image/*` 通配
+    pub mime_types: Vec<String>,
+}
+
+impl HarFilter {
+    fn matches(&self, host: &str, method: &str, mime_type: &str) -> bool {
+        let host_ok = self.hosts.is_empty()
+            || self
+                .hosts
+                .iter()
+                .any(|pattern| match pattern.strip_prefix("*.") {
+                    Some(suffix) => {
+                        host.eq_ignore_ascii_case(suffix)
+                            || host
+                                .to_ascii_lowercase()
+                                .ends_with(&format!(".{}", suffix.to_ascii_lowercase()))
+                    }
+                    None => host.eq_ignore_ascii_case(pattern),
+                });
+        let method_ok =
+            self.methods.is_empty() || self.methods.iter().any(|m| m.eq_ignore_ascii_case(method));
+
+        // 只比较 MIME 本体，忽略 `; charset=...` 等参数
+        let essence = mime_type.split(';').next().unwrap_or("").trim();
+        let mime_ok = self.mime_types.is_empty()
+            || self
+                .mime_types
+                .iter()
+                .any(|pattern| match pattern.strip_suffix("/*") {
+                    Some(main) => essence
+                        .split('/')
+                        .next()
+                        .is_some_and(|m| m.eq_ignore_ascii_case(main)),
+                    None => essence.eq_ignore_ascii_case(pattern),
+                });
+
+        host_ok && method_ok && mime_ok
+    }
+}
+
+#[derive(Deserialize)]
+struct Har {
+    log: HarLog,
+}
+
+#[derive(Deserialize)]
+struct HarLog {
+    entries: Vec<HarEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarEntry {
+    started_date_time: String,
+    request: HarRequest,
+    #[serde(default)]
+    response: Option<HarResponse>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarRequest {
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: Vec<HarParam>,
+    #[serde(default)]
+    post_data: Option<HarPostData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarPostData {
+    #[serde(default)]
+    mime_type: String,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    params: Vec<HarParam>,
+}
+
+#[derive(Deserialize)]
+struct HarParam {
+    name: String,
+    #[serde(default)]
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct HarResponse {
+    #[serde(default)]
+    content: Option<HarContent>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarContent {
+    #[serde(default)]
+    mime_type: String,
+}
+
+/// 从 HAR 文件导入请求
+///
+/// 条目按开始时间排序，每条命令的 `offset` 是相对第一个导入请求的开始时间，
+/// 供 `--load-strategy replay` 按原始节奏重放。
+pub fn parse_har_file(path: &Path, filter: &HarFilter) -> Result<Vec<CurlCommand>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read HAR file {}: {}", path.display(), e))?;
+    parse_har(&content, filter).map_err(|e| anyhow!("Invalid HAR file {}: {}", path.display(), e))
+}
+
+fn parse_har(content: &str, filter: &HarFilter) -> Result<Vec<CurlCommand>> {
+    let har: Har = serde_json::from_str(content)?;
+    let total = har.log.entries.len();
+
+    let mut entries = Vec::new();
+    for entry in har.log.entries {
+        let started = DateTime::parse_from_rfc3339(&entry.started_date_time)
+            .map_err(|e| anyhow!("Invalid startedDateTime {}: {}", entry.started_date_time, e))?;
+        // 跳过 data:、blob:、ws: 等非 HTTP 请求
+        let Ok(url) = url::Url::parse(&entry.request.url) else {
+            continue;
+        };
+        if !matches!(url.scheme(), "http" | "https") {
+            continue;
+        }
+        let mime_type = entry
+            .response
+            .as_ref()
+            .and_then(|response| response.content.as_ref())
+            .map(|content| content.mime_type.as_str())
+            .unwrap_or("");
+        if filter.matches(
+            url.host_str().unwrap_or(""),
+            &entry.request.method,
+            mime_type,
+        ) {
+            entries.push((started, entry.request));
+        }
+    }
+
+    if entries.is_empty() {
+        return Err(anyhow!(
+            "No HAR entries match the filters ({} entries in file)",
+            total
+        ));
+    }
+
+    entries.sort_by_key(|(started, _)| *started);
+    let first = entries[0].0;
+    Ok(entries
+        .into_iter()
+        .map(|(started, request)| to_command(request, offset_between(first, started)))
+        .collect())
+}
+
+fn offset_between(first: DateTime<FixedOffset>, started: DateTime<FixedOffset>) -> Duration {
+    (started - first).to_std().unwrap_or_default()
+}
+
+fn to_command(request: HarRequest, offset: Duration) -> CurlCommand {
+    let mut cmd = CurlCommand::new(request.url);
+    cmd.method = request.method.to_uppercase();
+    cmd.offset = Some(offset);
+
+    for header in request.headers {
+        // HTTP/2 的伪首部（:authority 等）由客户端生成
+        if header.name.starts_with(':')
+            || SKIPPED_HEADERS
+                .iter()
+                .any(|skipped| header.name.eq_ignore_ascii_case(skipped))
+        {
+            continue;
+        }
+        cmd.headers.append(header.name, header.value);
+    }
+
+    if let Some(post_data) = request.post_data {
+        let body = match post_data.text {
+            Some(text) => text,
+            None => url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(post_data.params.iter().map(|p| (&p.name, &p.value)))
+                .finish(),
+        };
+        if !post_data.mime_type.is_empty() {
+            cmd.headers.set_default("Content-Type", post_data.mime_type);
+        }
+        cmd.body = Some(body);
+    }
+
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SESSION: &str = r#"{
+      "log": {
+        "version": "1.2",
+        "creator": {"name": "WebInspector", "version": "537.36"},
+        "entries": [
+          {
+            "startedDateTime": "2024-05-01T10:00:00.250Z",
+            "request": {
+              "method": "POST",
+              "url": "https://api.example.com/v1/login",
+              "httpVersion": "http/2.0",
+              "headers": [
+                {"name": ":authority", "value": "api.example.com"},
+                {"name": "content-type", "value": "application/json"},
+                {"name": "content-length", "value": "17"},
+                {"name": "cookie", "value": "a=1"},
+                {"name": "cookie", "value": "b=2"}
+              ],
+              "postData": {"mimeType": "application/json", "text": "{\"user\":\"demo\"}"}
+            },
+            "response": {"status": 200, "content": {"mimeType": "application/json; charset=utf-8"}}
+          },
+          {
+            "startedDateTime": "2024-05-01T12:00:00.000+02:00",
+            "request": {"method": "GET", "url": "https://www.example.com/", "headers": []},
+            "response": {"status": 200, "content": {"mimeType": "text/html"}}
+          },
+          {
+            "startedDateTime": "2024-05-01T10:00:01.000Z",
+            "request": {"method": "GET", "url": "https://cdn.example.com/logo.png", "headers": []},
+            "response": {"status": 200, "content": {"mimeType": "image/png"}}
+          },
+          {
+            "startedDateTime": "2024-05-01T10:00:01.500Z",
+            "request": {
+              "method": "post",
+              "url": "https://www.example.com/search",
+              "headers": [],
+              "postData": {
+                "mimeType": "application/x-www-form-urlencoded",
+                "params": [{"name": "q", "value": "a b"}, {"name": "page", "value": "2"}]
+              }
+            },
+            "response": {"status": 200, "content": {"mimeType": "text/html"}}
+          },
+          {
+            "startedDateTime": "2024-05-01T10:00:02.000Z",
+            "request": {"method": "GET", "url": "data:image/png;base64,AAAA", "headers": []}
+          }
+        ]
+      }
+    }"#;
+
+    #[test]
+    fn test_parse_session_in_time_order() {
+        let commands = parse_har(SESSION, &HarFilter::default()).unwrap();
+        let urls: Vec<_> = commands.iter().map(|c| c.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://www.example.com/",
+                "https://api.example.com/v1/login",
+                "https://cdn.example.com/logo.png",
+                "https://www.example.com/search",
+            ]
+        );
+        let offsets: Vec<_> = commands.iter().map(|c| c.offset.unwrap()).collect();
+        assert_eq!(
+            offsets,
+            [
+                Duration::ZERO,
+                Duration::from_millis(250),
+                Duration::from_secs(1),
+                Duration::from_millis(1500),
+            ]
+        );
+
+        let login = &commands[1];
+        assert_eq!(login.method, "POST");
+        assert_eq!(login.body.as_deref(), Some(r#"{"user":"demo"}"#));
+        let headers: Vec<_> = login.headers.iter().collect();
+        assert_eq!(
+            headers,
+            [
+                ("content-type", "application/json"),
+                ("cookie", "a=1"),
+                ("cookie", "b=2"),
+            ]
+        );
+
+        let search = &commands[3];
+        assert_eq!(search.method, "POST");
+        assert_eq!(search.body.as_deref(), Some("q=a+b&page=2"));
+        assert_eq!(
+            search.headers.get("Content-Type"),
+            Some("application/x-www-form-urlencoded")
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        let filter = HarFilter {
+            hosts: vec!["*.example.com".to_string()],
+            methods: vec!["GET".to_string()],
+            mime_types: vec!["image/*".to_string(), "text/html".to_string()],
+        };
+        let commands = parse_har(SESSION, &filter).unwrap();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[1].url, "https://cdn.example.com/logo.png");
+
+        let filter = HarFilter {
+            hosts: vec!["api.example.com".to_string()],
+            mime_types: vec!["application/json".to_string()],
+            ..Default::default()
+        };
+        let commands = parse_har(SESSION, &filter).unwrap();
+        assert_eq!(commands.len(), 1);
+        // 过滤后以第一个导入的请求为起点
+        assert_eq!(commands[0].offset, Some(Duration::ZERO));
+
+        let filter = HarFilter {
+            methods: vec!["DELETE".to_string()],
+            ..Default::default()
+        };
+        let err = parse_har(SESSION, &filter).unwrap_err().to_string();
+        assert!(err.contains("5 entries"), "{}", err);
+    }
+}
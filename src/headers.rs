@@ -0,0 +1,223 @@
+/// 有序、可重复的请求头列表
+///
+/// 保留请求头的书写顺序、大小写和重复项（多个 `Cookie`、`X-Forwarded-For` 等），
+/// 查找时不区分大小写。被 `-H 'Name:'` 删除的名称单独记录，
+/// 用来阻止客户端自动添加的请求头（如 Host、默认的 Content-Type）。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Headers {
+    entries: Vec<(String, String)>,
+    removed: Vec<String>,
+}
+
+impl Headers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 追加一个请求头，不影响已有的同名请求头
+    pub fn append(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        self.removed
+            .retain(|removed| !removed.eq_ignore_ascii_case(&name));
+        self.entries.push((name, value.into()));
+    }
+
+    /// 在指定位置插入请求头（超出末尾时追加）
+    pub fn insert(&mut self, index: usize, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        self.removed
+            .retain(|removed| !removed.eq_ignore_ascii_case(&name));
+        let index = index.min(self.entries.len());
+        self.entries.insert(index, (name, value.into()));
+    }
+
+    /// 替换所有同名请求头
+    pub fn set(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        self.entries
+            .retain(|(key, _)| !key.eq_ignore_ascii_case(&name));
+        self.append(name, value);
+    }
+
+    /// 只在没有同名请求头、且没有被显式删除时添加
+    pub fn set_default(&mut self, name: &str, value: impl Into<String>) {
+        if !self.contains(name) && !self.is_removed(name) {
+            self.append(name, value);
+        }
+    }
+
+    /// 删除所有同名请求头，并记录为已删除
+    pub fn remove(&mut self, name: &str) {
+        self.entries
+            .retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+        if !self.is_removed(name) {
+            self.removed.push(name.to_string());
+        }
+    }
+
+    /// 按 curl `-H` 的语法应用一行请求头
+    ///
+    /// - `Name: value`：追加请求头
+    /// - `Name:`：删除请求头（包括客户端自动添加的）
+    /// - `Name;`：发送值为空的请求头
+    ///
+    /// 其他格式与 curl 一样被忽略。
+    pub fn apply_curl(&mut self, line: &str) {
+        if let Some((name, value)) = line.split_once(':') {
+            let name = name.trim();
+            let value = value.trim();
+            if name.is_empty() {
+                return;
+            }
+            if value.is_empty() {
+                self.remove(name);
+            } else {
+                self.append(name, value);
+            }
+        } else if let Some(name) = line.trim().strip_suffix(';') {
+            let name = name.trim();
+            if !name.is_empty() {
+                self.append(name, "");
+            }
+        }
+    }
+
+    /// 第一个同名请求头的值
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// 所有同名请求头的值，按书写顺序
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries
+            .iter()
+            .filter(move |(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// 是否被 `-H 'Name:'` 删除
+    pub fn is_removed(&self, name: &str) -> bool {
+        self.removed
+            .iter()
+            .any(|removed| removed.eq_ignore_ascii_case(name))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 转换每个请求头的值，名称、顺序和删除记录不变
+    pub fn map_values(&self, mut f: impl FnMut(&str) -> String) -> Self {
+        Self {
+            entries: self
+                .entries
+                .iter()
+                .map(|(key, value)| (key.clone(), f(value)))
+                .collect(),
+            removed: self.removed.clone(),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
+
+impl<'a> IntoIterator for &'a Headers {
+    type Item = (&'a str, &'a str);
+    type IntoIter = std::iter::Map<
+        std::slice::Iter<'a, (String, String)>,
+        fn(&'a (String, String)) -> (&'a str, &'a str),
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Headers {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut headers = Headers::new();
+        for (name, value) in iter {
+            headers.append(name, value);
+        }
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicates_and_order_preserved() {
+        let mut headers = Headers::new();
+        headers.apply_curl("Cookie: a=1");
+        headers.apply_curl("accept: text/html");
+        headers.apply_curl("Cookie: b=2");
+        headers.apply_curl("Accept: application/json");
+
+        let pairs: Vec<_> = headers.iter().collect();
+        assert_eq!(
+            pairs,
+            [
+                ("Cookie", "a=1"),
+                ("accept", "text/html"),
+                ("Cookie", "b=2"),
+                ("Accept", "application/json"),
+            ]
+        );
+        assert_eq!(headers.get("COOKIE"), Some("a=1"));
+        assert_eq!(
+            headers.get_all("accept").collect::<Vec<_>>(),
+            ["text/html", "application/json"]
+        );
+    }
+
+    #[test]
+    fn test_curl_remove_and_empty_syntax() {
+        let mut headers = Headers::new();
+        headers.apply_curl("X-Trace: 1");
+        headers.apply_curl("x-trace:");
+        headers.apply_curl("Host:   ");
+        headers.apply_curl("X-Empty;");
+        headers.apply_curl("not a header");
+
+        assert!(!headers.contains("X-Trace"));
+        assert!(headers.is_removed("host"));
+        assert_eq!(headers.get("x-empty"), Some(""));
+        assert_eq!(headers.len(), 1);
+
+        // 被删除的请求头不会再以默认值加回来，显式追加则恢复
+        headers.set_default("Host", "example.com");
+        assert!(!headers.contains("Host"));
+        headers.append("Host", "example.com");
+        assert!(!headers.is_removed("Host"));
+    }
+
+    #[test]
+    fn test_set_and_set_default() {
+        let mut headers: Headers = [("User-Agent", "a"), ("user-agent", "b")]
+            .into_iter()
+            .collect();
+        headers.set_default("User-Agent", "curl");
+        assert_eq!(headers.len(), 2);
+        headers.set("USER-AGENT", "c");
+        assert_eq!(headers.iter().collect::<Vec<_>>(), [("USER-AGENT", "c")]);
+    }
+}
@@ -1,25 +1,67 @@
+use crate::curl_parser::{HttpVersion, Transport};
+use crate::headers::Headers;
+use crate::net::{ConnectRoute, SocketOptions, SourceAddresses, SourceConnect, TcpConnector};
+use crate::streaming::{StreamFormat, StreamRecorder, StreamTiming};
+use crate::tls;
 use anyhow::{anyhow, Result};
 use bytes::Bytes;
 use http_body_util::{BodyExt, Full};
-use hyper::client::conn::http1;
-use hyper::{Method, Request, Uri};
+use hyper::body::Incoming;
+use hyper::client::conn::{http1, http2};
+use hyper::http::uri::{Authority, Scheme};
+use hyper::{Method, Request, Response, Uri};
 use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
-use hyper_util::client::legacy::connect::HttpConnector;
-use std::collections::HashMap;
+use hyper_util::client::legacy::connect::Connection;
+use hyper_util::rt::TokioExecutor;
+use std::borrow::Cow;
+use std::net::IpAddr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-type HttpsConn = HttpsConnector<HttpConnector>;
+type HttpsConn = HttpsConnector<TcpConnector>;
+
+/// 已建立的连接（HTTP/1.1 或 HTTP/2）
+pub enum Sender {
+    Http1(http1::SendRequest<Full<Bytes>>),
+    Http2(http2::SendRequest<Full<Bytes>>),
+}
+
+impl Sender {
+    async fn ready(&mut self) -> hyper::Result<()> {
+        match self {
+            Self::Http1(sender) => sender.ready().await,
+            Self::Http2(sender) => sender.ready().await,
+        }
+    }
+
+    async fn send_request(
+        &mut self,
+        request: Request<Full<Bytes>>,
+    ) -> hyper::Result<Response<Incoming>> {
+        match self {
+            Self::Http1(sender) => sender.send_request(request).await,
+            Self::Http2(sender) => sender.send_request(request).await,
+        }
+    }
+}
 
 /// 客户端状态 - 每个 worker 维护一个，用于连接复用
 pub struct ClientState {
-    /// HTTP/1.1 连接的 SendRequest（保持连接复用）
-    pub send_request: Option<http1::SendRequest<Full<Bytes>>>,
+    /// 当前连接的 SendRequest（保持连接复用）
+    pub send_request: Option<Sender>,
+    /// `send_request` 连接的 scheme 和 authority，只有同一来源的请求才复用这个连接
+    origin: Option<(Scheme, Authority)>,
+    /// 绑定源地址时，本次请求过程中的建连记录（由调用方取走）
+    pub source_connects: Vec<SourceConnect>,
 }
 
 impl ClientState {
     pub fn new() -> Self {
-        Self { send_request: None }
+        Self {
+            send_request: None,
+            origin: None,
+            source_connects: Vec::new(),
+        }
     }
 }
 
@@ -29,50 +71,131 @@ impl Default for ClientState {
     }
 }
 
+/// HttpClient 的创建参数
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// 请求超时时间（curl --max-time 优先）
+    pub timeout: Duration,
+    /// 是否启用 HTTP/2（curl --http1.1/--http2 优先）
+    pub enable_http2: bool,
+    /// 新连接轮流绑定的本地源地址
+    pub bind_addresses: Vec<IpAddr>,
+    /// 套接字参数
+    pub socket: SocketOptions,
+    /// curl 命令的传输层参数
+    pub transport: Transport,
+}
+
+/// 保存下来的响应内容（场景步骤从中提取变量）
+#[derive(Debug, Default)]
+pub struct CapturedResponse {
+    /// 最终响应的响应头
+    pub headers: Vec<(String, String)>,
+    /// 包括重定向在内所有响应的 Set-Cookie
+    pub cookies: Vec<String>,
+    /// 最终响应的响应体
+    pub body: Vec<u8>,
+}
+
+impl CapturedResponse {
+    /// 第一个同名响应头的值（名称不区分大小写）
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// 一次请求/响应的结果
+struct Exchange {
+    status: u16,
+    len_bytes: usize,
+    /// 3xx 响应的 Location
+    location: Option<String>,
+}
+
 /// 高性能 HTTP 客户端，基于 hyper 1.4
 /// 参考 oha 的优化策略：
-/// 1. 直接管理 HTTP/1.1 连接，避免连接池开销
+/// 1. 直接管理连接，避免连接池开销
 /// 2. 流式处理响应体，不完整缓存
 /// 3. 连接复用，减少握手开销
 pub struct HttpClient {
-    connector: Arc<HttpsConn>,
+    /// 每个源地址一个连接器；未绑定源地址时只有一个默认连接器
+    connectors: Vec<HttpsConn>,
+    sources: Option<Arc<SourceAddresses>>,
     timeout: Duration,
+    /// 明文连接直接使用 HTTP/2（h2c，--http2-prior-knowledge）
+    h2c: bool,
+    /// 跟随重定向的最大次数（-L）
+    follow_redirects: Option<usize>,
+    /// 重定向到其他主机时保留 Authorization 和 Cookie（--location-trusted）
+    location_trusted: bool,
+    /// 明文请求经 HTTP 代理转发（请求行使用完整 URL）
+    forward_proxy: bool,
+    /// 经 HTTP 代理发送明文请求时附带的 Proxy-Authorization
+    proxy_authorization: Option<String>,
 }
 
 impl HttpClient {
     /// 创建新的 HTTP 客户端
     ///
     /// # 参数
-    /// - `timeout`: 请求超时时间
-    /// - `pool_size`: 连接池大小
-    /// - `enable_http2`: 是否启用 HTTP/2（默认只使用 HTTP/1.1）
-    pub fn new(timeout: Duration, _pool_size: usize, enable_http2: bool) -> Result<Self> {
-        // 初始化 rustls crypto provider（只需要初始化一次）
-        let _ = rustls::crypto::ring::default_provider().install_default();
-
-        // 根据参数决定是否启用 HTTP/2，构建连接器
-        let connector = if enable_http2 {
-            // 启用 HTTP/1.1 和 HTTP/2
-            HttpsConnectorBuilder::new()
-                .with_native_roots()
-                .map_err(|e| anyhow!("Failed to load native certs: {}", e))?
-                .https_or_http()
-                .enable_http1()
-                .enable_http2()
-                .build()
-        } else {
-            // 只启用 HTTP/1.1
-            HttpsConnectorBuilder::new()
-                .with_native_roots()
-                .map_err(|e| anyhow!("Failed to load native certs: {}", e))?
+    /// - `options`: 超时、HTTP 版本、套接字与 curl 传输层参数
+    /// - `sources`: 新连接轮流绑定的本地源地址
+    pub fn new(options: &ClientOptions, sources: Option<Arc<SourceAddresses>>) -> Result<Self> {
+        let transport = &options.transport;
+        let enable_http2 = match transport.http_version {
+            Some(HttpVersion::Http1) => false,
+            Some(HttpVersion::Http2) | Some(HttpVersion::Http2PriorKnowledge) => true,
+            None => options.enable_http2,
+        };
+
+        let tls_config = tls::client_config(transport)?;
+        let socket = Arc::new(options.socket.clone());
+        let route = Arc::new(ConnectRoute {
+            resolve: transport.resolve.clone(),
+            proxy: transport.proxy.clone(),
+            unix_socket: transport.unix_socket.clone(),
+            connect_timeout: transport.connect_timeout,
+        });
+
+        let build = |local_address: Option<IpAddr>| {
+            let tcp = TcpConnector::new(local_address, socket.clone(), route.clone());
+            let builder = HttpsConnectorBuilder::new()
+                .with_tls_config(tls_config.clone())
                 .https_or_http()
-                .enable_http1()
-                .build()
+                .enable_http1();
+
+            // 根据参数决定是否启用 HTTP/2（通过 ALPN 协商）
+            if enable_http2 {
+                builder.enable_http2().wrap_connector(tcp)
+            } else {
+                builder.wrap_connector(tcp)
+            }
+        };
+
+        let connectors = match &sources {
+            Some(sources) => sources
+                .addresses()
+                .iter()
+                .map(|&ip| build(Some(ip)))
+                .collect(),
+            None => vec![build(None)],
         };
 
         Ok(Self {
-            connector: Arc::new(connector),
-            timeout,
+            connectors,
+            sources,
+            timeout: transport.max_time.unwrap_or(options.timeout),
+            h2c: transport.http_version == Some(HttpVersion::Http2PriorKnowledge),
+            follow_redirects: transport.follow_redirects,
+            location_trusted: transport.location_trusted,
+            forward_proxy: transport.proxy.is_some(),
+            proxy_authorization: transport
+                .proxy
+                .as_ref()
+                .and_then(|proxy| proxy.authorization.clone()),
         })
     }
 
@@ -89,116 +212,389 @@ impl HttpClient {
         state: &mut ClientState,
         method: &str,
         url: &str,
-        headers: &HashMap<String, String>,
+        headers: &Headers,
         body: Option<&str>,
     ) -> Result<(u16, usize)> {
-        let do_req = async {
-            // 解析 URL
-            let uri: Uri = url.parse().map_err(|e| anyhow!("Invalid URL: {}", e))?;
-
-            // 构建 HTTP 方法
-            let http_method = match method.to_uppercase().as_str() {
-                "GET" => Method::GET,
-                "POST" => Method::POST,
-                "PUT" => Method::PUT,
-                "DELETE" => Method::DELETE,
-                "HEAD" => Method::HEAD,
-                "PATCH" => Method::PATCH,
-                "OPTIONS" => Method::OPTIONS,
-                _ => Method::GET,
-            };
+        let do_req = self.send(state, method, url, headers, body, None, None);
+        self.with_timeout(do_req).await
+    }
 
-            // 构建请求体
-            let body_data = if let Some(data) = body {
-                Full::new(Bytes::from(data.to_string()))
-            } else {
-                Full::new(Bytes::new())
-            };
+    /// 发送请求并保存响应头和响应体，供调用方提取数据
+    pub async fn request_capture(
+        &self,
+        state: &mut ClientState,
+        method: &str,
+        url: &str,
+        headers: &Headers,
+        body: Option<&str>,
+    ) -> Result<(u16, usize, CapturedResponse)> {
+        let mut captured = CapturedResponse::default();
+        let do_req = self.send(state, method, url, headers, body, None, Some(&mut captured));
+        let (status, len_bytes) = self.with_timeout(do_req).await?;
+        Ok((status, len_bytes, captured))
+    }
+
+    /// 超时控制（timeout 为 0 表示不限制）
+    async fn with_timeout<T>(
+        &self,
+        fut: impl std::future::Future<Output = Result<T>>,
+    ) -> Result<T> {
+        if self.timeout.is_zero() {
+            return fut.await;
+        }
+        tokio::select! {
+            res = fut => res,
+            _ = tokio::time::sleep(self.timeout) => {
+                Err(anyhow!("Request timeout"))
+            }
+        }
+    }
+
+    /// 发送流式请求，按 `format` 解析响应体中的事件并记录到达时间
+    ///
+    /// 服务端关闭流时请求结束；超时前已经收到事件的流视为正常结束，
+    /// 因此 `--timeout` 也用来限制每个流的读取时长。
+    pub async fn request_stream(
+        &self,
+        state: &mut ClientState,
+        method: &str,
+        url: &str,
+        headers: &Headers,
+        body: Option<&str>,
+        format: StreamFormat,
+    ) -> Result<(u16, usize, StreamTiming)> {
+        let mut recorder = StreamRecorder::new(format, Instant::now());
+        let mut progress = (0u16, 0usize);
+
+        let result = {
+            let do_req = self.send(
+                state,
+                method,
+                url,
+                headers,
+                body,
+                Some((&mut recorder, &mut progress)),
+                None,
+            );
+            self.with_timeout(do_req).await
+        };
+
+        match result {
+            Ok((status, len_bytes)) => Ok((status, len_bytes, recorder.finish())),
+            Err(_) if recorder.events() > 0 => {
+                let (status, len_bytes) = progress;
+                Ok((status, len_bytes, recorder.finish()))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 发送请求；启用 -L 时按 curl 的规则跟随重定向
+    ///
+    /// 重定向到其他主机时使用临时连接，`state` 中的连接保持指向原主机；
+    /// 与 curl 一样，除非 --location-trusted，不向其他主机发送 Authorization 和 Cookie。
+    /// 返回最终响应的状态码和所有响应体的总字节数。
+    #[allow(clippy::too_many_arguments)]
+    async fn send(
+        &self,
+        state: &mut ClientState,
+        method: &str,
+        url: &str,
+        headers: &Headers,
+        body: Option<&str>,
+        mut stream: Option<(&mut StreamRecorder, &mut (u16, usize))>,
+        mut capture: Option<&mut CapturedResponse>,
+    ) -> Result<(u16, usize)> {
+        let Some(max_redirs) = self.follow_redirects else {
+            let exchange = self
+                .exchange(state, method, url, headers, body, stream, capture)
+                .await?;
+            return Ok((exchange.status, exchange.len_bytes));
+        };
+
+        let origin = authority_of(url);
+        let mut foreign_headers = None;
+        let mut method = Cow::Borrowed(method);
+        let mut url = Cow::Borrowed(url);
+        let mut body = body;
+        let mut total_bytes = 0;
+        let mut redirects = 0;
 
-            // 构建请求
-            let mut request = Request::builder().method(http_method).uri(uri.clone());
+        loop {
+            let hop_stream = stream
+                .as_mut()
+                .map(|(recorder, progress)| (&mut **recorder, &mut **progress));
 
-            // 添加 Host header（HTTP/1.1 必需）
-            if let Some(host) = uri.host() {
-                let host_value = if let Some(port) = uri.port_u16() {
-                    format!("{}:{}", host, port)
+            let hop_capture = capture.as_deref_mut();
+
+            let exchange = if authority_of(&url) == origin {
+                self.exchange(state, &method, &url, headers, body, hop_stream, hop_capture)
+                    .await?
+            } else {
+                let hop_headers = if self.location_trusted {
+                    headers
                 } else {
-                    host.to_string()
+                    foreign_headers.get_or_insert_with(|| {
+                        let mut headers = headers.clone();
+                        headers.remove("Authorization");
+                        headers.remove("Cookie");
+                        headers
+                    })
                 };
-                request = request.header("Host", host_value);
+                let mut hop_state = ClientState::new();
+                let exchange = self
+                    .exchange(
+                        &mut hop_state,
+                        &method,
+                        &url,
+                        hop_headers,
+                        body,
+                        hop_stream,
+                        hop_capture,
+                    )
+                    .await;
+                state.source_connects.append(&mut hop_state.source_connects);
+                exchange?
+            };
+            total_bytes += exchange.len_bytes;
+
+            let Some(location) = exchange.location else {
+                return Ok((exchange.status, total_bytes));
+            };
+            if redirects >= max_redirs {
+                return Err(anyhow!("Maximum ({}) redirects followed", max_redirs));
             }
+            redirects += 1;
+
+            let next = url::Url::parse(&url)
+                .and_then(|base| base.join(&location))
+                .map_err(|e| anyhow!("Invalid redirect location {}: {}", location, e))?;
+            url = Cow::Owned(next.to_string());
 
-            // 添加 headers
-            for (key, value) in headers {
-                request = request.header(key, value);
+            // curl：301/302/303 把 POST 改为 GET；303 把除 HEAD 外的方法都改为 GET
+            let switch_to_get = match exchange.status {
+                301 | 302 => method.eq_ignore_ascii_case("POST"),
+                303 => !method.eq_ignore_ascii_case("HEAD"),
+                _ => false,
+            };
+            if switch_to_get {
+                method = Cow::Borrowed("GET");
+                body = None;
             }
+        }
+    }
 
-            let request = request
-                .body(body_data)
-                .map_err(|e| anyhow!("Failed to build request: {}", e))?;
+    /// 发送请求并读取完整响应体
+    ///
+    /// `stream` 不为空时，每个数据块都会交给事件记录器，并同步更新已读取的状态码与字节数；
+    /// `capture` 不为空时保存响应头和响应体（重定向时只保留最后一个响应）。
+    #[allow(clippy::too_many_arguments)]
+    async fn exchange(
+        &self,
+        state: &mut ClientState,
+        method: &str,
+        url: &str,
+        headers: &Headers,
+        body: Option<&str>,
+        mut stream: Option<(&mut StreamRecorder, &mut (u16, usize))>,
+        mut capture: Option<&mut CapturedResponse>,
+    ) -> Result<Exchange> {
+        // 解析 URL
+        let uri: Uri = url.parse().map_err(|e| anyhow!("Invalid URL: {}", e))?;
 
-            // 获取或创建连接（关键优化：连接复用）
-            let mut send_request = if let Some(sr) = state.send_request.take() {
-                sr
-            } else {
+        // 构建 HTTP 方法（方法名区分大小写，PURGE 等自定义方法原样发送）
+        let http_method = Method::from_bytes(method.as_bytes())
+            .map_err(|_| anyhow!("Invalid HTTP method: {:?}", method))?;
+
+        // 构建请求体
+        let body_data = if let Some(data) = body {
+            Full::new(Bytes::from(data.to_string()))
+        } else {
+            Full::new(Bytes::new())
+        };
+
+        // 获取或创建连接（关键优化：连接复用）；已有连接指向其他来源时重新建连
+        let origin = uri.scheme().cloned().zip(uri.authority().cloned());
+        let mut send_request = match state.send_request.take() {
+            Some(sr) if state.origin == origin => sr,
+            _ => {
                 // 建立新连接
-                self.establish_connection(&uri).await?
+                self.establish_connection(&uri, &mut state.source_connects)
+                    .await?
+            }
+        };
+
+        // 检查连接是否可用，如果不可用则重连（oha 的策略）
+        while send_request.ready().await.is_err() {
+            send_request = self
+                .establish_connection(&uri, &mut state.source_connects)
+                .await?;
+        }
+
+        // 构建请求：HTTP/1.1 直连时请求行只带路径（origin-form），
+        // 经代理转发的明文请求和 HTTP/2 使用完整 URL
+        let plain_http = uri.scheme_str() != Some("https");
+        let target = match &send_request {
+            Sender::Http1(_) if !(self.forward_proxy && plain_http) => uri
+                .path_and_query()
+                .map(|pq| Uri::from(pq.clone()))
+                .unwrap_or_else(|| Uri::from_static("/")),
+            _ => uri.clone(),
+        };
+        let mut request = Request::builder().method(http_method).uri(target);
+
+        // 添加 Host header（HTTP/1.1 必需；HTTP/2 使用 :authority），
+        // 命令中自带 Host 或用 -H 'Host:' 删除时不添加
+        let host_header = !headers.contains("Host") && !headers.is_removed("Host");
+        if let (Sender::Http1(_), Some(host), true) = (&send_request, uri.host(), host_header) {
+            let host_value = if let Some(port) = uri.port_u16() {
+                format!("{}:{}", host, port)
+            } else {
+                host.to_string()
             };
+            request = request.header("Host", host_value);
+        }
 
-            // 检查连接是否可用，如果不可用则重连（oha 的策略）
-            while send_request.ready().await.is_err() {
-                send_request = self.establish_connection(&uri).await?;
+        // 明文请求经代理转发时由客户端携带代理认证（https 在 CONNECT 时携带）
+        if let Some(authorization) = &self.proxy_authorization {
+            if plain_http {
+                request = request.header("Proxy-Authorization", authorization);
             }
+        }
 
-            // 发送请求
-            match send_request.send_request(request).await {
-                Ok(res) => {
-                    let (parts, mut stream) = res.into_parts();
-                    let status = parts.status.as_u16();
-
-                    // 流式读取响应体（关键优化：不完整缓存）
-                    let mut len_bytes = 0;
-                    while let Some(chunk) = stream.frame().await {
-                        if let Ok(frame) = chunk {
-                            len_bytes += frame.data_ref().map(|d| d.len()).unwrap_or_default();
-                        }
-                    }
+        // 按书写顺序添加 headers，重复的请求头全部发送
+        for (key, value) in headers {
+            request = request.header(key, value);
+        }
 
-                    // 保存连接以便复用（关键优化：连接复用）
-                    state.send_request = Some(send_request);
+        let request = request
+            .body(body_data)
+            .map_err(|e| anyhow!("Failed to build request: {}", e))?;
 
-                    Ok::<_, anyhow::Error>((status, len_bytes))
-                }
-                Err(e) => {
-                    // 即使出错也保存连接，下次会重连
-                    state.send_request = Some(send_request);
-                    Err(anyhow!("Request failed: {}", e))
+        // 发送请求
+        match send_request.send_request(request).await {
+            Ok(res) => {
+                let (parts, mut body_stream) = res.into_parts();
+                let status = parts.status.as_u16();
+                let location = if parts.status.is_redirection() {
+                    parts
+                        .headers
+                        .get(hyper::header::LOCATION)
+                        .and_then(|value| value.to_str().ok())
+                        .map(str::to_string)
+                } else {
+                    None
+                };
+                if let Some(captured) = capture.as_mut() {
+                    captured.headers = parts
+                        .headers
+                        .iter()
+                        .map(|(key, value)| {
+                            (
+                                key.to_string(),
+                                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                            )
+                        })
+                        .collect();
+                    captured.cookies.extend(
+                        parts
+                            .headers
+                            .get_all(hyper::header::SET_COOKIE)
+                            .iter()
+                            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned()),
+                    );
+                    captured.body.clear();
                 }
-            }
-        };
 
-        // 超时控制
-        if self.timeout.as_secs() > 0 {
-            tokio::select! {
-                res = do_req => res,
-                _ = tokio::time::sleep(self.timeout) => {
-                    Err(anyhow!("Request timeout"))
+                // 流式读取响应体（关键优化：不完整缓存）
+                let mut len_bytes = 0;
+                while let Some(chunk) = body_stream.frame().await {
+                    if let Ok(frame) = chunk {
+                        if let Some(data) = frame.data_ref() {
+                            len_bytes += data.len();
+                            if let Some(captured) = capture.as_mut() {
+                                captured.body.extend_from_slice(data);
+                            }
+                            if let Some((recorder, progress)) = stream.as_mut() {
+                                recorder.on_chunk(data);
+                                **progress = (status, len_bytes);
+                            }
+                        }
+                    }
                 }
+
+                // 保存连接以便复用（关键优化：连接复用）
+                state.send_request = Some(send_request);
+                state.origin = origin;
+
+                Ok(Exchange {
+                    status,
+                    len_bytes,
+                    location,
+                })
+            }
+            Err(e) => {
+                // 即使出错也保存连接，下次会重连
+                state.send_request = Some(send_request);
+                state.origin = origin;
+                Err(anyhow!("Request failed: {}", e))
             }
-        } else {
-            do_req.await
         }
     }
 
-    /// 建立 HTTP/1.1 连接
-    async fn establish_connection(&self, uri: &Uri) -> Result<http1::SendRequest<Full<Bytes>>> {
+    /// 建立连接：ALPN 协商出 h2 或指定了 h2c 时使用 HTTP/2，否则使用 HTTP/1.1
+    ///
+    /// 绑定了源地址时轮流选择地址，并把建连结果追加到 `connects`。
+    async fn establish_connection(
+        &self,
+        uri: &Uri,
+        connects: &mut Vec<SourceConnect>,
+    ) -> Result<Sender> {
+        let (mut connector, address) = match &self.sources {
+            Some(sources) => {
+                let target = uri
+                    .host()
+                    .map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+                    .and_then(|host| host.parse::<IpAddr>().ok());
+                let index = sources.next_index_for(target).ok_or_else(|| {
+                    anyhow!("No bind address matches the address family of {}", uri)
+                })?;
+                (
+                    self.connectors[index].clone(),
+                    Some(sources.addresses()[index]),
+                )
+            }
+            None => (self.connectors[0].clone(), None),
+        };
+
         // 通过 connector 建立 TCP 连接
         use tower::Service;
-        let mut connector = self.connector.as_ref().clone();
-        let stream = connector
-            .call(uri.clone())
-            .await
-            .map_err(|e| anyhow!("Failed to connect: {}", e))?;
+        let stream = connector.call(uri.clone()).await;
+        if let Some(address) = address {
+            connects.push(SourceConnect {
+                address,
+                failed: stream.is_err(),
+            });
+        }
+        let stream = stream.map_err(|e| anyhow!("Failed to connect: {}", e))?;
+
+        let use_http2 = stream.connected().is_negotiated_h2()
+            || (self.h2c && uri.scheme_str() != Some("https"));
+
+        if use_http2 {
+            let (send_request, conn) = http2::handshake(TokioExecutor::new(), stream)
+                .await
+                .map_err(|e| anyhow!("Failed to handshake: {}", e))?;
+
+            // 在后台运行连接
+            tokio::spawn(async move {
+                if let Err(_e) = conn.await {
+                    // 连接错误，静默处理
+                }
+            });
+
+            return Ok(Sender::Http2(send_request));
+        }
 
         // 创建 HTTP/1.1 handshake
         let (send_request, conn) = http1::handshake(stream)
@@ -212,10 +608,19 @@ impl HttpClient {
             }
         });
 
-        Ok(send_request)
+        Ok(Sender::Http1(send_request))
     }
 }
 
+/// URL 的 scheme + authority，用于判断重定向和不同命令是否指向同一主机
+pub fn authority_of(url: &str) -> Option<(String, String)> {
+    let uri: Uri = url.parse().ok()?;
+    Some((
+        uri.scheme_str()?.to_ascii_lowercase(),
+        uri.authority()?.as_str().to_ascii_lowercase(),
+    ))
+}
+
 /// 连接池管理器
 pub struct ConnectionPool {
     clients: Vec<Arc<HttpClient>>,
@@ -227,19 +632,19 @@ impl ConnectionPool {
     ///
     /// # 参数
     /// - `pool_size`: 连接池中客户端数量
-    /// - `timeout`: 请求超时时间
-    /// - `connections_per_client`: 每个客户端的连接数
-    /// - `enable_http2`: 是否启用 HTTP/2
-    pub fn new(
-        pool_size: usize,
-        timeout: Duration,
-        connections_per_client: usize,
-        enable_http2: bool,
-    ) -> Result<Self> {
+    /// - `options`: 客户端参数
+    pub fn new(pool_size: usize, options: &ClientOptions) -> Result<Self> {
         let mut clients = Vec::with_capacity(pool_size);
+        let sources = if options.bind_addresses.is_empty() {
+            None
+        } else {
+            Some(Arc::new(SourceAddresses::new(
+                options.bind_addresses.clone(),
+            )))
+        };
 
         for _ in 0..pool_size {
-            let client = HttpClient::new(timeout, connections_per_client, enable_http2)?;
+            let client = HttpClient::new(options, sources.clone())?;
             clients.push(Arc::new(client));
         }
 
@@ -257,3 +662,273 @@ impl ConnectionPool {
         self.clients[index % self.clients.len()].clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::sync::Mutex;
+
+    type Recorded = Arc<Mutex<Vec<Value>>>;
+
+    /// 记录每个请求（方法、路径、小写请求头、请求体）的测试服务器
+    ///
+    /// `redirects` 中的路径返回对应的状态码和 Location，其余路径返回 200。
+    async fn spawn_recorder(redirects: &[(&str, u16, &str)]) -> (SocketAddr, Recorded) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let redirects: Arc<Vec<(String, u16, String)>> = Arc::new(
+            redirects
+                .iter()
+                .map(|(path, status, location)| (path.to_string(), *status, location.to_string()))
+                .collect(),
+        );
+        let recorded = Recorded::default();
+        let requests = recorded.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let redirects = redirects.clone();
+                let requests = requests.clone();
+                let service = hyper::service::service_fn(move |req: Request<Incoming>| {
+                    let redirects = redirects.clone();
+                    let requests = requests.clone();
+                    async move {
+                        let (parts, body) = req.into_parts();
+                        let body = body
+                            .collect()
+                            .await
+                            .map(|b| b.to_bytes())
+                            .unwrap_or_default();
+                        let headers: serde_json::Map<String, Value> = parts
+                            .headers
+                            .iter()
+                            .map(|(name, value)| {
+                                (name.to_string(), json!(value.to_str().unwrap_or("")))
+                            })
+                            .collect();
+                        requests.lock().unwrap().push(json!({
+                            "method": parts.method.as_str(),
+                            "path": parts.uri.path(),
+                            "headers": headers,
+                            "body": String::from_utf8_lossy(&body),
+                        }));
+
+                        let mut response = Response::builder();
+                        if let Some((_, status, location)) = redirects
+                            .iter()
+                            .find(|(path, _, _)| path == parts.uri.path())
+                        {
+                            response = response.status(*status).header("Location", location);
+                        }
+                        Ok::<_, Infallible>(response.body(Full::new(Bytes::new())).unwrap())
+                    }
+                });
+                tokio::spawn(
+                    hyper::server::conn::http1::Builder::new()
+                        .serve_connection(hyper_util::rt::TokioIo::new(stream), service),
+                );
+            }
+        });
+        (addr, recorded)
+    }
+
+    fn client(max_redirs: usize, location_trusted: bool) -> HttpClient {
+        let options = ClientOptions {
+            timeout: Duration::from_secs(5),
+            enable_http2: false,
+            bind_addresses: Vec::new(),
+            socket: SocketOptions::default(),
+            transport: Transport {
+                follow_redirects: Some(max_redirs),
+                location_trusted,
+                ..Default::default()
+            },
+        };
+        HttpClient::new(&options, None).unwrap()
+    }
+
+    /// 跟随重定向，返回最终状态码
+    async fn follow(
+        client: &HttpClient,
+        method: &str,
+        url: &str,
+        headers: &Headers,
+        body: Option<&str>,
+    ) -> Result<u16> {
+        let mut state = ClientState::new();
+        let (status, _) = client
+            .request(&mut state, method, url, headers, body)
+            .await?;
+        Ok(status)
+    }
+
+    #[tokio::test]
+    async fn test_connection_per_origin() {
+        let (a, a_recorded) = spawn_recorder(&[]).await;
+        let (b, b_recorded) = spawn_recorder(&[]).await;
+        let client = client(0, false);
+        let headers = Headers::new();
+
+        // 同一个 ClientState 交替请求两个主机，每个请求都要到达自己的主机
+        let mut state = ClientState::new();
+        for path in ["/1", "/2", "/3"] {
+            for addr in [a, b] {
+                let url = format!("http://{}{}", addr, path);
+                client
+                    .request(&mut state, "GET", &url, &headers, None)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        for recorded in [a_recorded, b_recorded] {
+            let paths: Vec<_> = recorded
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|request| request["path"].clone())
+                .collect();
+            assert_eq!(paths, ["/1", "/2", "/3"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_methods() {
+        let (addr, recorded) = spawn_recorder(&[]).await;
+        let client = client(0, false);
+        let headers = Headers::new();
+        let url = format!("http://{}/", addr);
+
+        // 方法名区分大小写，不做大小写转换
+        for method in ["DELETE", "PURGE", "purge"] {
+            follow(&client, method, &url, &headers, None).await.unwrap();
+        }
+        let methods: Vec<_> = recorded
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request["method"].clone())
+            .collect();
+        assert_eq!(methods, ["DELETE", "PURGE", "purge"]);
+
+        // 非法的方法名报错，而不是改成 GET 发送
+        let err = follow(&client, "GET /x", &url, &headers, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), r#"Invalid HTTP method: "GET /x""#);
+    }
+
+    #[tokio::test]
+    async fn test_redirect_methods() {
+        let (addr, recorded) = spawn_recorder(&[
+            ("/moved", 301, "/final"),
+            ("/found", 302, "/final"),
+            ("/other", 303, "/final"),
+            ("/temporary", 307, "/final"),
+            ("/loop", 302, "/loop"),
+        ])
+        .await;
+        let client = client(3, false);
+        let headers = Headers::new();
+        let url = |path: &str| format!("http://{}{}", addr, path);
+
+        // 301/302 把 POST 改为 GET 并丢弃请求体，303 对 PUT 也一样，307 保留方法和请求体
+        for (method, path, expected, body) in [
+            ("POST", "/moved", "GET", ""),
+            ("POST", "/found", "GET", ""),
+            ("PUT", "/other", "GET", ""),
+            ("POST", "/temporary", "POST", "data"),
+        ] {
+            recorded.lock().unwrap().clear();
+            let status = follow(&client, method, &url(path), &headers, Some("data"))
+                .await
+                .unwrap();
+            assert_eq!(status, 200, "{}", path);
+            let requests = recorded.lock().unwrap();
+            assert_eq!(requests.len(), 2, "{}", path);
+            assert_eq!(requests[1]["path"], "/final", "{}", path);
+            assert_eq!(requests[1]["method"], expected, "{}", path);
+            assert_eq!(requests[1]["body"], body, "{}", path);
+        }
+
+        let err = follow(&client, "GET", &url("/loop"), &headers, None)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Maximum (3) redirects followed");
+    }
+
+    #[tokio::test]
+    async fn test_cross_origin_redirect_credentials() {
+        let (other, other_recorded) = spawn_recorder(&[]).await;
+        let away = format!("http://{}/final", other);
+        let (addr, recorded) =
+            spawn_recorder(&[("/away", 302, away.as_str()), ("/local", 302, "/final")]).await;
+        let mut headers = Headers::new();
+        headers.append("authorization", "Basic dXNlcjpwYXNz");
+        headers.append("Cookie", "session=1");
+        headers.append("X-Trace", "abc");
+
+        // 重定向到其他主机时不发送 Authorization 和 Cookie（不区分大小写）
+        let status = follow(
+            &client(5, false),
+            "GET",
+            &format!("http://{}/away", addr),
+            &headers,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, 200);
+        {
+            let requests = other_recorded.lock().unwrap();
+            assert_eq!(requests.len(), 1);
+            assert_eq!(requests[0]["path"], "/final");
+            assert_eq!(requests[0]["headers"].get("authorization"), None);
+            assert_eq!(requests[0]["headers"].get("cookie"), None);
+            assert_eq!(requests[0]["headers"]["x-trace"], "abc");
+        }
+
+        // 同一主机内的重定向保留
+        recorded.lock().unwrap().clear();
+        follow(
+            &client(5, false),
+            "GET",
+            &format!("http://{}/local", addr),
+            &headers,
+            None,
+        )
+        .await
+        .unwrap();
+        {
+            let requests = recorded.lock().unwrap();
+            assert_eq!(requests.len(), 2);
+            assert_eq!(requests[1]["path"], "/final");
+            assert_eq!(
+                requests[1]["headers"]["authorization"],
+                "Basic dXNlcjpwYXNz"
+            );
+            assert_eq!(requests[1]["headers"]["cookie"], "session=1");
+        }
+
+        // --location-trusted
+        other_recorded.lock().unwrap().clear();
+        follow(
+            &client(5, true),
+            "GET",
+            &format!("http://{}/away", addr),
+            &headers,
+            None,
+        )
+        .await
+        .unwrap();
+        let requests = other_recorded.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            requests[0]["headers"]["authorization"],
+            "Basic dXNlcjpwYXNz"
+        );
+        assert_eq!(requests[0]["headers"]["cookie"], "session=1");
+    }
+}
@@ -0,0 +1,357 @@
+use crate::curl_parser::CurlCommand;
+use crate::postman::substitute;
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use std::path::Path;
+
+const METHODS: &[&str] = &[
+    "GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS", "TRACE", "CONNECT",
+];
+
+lazy_static::lazy_static! {
+    /// REST Client 的 `{{$randomInt min max}}`（上限不含）
+    static ref RANDOM_INT_REGEX: Regex =
+        Regex::new(r"\{\{\s*\$randomInt\s+(\d+)\s+(\d+)\s*\}\}").unwrap();
+}
+
+/// 系统变量与内置模板函数的对应关系（REST Client 和 JetBrains HTTP Client）
+const SYSTEM_VARIABLES: &[(&str, &str)] = &[
+    ("{{$guid}}", "{{uuid}}"),
+    ("{{$uuid}}", "{{uuid}}"),
+    ("{{$random.uuid}}", "{{uuid}}"),
+    ("{{$timestamp}}", "{{timestamp}}"),
+    ("{{$isoTimestamp}}", "{{timestamp:rfc3339}}"),
+    ("{{$datetime iso8601}}", "{{timestamp:rfc3339}}"),
+    ("{{$randomInt}}", "{{random:0-1000}}"),
+];
+
+/// 解析后的 `.http` 文件
+#[derive(Debug)]
+pub struct HttpFile {
+    /// 每个请求一条命令，`### 名称` 或 `# @name 名称` 作为端点名称
+    pub commands: Vec<CurlCommand>,
+    /// `@var = value` 声明的文件变量，注册为模板变量
+    pub variables: Vec<(String, String)>,
+}
+
+#[derive(PartialEq)]
+enum Section {
+    /// 请求行之前：注释、变量声明、空行
+    Preamble,
+    Headers,
+    Body,
+}
+
+/// 解析 VS Code REST Client / JetBrains HTTP Client 的 `.http` 文件
+///
+/// 请求之间用 `###` 分隔；每个请求是请求行（`METHOD URL [HTTP/1.1]`，省略方法时为 GET）、
+/// 请求头和空行之后的请求体。以 `?` / `&` 开头的行接在 URL 之后，`< ./file.json`
+/// 从文件读取请求体，响应处理脚本（`> {% ... %}`）和输出重定向（`>> file`）被忽略。
+pub fn parse_http_file(path: &Path) -> Result<HttpFile> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read HTTP file {}: {}", path.display(), e))?;
+    let base_dir = path.parent().unwrap_or(Path::new("."));
+    parse_http(&content, base_dir)
+}
+
+fn parse_http(content: &str, base_dir: &Path) -> Result<HttpFile> {
+    let mut variables: Vec<(String, String)> = Vec::new();
+    let mut commands = Vec::new();
+
+    let mut lines = content.lines().enumerate().peekable();
+    while lines.peek().is_some() {
+        // 一个 `###` 块
+        let mut name: Option<String> = None;
+        let mut section = Section::Preamble;
+        let mut start_line = 0;
+        let mut request_line = String::new();
+        let mut headers: Vec<(String, String)> = Vec::new();
+        let mut body: Vec<&str> = Vec::new();
+        let mut in_script = false;
+
+        while let Some((number, line)) = lines.peek().copied() {
+            if let Some(title) = line.strip_prefix("###") {
+                if section != Section::Preamble || name.is_some() {
+                    break;
+                }
+                lines.next();
+                let title = title.trim();
+                if !title.is_empty() {
+                    name = Some(title.to_string());
+                }
+                continue;
+            }
+            lines.next();
+            let trimmed = line.trim();
+
+            // 响应处理脚本可以跨越多行
+            if in_script {
+                in_script = !trimmed.ends_with("%}");
+                continue;
+            }
+            if section != Section::Preamble
+                && (trimmed.starts_with("> ") || trimmed.starts_with(">>"))
+            {
+                in_script = trimmed.starts_with("> {%") && !trimmed.ends_with("%}");
+                continue;
+            }
+
+            match section {
+                Section::Preamble => {
+                    if trimmed.is_empty() {
+                        continue;
+                    }
+                    if let Some(comment) = trimmed
+                        .strip_prefix('#')
+                        .or_else(|| trimmed.strip_prefix("//"))
+                    {
+                        if let Some(request_name) = comment.trim().strip_prefix("@name") {
+                            name = Some(request_name.trim().to_string());
+                        }
+                        continue;
+                    }
+                    if let Some(declaration) = trimmed.strip_prefix('@') {
+                        let (key, value) = declaration
+                            .split_once('=')
+                            .ok_or_else(|| anyhow!("line {}: invalid variable", number + 1))?;
+                        let key = key.trim().to_string();
+                        variables.retain(|(existing, _)| *existing != key);
+                        variables.push((key, value.trim().to_string()));
+                        continue;
+                    }
+                    start_line = number + 1;
+                    request_line = trimmed.to_string();
+                    section = Section::Headers;
+                }
+                Section::Headers => {
+                    if trimmed.is_empty() {
+                        section = Section::Body;
+                    } else if trimmed.starts_with('?') || trimmed.starts_with('&') {
+                        request_line.push_str(trimmed);
+                    } else if trimmed.starts_with('#') || trimmed.starts_with("//") {
+                        continue;
+                    } else if let Some((key, value)) = trimmed.split_once(':') {
+                        headers.push((key.trim().to_string(), value.trim().to_string()));
+                    } else {
+                        return Err(anyhow!("line {}: invalid header: {}", number + 1, trimmed));
+                    }
+                }
+                Section::Body => body.push(line),
+            }
+        }
+
+        if section == Section::Preamble {
+            continue;
+        }
+        let mut cmd = build_command(&request_line, headers, &body, base_dir)
+            .map_err(|e| anyhow!("line {}: {}", start_line, e))?;
+        cmd.name = name;
+        commands.push(cmd);
+    }
+
+    if commands.is_empty() {
+        return Err(anyhow!("No requests found in HTTP file"));
+    }
+
+    // 变量值可以引用前面声明的变量
+    let variables = variables
+        .iter()
+        .map(|(key, value)| (key.clone(), map_system(&substitute(value, &variables))))
+        .collect::<Vec<_>>();
+
+    Ok(HttpFile {
+        commands,
+        variables,
+    })
+}
+
+fn build_command(
+    request_line: &str,
+    headers: Vec<(String, String)>,
+    body: &[&str],
+    base_dir: &Path,
+) -> Result<CurlCommand> {
+    // 先替换系统变量，`{{$randomInt 1 10}}` 中含有空格
+    let request_line = map_system(request_line);
+    let mut parts: Vec<&str> = request_line.split_whitespace().collect();
+    if parts.len() > 1 && parts.last().is_some_and(|p| p.starts_with("HTTP/")) {
+        parts.pop();
+    }
+    let (method, url) = match parts.as_slice() {
+        [url] => ("GET", *url),
+        [method, url] if METHODS.contains(method) => (*method, *url),
+        _ => return Err(anyhow!("invalid request line: {}", request_line)),
+    };
+
+    // `GET /path` 加 `Host` 请求头的写法
+    let url = if url.starts_with('/') {
+        let host = headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case("Host"))
+            .map(|(_, value)| value.as_str())
+            .ok_or_else(|| anyhow!("relative URL {} needs a Host header", url))?;
+        format!("http://{}{}", host, url)
+    } else {
+        url.to_string()
+    };
+
+    let mut cmd = CurlCommand::new(url);
+    cmd.method = method.to_string();
+    for (key, value) in headers {
+        cmd.headers.append(key, map_system(&value));
+    }
+
+    // 去掉请求体末尾的空行
+    let end = body
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .map_or(0, |i| i + 1);
+    let body = &body[..end];
+    if let [line] = body {
+        // `< ./file`（`<@` 表示文件内容中的变量也会替换，请求体总是经过模板处理）
+        if let Some(file) = line
+            .strip_prefix("<@ ")
+            .or_else(|| line.strip_prefix("< "))
+            .map(str::trim)
+        {
+            let path = base_dir.join(file);
+            let content = std::fs::read_to_string(&path)
+                .map_err(|e| anyhow!("Failed to read body file {}: {}", path.display(), e))?;
+            cmd.body = Some(map_system(&content));
+            return Ok(cmd);
+        }
+    }
+    if !body.is_empty() {
+        cmd.body = Some(map_system(&body.join("\n")));
+    }
+
+    Ok(cmd)
+}
+
+fn map_system(text: &str) -> String {
+    let text = RANDOM_INT_REGEX.replace_all(text, |caps: &regex::Captures| {
+        let min: u64 = caps[1].parse().unwrap_or(0);
+        let max: u64 = caps[2].parse().unwrap_or(0);
+        format!("{{{{random:{}-{}}}}}", min, max.saturating_sub(1).max(min))
+    });
+    SYSTEM_VARIABLES
+        .iter()
+        .fold(text.into_owned(), |text, (from, to)| text.replace(from, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = r#"@host = api.example.com
+@baseUrl = https://{{host}}/v1
+@token = secret
+
+### List users
+GET {{baseUrl}}/users
+    ?page=2
+    &size={{$randomInt 10 51}}
+Accept: application/json
+Authorization: Bearer {{token}}
+
+> {%
+    client.global.set("id", response.body.id);
+%}
+
+###
+
+# @name createUser
+POST {{baseUrl}}/users HTTP/1.1
+Content-Type: application/json
+// X-Debug: 1
+
+{
+  "id": "{{$guid}}",
+  "name": "demo"
+}
+
+
+>> ./out.json
+
+### Relative URL with Host
+DELETE /users/1
+Host: localhost:8080
+
+###
+https://example.com/health
+"#;
+
+    #[test]
+    fn test_parse_requests_and_variables() {
+        let file = parse_http(FILE, Path::new(".")).unwrap();
+        assert_eq!(
+            file.variables,
+            [
+                ("host".to_string(), "api.example.com".to_string()),
+                (
+                    "baseUrl".to_string(),
+                    "https://api.example.com/v1".to_string()
+                ),
+                ("token".to_string(), "secret".to_string()),
+            ]
+        );
+
+        let names: Vec<_> = file.commands.iter().map(|c| c.name.as_deref()).collect();
+        assert_eq!(
+            names,
+            [
+                Some("List users"),
+                Some("createUser"),
+                Some("Relative URL with Host"),
+                None,
+            ]
+        );
+
+        let list = &file.commands[0];
+        assert_eq!(list.method, "GET");
+        assert_eq!(list.url, "{{baseUrl}}/users?page=2&size={{random:10-50}}");
+        assert_eq!(list.headers.get("Authorization"), Some("Bearer {{token}}"));
+        assert_eq!(list.body, None);
+
+        let create = &file.commands[1];
+        assert_eq!(create.method, "POST");
+        assert_eq!(create.url, "{{baseUrl}}/users");
+        assert_eq!(create.headers.len(), 1);
+        assert_eq!(
+            create.body.as_deref(),
+            Some("{\n  \"id\": \"{{uuid}}\",\n  \"name\": \"demo\"\n}")
+        );
+
+        let delete = &file.commands[2];
+        assert_eq!(delete.method, "DELETE");
+        assert_eq!(delete.url, "http://localhost:8080/users/1");
+
+        assert_eq!(file.commands[3].url, "https://example.com/health");
+    }
+
+    #[test]
+    fn test_body_from_file() {
+        let dir = std::env::temp_dir().join(format!("quickurl-http-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("body.json"), "{\"ts\": {{$timestamp}}}").unwrap();
+
+        let content =
+            "POST http://localhost/items\nContent-Type: application/json\n\n< ./body.json\n";
+        let file = parse_http(content, &dir).unwrap();
+        assert_eq!(
+            file.commands[0].body.as_deref(),
+            Some("{\"ts\": {{timestamp}}}")
+        );
+
+        let missing = "POST http://localhost/items\n\n< ./missing.json\n";
+        assert!(parse_http(missing, &dir).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        assert!(parse_http("@a = 1\n# only comments\n", Path::new(".")).is_err());
+        let err = parse_http("\n\nFETCH http://x y z\n", Path::new(".")).unwrap_err();
+        assert!(err.to_string().contains("line 3"), "{}", err);
+    }
+}
@@ -0,0 +1,386 @@
+use crate::curl_parser::{base64_encode, CurlCommand};
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const METHODS: &[&str] = &[
+    "GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS", "TRACE", "CONNECT",
+];
+
+/// 请求项的分隔符，同一位置匹配时长的优先
+const SEPARATORS: &[&str] = &[":=@", "==", "=@", ":=", "=", "@", ":", ";"];
+
+/// 带值的 HTTPie 参数中不影响请求的部分（输出、会话等），直接跳过
+const SKIPPED_WITH_VALUE: &[&str] = &[
+    "--print",
+    "-p",
+    "--pretty",
+    "--style",
+    "-s",
+    "--output",
+    "-o",
+    "--session",
+    "--session-read-only",
+    "--format-options",
+    "--response-charset",
+    "--response-mime",
+];
+
+/// 不影响请求的开关
+const SKIPPED_FLAGS: &[&str] = &[
+    "--verbose",
+    "-v",
+    "--headers",
+    "-h",
+    "--body",
+    "-b",
+    "--meta",
+    "-m",
+    "--offline",
+    "--check-status",
+    "--ignore-stdin",
+    "-I",
+    "--quiet",
+    "-q",
+    "--stream",
+    "-S",
+    "--all",
+    "--ignore-netrc",
+    "--download",
+    "-d",
+];
+
+/// HTTPie 默认的最大重定向次数
+const DEFAULT_MAX_REDIRECTS: usize = 30;
+
+/// 请求项中的数据字段
+enum Field {
+    Text(String),
+    Json(Value),
+}
+
+/// 解析已经分好词的 HTTPie 命令（`http` / `https` 开头）
+///
+/// 支持 `[METHOD] URL [ITEM ...]` 形式：`Header:value`、`name==query`、`name=text`、
+/// `name:=json`、`name=@file` / `name:=@file`，默认按 JSON 发送数据字段，
+/// `--form` 时按表单发送；URL 可以省略协议或写成 `:3000/path`（localhost）。
+pub fn parse_httpie_args(tokens: Vec<String>) -> Result<CurlCommand> {
+    let mut tokens = tokens.into_iter();
+    let scheme = match tokens.next().as_deref() {
+        Some("https") => "https",
+        _ => "http",
+    };
+
+    let mut positionals: Vec<String> = Vec::new();
+    let mut form = false;
+    let mut json = false;
+    let mut auth: Option<String> = None;
+    let mut auth_type = "basic".to_string();
+    let mut raw: Option<String> = None;
+    let mut follow = false;
+    let mut max_redirects: Option<usize> = None;
+    let mut cmd = CurlCommand::new(String::new());
+
+    while let Some(token) = tokens.next() {
+        if !token.starts_with('-') || token == "-" {
+            positionals.push(token);
+            continue;
+        }
+        let (flag, inline) = match token.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (token.clone(), None),
+        };
+        let mut value = |name: &str| -> Result<String> {
+            match inline.clone().or_else(|| tokens.next()) {
+                Some(value) => Ok(value),
+                None => Err(anyhow!("{} requires a value", name)),
+            }
+        };
+
+        match flag.as_str() {
+            "--json" | "-j" => json = true,
+            "--form" | "-f" => form = true,
+            "--follow" | "-F" => follow = true,
+            "--auth" | "-a" => auth = Some(value(&flag)?),
+            "--auth-type" | "-A" => auth_type = value(&flag)?.to_lowercase(),
+            "--raw" => raw = Some(value(&flag)?),
+            "--max-redirects" => {
+                max_redirects = Some(
+                    value(&flag)?
+                        .parse()
+                        .map_err(|_| anyhow!("invalid --max-redirects"))?,
+                )
+            }
+            "--timeout" => {
+                let seconds: f64 = value(&flag)?
+                    .parse()
+                    .map_err(|_| anyhow!("invalid --timeout"))?;
+                cmd.transport.max_time = Some(Duration::from_secs_f64(seconds));
+            }
+            "--verify" => match value(&flag)?.to_lowercase().as_str() {
+                "no" | "false" => cmd.transport.insecure = true,
+                "yes" | "true" => {}
+                path => cmd.transport.cacert = Some(PathBuf::from(path)),
+            },
+            "--cert" => cmd.transport.cert = Some(PathBuf::from(value(&flag)?)),
+            "--cert-key" => cmd.transport.key = Some(PathBuf::from(value(&flag)?)),
+            flag if SKIPPED_WITH_VALUE.contains(&flag) => {
+                value(flag)?;
+            }
+            flag if SKIPPED_FLAGS.contains(&flag) => {}
+            _ => cmd.ignored_options.push(flag),
+        }
+    }
+
+    // 第一个位置参数是已知方法或全大写、且后面还有 URL 时是方法
+    let mut positionals = positionals.into_iter().peekable();
+    let mut method = None;
+    let first = positionals
+        .next()
+        .ok_or_else(|| anyhow!("missing URL in HTTPie command"))?;
+    let is_method = first.chars().all(|c| c.is_ascii_alphabetic())
+        && (METHODS.contains(&first.to_uppercase().as_str())
+            || first.chars().all(|c| c.is_ascii_uppercase()));
+    let url = if positionals.peek().is_some() && is_method {
+        method = Some(first.to_uppercase());
+        positionals.next().unwrap()
+    } else {
+        first
+    };
+    cmd.url = normalize_url(&url, scheme);
+
+    let mut query: Vec<(String, String)> = Vec::new();
+    let mut fields: Vec<(String, Field)> = Vec::new();
+    for item in positionals {
+        let (key, separator, value) =
+            split_item(&item).ok_or_else(|| anyhow!("invalid request item `{}`", item))?;
+        match separator {
+            ":" if value.is_empty() => cmd.headers.remove(&key),
+            ":" => cmd.headers.append(key, value),
+            ";" => cmd.headers.append(key, ""),
+            "==" => query.push((key, value)),
+            "=" => fields.push((key, Field::Text(value))),
+            "=@" => fields.push((key, Field::Text(read_file(&value)?))),
+            ":=" | ":=@" => {
+                let text = if separator == ":=@" {
+                    read_file(&value)?
+                } else {
+                    value
+                };
+                let json: Value = serde_json::from_str(&text)
+                    .map_err(|e| anyhow!("invalid JSON in `{}`: {}", item, e))?;
+                fields.push((key, Field::Json(json)));
+            }
+            _ => return Err(anyhow!("file uploads (`{}`) are not supported", item)),
+        }
+    }
+
+    if !query.is_empty() {
+        let encoded = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(&query)
+            .finish();
+        let separator = if cmd.url.contains('?') { '&' } else { '?' };
+        cmd.url = format!("{}{}{}", cmd.url, separator, encoded);
+    }
+
+    if let Some(raw) = raw {
+        cmd.body = Some(raw);
+        if !form {
+            cmd.headers.set_default("Content-Type", "application/json");
+        }
+    } else if !fields.is_empty() {
+        if form {
+            let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+            for (key, field) in &fields {
+                match field {
+                    Field::Text(text) => serializer.append_pair(key, text),
+                    Field::Json(_) => {
+                        return Err(anyhow!("raw JSON fields (`{}:=`) need JSON mode", key))
+                    }
+                };
+            }
+            cmd.body = Some(serializer.finish());
+        } else {
+            let object: Map<String, Value> = fields
+                .into_iter()
+                .map(|(key, field)| match field {
+                    Field::Text(text) => (key, Value::String(text)),
+                    Field::Json(json) => (key, json),
+                })
+                .collect();
+            cmd.body = Some(Value::Object(object).to_string());
+        }
+    }
+
+    if form {
+        if cmd.body.is_some() {
+            cmd.headers.set_default(
+                "Content-Type",
+                "application/x-www-form-urlencoded; charset=utf-8",
+            );
+        }
+    } else if json || cmd.body.is_some() {
+        if cmd.body.is_some() {
+            cmd.headers.set_default("Content-Type", "application/json");
+        }
+        cmd.headers
+            .set_default("Accept", "application/json, */*;q=0.5");
+    }
+
+    if let Some(credentials) = auth {
+        match auth_type.as_str() {
+            "basic" => {
+                cmd.headers.set_default(
+                    "Authorization",
+                    format!("Basic {}", base64_encode(&credentials)),
+                );
+            }
+            "bearer" => {
+                cmd.headers
+                    .set_default("Authorization", format!("Bearer {}", credentials));
+            }
+            other => cmd.ignored_options.push(format!("--auth-type={}", other)),
+        }
+    }
+
+    if follow {
+        cmd.transport.follow_redirects = Some(max_redirects.unwrap_or(DEFAULT_MAX_REDIRECTS));
+    }
+    cmd.method = method.unwrap_or_else(|| {
+        if cmd.body.is_some() {
+            "POST".to_string()
+        } else {
+            "GET".to_string()
+        }
+    });
+
+    Ok(cmd)
+}
+
+/// `:3000/path` → `http://localhost:3000/path`，`example.com` → `http://example.com`
+fn normalize_url(url: &str, scheme: &str) -> String {
+    if url.contains("://") {
+        return url.to_string();
+    }
+    match url.strip_prefix(':') {
+        Some(path) if path.is_empty() || path.starts_with('/') => {
+            format!("{}://localhost{}", scheme, path)
+        }
+        Some(port) => format!("{}://localhost:{}", scheme, port),
+        None => format!("{}://{}", scheme, url),
+    }
+}
+
+/// 按第一个未转义的分隔符拆分请求项，返回（键，分隔符，值）
+fn split_item(item: &str) -> Option<(String, &'static str, String)> {
+    let mut key = String::new();
+    let mut chars = item.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        // `\:`、`\=` 等转义分隔符
+        if c == '\\' {
+            if let Some((_, next)) = chars.next() {
+                key.push(next);
+            }
+            continue;
+        }
+        if let Some(separator) = SEPARATORS.iter().find(|sep| item[i..].starts_with(**sep)) {
+            if key.is_empty() {
+                return None;
+            }
+            let value = unescape(&item[i + separator.len()..]);
+            return Some((key, separator, value));
+        }
+        key.push(c);
+    }
+    None
+}
+
+/// 值中转义的分隔符字符（`\=`、`\:` 等）
+fn unescape(value: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(&next) if c == '\\' && ":=@;\\".contains(next) => {
+                result.push(next);
+                chars.next();
+            }
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+fn read_file(path: &str) -> Result<String> {
+    std::fs::read_to_string(path).map_err(|e| anyhow!("Failed to read {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_json_items() {
+        let cmd = parse_httpie_args(args(
+            "http PUT example.com/api/items/1 X-Trace:abc Accept: search==a&b name=demo count:=3 tags:=[\"x\"] -a user:pw",
+        ))
+        .unwrap();
+        assert_eq!(cmd.method, "PUT");
+        assert_eq!(cmd.url, "http://example.com/api/items/1?search=a%26b");
+        assert_eq!(
+            cmd.body.as_deref(),
+            Some(r#"{"name":"demo","count":3,"tags":["x"]}"#)
+        );
+        assert_eq!(
+            cmd.headers.iter().collect::<Vec<_>>(),
+            [
+                ("X-Trace", "abc"),
+                ("Content-Type", "application/json"),
+                ("Authorization", "Basic dXNlcjpwdw=="),
+            ]
+        );
+        // `Accept:` 删除了默认的 Accept
+        assert!(cmd.headers.is_removed("Accept"));
+    }
+
+    #[test]
+    fn test_form_defaults_and_flags() {
+        let cmd = parse_httpie_args(args(
+            "https -f --follow --verify=no -A bearer --auth=tok --pretty all :8443/login user=a\\=b remember=yes",
+        ))
+        .unwrap();
+        assert_eq!(cmd.method, "POST");
+        assert_eq!(cmd.url, "https://localhost:8443/login");
+        assert_eq!(cmd.body.as_deref(), Some("user=a%3Db&remember=yes"));
+        assert_eq!(
+            cmd.headers.get("Content-Type"),
+            Some("application/x-www-form-urlencoded; charset=utf-8")
+        );
+        assert_eq!(cmd.headers.get("Authorization"), Some("Bearer tok"));
+        assert!(cmd.transport.insecure);
+        assert_eq!(cmd.transport.follow_redirects, Some(30));
+        assert!(cmd.ignored_options.is_empty());
+
+        let get = parse_httpie_args(args("http :/health X-Empty; --unknown")).unwrap();
+        assert_eq!(get.method, "GET");
+        assert_eq!(get.url, "http://localhost/health");
+        assert_eq!(get.body, None);
+        assert_eq!(get.headers.get("X-Empty"), Some(""));
+        assert_eq!(get.ignored_options, ["--unknown"]);
+    }
+
+    #[test]
+    fn test_invalid_items() {
+        assert!(parse_httpie_args(args("http example.com file@/tmp/a.bin")).is_err());
+        assert!(parse_httpie_args(args("http example.com bad:=notjson")).is_err());
+        assert!(parse_httpie_args(args("http")).is_err());
+        assert!(parse_httpie_args(args("http example.com novalue")).is_err());
+    }
+}
@@ -1,12 +1,29 @@
+mod access_log;
 mod batch;
 mod cli;
 mod curl_parser;
 mod engine;
+mod feeder;
+mod fetch_parser;
+mod grpc;
+mod har;
+mod headers;
 mod http_client;
+mod http_file;
+mod httpie_parser;
 mod mock_server;
+mod net;
+mod openapi;
+mod postman;
+mod powershell_parser;
+mod scenario;
+mod shell;
 mod stats;
+mod streaming;
 mod template;
+mod tls;
 mod ui;
+mod websocket;
 
 use anyhow::Result;
 use clap::Parser;
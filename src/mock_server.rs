@@ -1,19 +1,26 @@
 use crate::cli::Args;
 use anyhow::Result;
-use http_body_util::{BodyExt, Full};
-use hyper::body::Bytes;
-use hyper::server::conn::http1;
+use futures::{SinkExt, StreamExt};
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full, StreamBody};
+use hyper::body::{Bytes, Frame};
 use hyper::service::service_fn;
 use hyper::{Error as HyperError, Method, Request, Response, StatusCode};
-use hyper_util::rt::TokioIo;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::convert::Infallible;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use tokio::signal;
 use tokio::time::{sleep, Instant};
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
 use tracing::{info, warn};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +40,20 @@ pub struct RouteConfig {
     pub delay: Option<String>,
     #[serde(default)]
     pub echo: bool,
+    /// Upgrade matching requests to WebSocket and echo every message back
+    #[serde(default)]
+    pub websocket: bool,
+    /// Answer matching requests as gRPC unary calls, echoing the request message
+    #[serde(default)]
+    pub grpc: bool,
+    /// grpc-status returned by a gRPC route (default: 0, OK)
+    pub grpc_status: Option<u32>,
+    /// Respond with a text/event-stream of this many events (0 = until the client disconnects)
+    pub stream_events: Option<u32>,
+    /// Pause between streamed events (default: 100ms)
+    pub stream_interval: Option<String>,
+    /// Location header of the response, e.g. for a 301/302 redirect route
+    pub location: Option<String>,
 }
 
 fn default_method() -> String {
@@ -51,6 +72,12 @@ struct Route {
     response: Option<String>,
     delay: Option<std::time::Duration>,
     echo: bool,
+    websocket: bool,
+    grpc: bool,
+    grpc_status: u32,
+    /// 事件流：事件数与间隔
+    stream: Option<(u32, std::time::Duration)>,
+    location: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -61,17 +88,17 @@ struct MockServerState {
 fn parse_duration_string(s: &str) -> Result<std::time::Duration> {
     let s = s.trim();
 
-    if s.ends_with("ms") {
-        let num: u64 = s[..s.len() - 2].parse()?;
+    if let Some(num) = s.strip_suffix("ms") {
+        let num: u64 = num.parse()?;
         Ok(std::time::Duration::from_millis(num))
-    } else if s.ends_with('s') {
-        let num: u64 = s[..s.len() - 1].parse()?;
+    } else if let Some(num) = s.strip_suffix('s') {
+        let num: u64 = num.parse()?;
         Ok(std::time::Duration::from_secs(num))
-    } else if s.ends_with('m') {
-        let num: u64 = s[..s.len() - 1].parse()?;
+    } else if let Some(num) = s.strip_suffix('m') {
+        let num: u64 = num.parse()?;
         Ok(std::time::Duration::from_secs(num * 60))
-    } else if s.ends_with('h') {
-        let num: u64 = s[..s.len() - 1].parse()?;
+    } else if let Some(num) = s.strip_suffix('h') {
+        let num: u64 = num.parse()?;
         Ok(std::time::Duration::from_secs(num * 3600))
     } else {
         // Default to seconds
@@ -122,6 +149,15 @@ fn parse_routes(config_routes: Option<Vec<RouteConfig>>) -> Result<Vec<Route>> {
                 .as_ref()
                 .and_then(|d| parse_duration_string(d).ok());
 
+            let stream = route_config.stream_events.map(|events| {
+                let interval = route_config
+                    .stream_interval
+                    .as_ref()
+                    .and_then(|d| parse_duration_string(d).ok())
+                    .unwrap_or(std::time::Duration::from_millis(100));
+                (events, interval)
+            });
+
             routes.push(Route {
                 path: route_config.path,
                 method,
@@ -129,6 +165,11 @@ fn parse_routes(config_routes: Option<Vec<RouteConfig>>) -> Result<Vec<Route>> {
                 response: route_config.response,
                 delay,
                 echo: route_config.echo,
+                websocket: route_config.websocket,
+                grpc: route_config.grpc,
+                grpc_status: route_config.grpc_status.unwrap_or(0),
+                stream,
+                location: route_config.location,
             });
         }
     }
@@ -168,6 +209,11 @@ fn build_server_state(args: &Args) -> Result<MockServerState> {
                 response: args.mock_response.clone(),
                 delay,
                 echo: false,
+                websocket: false,
+                grpc: false,
+                grpc_status: 0,
+                stream: None,
+                location: None,
             });
         }
 
@@ -177,16 +223,198 @@ fn build_server_state(args: &Args) -> Result<MockServerState> {
     Ok(MockServerState { routes })
 }
 
+/// 判断请求是否为 WebSocket 升级请求
+type MockBody = BoxBody<Bytes, Infallible>;
+
+fn full_body(data: impl Into<Bytes>) -> MockBody {
+    Full::new(data.into()).boxed()
+}
+
+fn is_websocket_upgrade(req: &Request<hyper::body::Incoming>) -> bool {
+    let header_contains = |name: &str, token: &str| {
+        req.headers()
+            .get_all(name)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .any(|v| v.trim().eq_ignore_ascii_case(token))
+    };
+
+    req.method() == Method::GET
+        && header_contains("connection", "upgrade")
+        && header_contains("upgrade", "websocket")
+        && req.headers().contains_key("sec-websocket-key")
+}
+
+/// 完成 WebSocket 握手，并在后台把收到的每条消息原样返回
+fn websocket_echo(
+    mut req: Request<hyper::body::Incoming>,
+    delay: Option<std::time::Duration>,
+) -> Result<Response<MockBody>> {
+    let key = req
+        .headers()
+        .get("sec-websocket-key")
+        .map(|k| derive_accept_key(k.as_bytes()))
+        .unwrap_or_default();
+    let path = req.uri().path().to_string();
+    let on_upgrade = hyper::upgrade::on(&mut req);
+
+    tokio::task::spawn(async move {
+        let upgraded = match on_upgrade.await {
+            Ok(upgraded) => upgraded,
+            Err(e) => {
+                warn!("WebSocket upgrade failed: {}", e);
+                return;
+            }
+        };
+
+        let mut ws =
+            WebSocketStream::from_raw_socket(TokioIo::new(upgraded), Role::Server, None).await;
+        info!("WebSocket {} connected", path);
+
+        while let Some(Ok(msg)) = ws.next().await {
+            match msg {
+                Message::Text(_) | Message::Binary(_) => {
+                    if let Some(delay) = delay {
+                        sleep(delay).await;
+                    }
+                    if ws.send(msg).await.is_err() {
+                        break;
+                    }
+                }
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+    });
+
+    let response = Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header("Connection", "Upgrade")
+        .header("Upgrade", "websocket")
+        .header("Sec-WebSocket-Accept", key)
+        .body(full_body(Bytes::new()))?;
+
+    Ok(response)
+}
+
+/// 判断请求是否为 gRPC 调用
+fn is_grpc_request(req: &Request<hyper::body::Incoming>) -> bool {
+    req.headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/grpc"))
+}
+
+/// gRPC unary 回显：把请求消息原样返回，状态放在 trailers 中
+async fn grpc_echo(
+    req: Request<hyper::body::Incoming>,
+    delay: Option<std::time::Duration>,
+    grpc_status: u32,
+) -> Result<Response<MockBody>> {
+    let path = req.uri().path().to_string();
+    let body = req.collect().await?.to_bytes();
+
+    if let Some(delay) = delay {
+        sleep(delay).await;
+    }
+
+    // mock server 不提供服务端反射
+    let grpc_status = if path.starts_with("/grpc.reflection.") {
+        12 // UNIMPLEMENTED
+    } else {
+        grpc_status
+    };
+
+    info!("gRPC {} -> {}", path, grpc_status);
+
+    let mut trailers = hyper::HeaderMap::new();
+    trailers.insert("grpc-status", grpc_status.into());
+
+    let mut frames = Vec::new();
+    if grpc_status == 0 {
+        frames.push(Ok::<_, Infallible>(Frame::data(body)));
+    }
+    frames.push(Ok(Frame::trailers(trailers)));
+
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "application/grpc")
+        .body(BodyExt::boxed(StreamBody::new(futures::stream::iter(
+            frames,
+        ))))?;
+
+    Ok(response)
+}
+
+/// 按固定间隔发送 text/event-stream 事件
+fn event_stream(
+    events: u32,
+    interval: std::time::Duration,
+    data: Option<String>,
+) -> Result<Response<MockBody>> {
+    let body = futures::stream::unfold(0u32, move |seq| {
+        let data = data.clone();
+        async move {
+            if events > 0 && seq >= events {
+                return None;
+            }
+            if seq > 0 {
+                sleep(interval).await;
+            }
+            let data = data.unwrap_or_else(|| format!(r#"{{"seq": {}}}"#, seq));
+            let frame = Frame::data(Bytes::from(format!("id: {}\ndata: {}\n\n", seq, data)));
+            Some((Ok::<_, Infallible>(frame), seq + 1))
+        }
+    });
+
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/event-stream")
+        .header("Cache-Control", "no-cache")
+        .body(BodyExt::boxed(StreamBody::new(body)))?;
+
+    Ok(response)
+}
+
 async fn handle_request(
     req: Request<hyper::body::Incoming>,
     state: Arc<MockServerState>,
-) -> Result<Response<Full<Bytes>>> {
+) -> Result<Response<MockBody>> {
     let start_time = Instant::now();
     let method = req.method().clone();
     let uri = req.uri().clone();
     let path = uri.path();
     let query = uri.query().unwrap_or("");
 
+    // WebSocket 升级：匹配 websocket 路由，未匹配任何路由时走默认回显
+    if is_websocket_upgrade(&req) {
+        let route = state
+            .routes
+            .iter()
+            .find(|route| route.method == method && (route.path == "*" || route.path == path));
+        match route {
+            Some(route) if route.websocket => return websocket_echo(req, route.delay),
+            None => return websocket_echo(req, None),
+            _ => {}
+        }
+    }
+
+    // gRPC：匹配 grpc 路由，未匹配任何路由时走默认回显
+    if is_grpc_request(&req) {
+        let route = state
+            .routes
+            .iter()
+            .find(|route| route.method == method && (route.path == "*" || route.path == path));
+        match route {
+            Some(route) if route.grpc => {
+                return grpc_echo(req, route.delay, route.grpc_status).await
+            }
+            None => return grpc_echo(req, None, 0).await,
+            _ => {}
+        }
+    }
+
     // Collect headers
     let headers: HashMap<String, String> = req
         .headers()
@@ -204,12 +432,18 @@ async fn handle_request(
         .iter()
         .find(|route| route.method == method && (route.path == "*" || route.path == path));
 
+    let location = matched_route.and_then(|route| route.location.clone());
     let (status_code, response_body, echo_mode) = if let Some(route) = matched_route {
         // Apply delay if configured
         if let Some(delay) = route.delay {
             sleep(delay).await;
         }
 
+        if let Some((events, interval)) = route.stream {
+            info!("{} {} -> event stream ({} events)", method, path, events);
+            return event_stream(events, interval, route.response.clone());
+        }
+
         if route.echo {
             (route.status_code, None, true)
         } else {
@@ -248,10 +482,13 @@ async fn handle_request(
     );
 
     // Build response
-    let response = Response::builder()
+    let mut response = Response::builder()
         .status(status_code)
-        .header("Content-Type", "application/json")
-        .body(Full::new(Bytes::from(response_body)))?;
+        .header("Content-Type", "application/json");
+    if let Some(location) = location {
+        response = response.header("Location", location);
+    }
+    let response = response.body(full_body(response_body))?;
 
     Ok(response)
 }
@@ -282,52 +519,72 @@ pub async fn run(args: Args) -> Result<()> {
         }
     }
 
+    tokio::select! {
+        _ = serve(listener, state) => {}
+        _ = signal::ctrl_c() => {
+            info!("Shutting down mock server...");
+        }
+    }
+
+    Ok(())
+}
+
+/// 在监听器上处理连接（HTTP/1.1 与 h2c 自动识别）
+async fn serve(listener: TcpListener, state: Arc<MockServerState>) {
     loop {
-        tokio::select! {
-            result = listener.accept() => {
-                match result {
-                    Ok((stream, _)) => {
-                        let io = TokioIo::new(stream);
-                        let state_clone = state.clone();
-
-                        tokio::task::spawn(async move {
-                            let service = service_fn(move |req| {
-                                let state = state_clone.clone();
-                                async move {
-                                    match handle_request(req, state).await {
-                                        Ok(response) => Ok::<Response<Full<Bytes>>, HyperError>(response),
-                                        Err(e) => {
-                                            warn!("Error handling request: {}", e);
-                                            let error_response = Response::builder()
-                                                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                                                .header("Content-Type", "application/json")
-                                                .body(Full::new(Bytes::from(format!(r#"{{"error": "{}"}}"#, e))))
-                                                .unwrap();
-                                            Ok(error_response)
-                                        }
-                                    }
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let io = TokioIo::new(stream);
+                let state_clone = state.clone();
+
+                tokio::task::spawn(async move {
+                    let service = service_fn(move |req| {
+                        let state = state_clone.clone();
+                        async move {
+                            match handle_request(req, state).await {
+                                Ok(response) => Ok::<Response<MockBody>, HyperError>(response),
+                                Err(e) => {
+                                    warn!("Error handling request: {}", e);
+                                    let error_response = Response::builder()
+                                        .status(StatusCode::INTERNAL_SERVER_ERROR)
+                                        .header("Content-Type", "application/json")
+                                        .body(full_body(format!(r#"{{"error": "{}"}}"#, e)))
+                                        .unwrap();
+                                    Ok(error_response)
                                 }
-                            });
-
-                            if let Err(err) = http1::Builder::new()
-                                .serve_connection(io, service)
-                                .await
-                            {
-                                warn!("Error serving connection: {}", err);
                             }
-                        });
+                        }
+                    });
+
+                    if let Err(err) = auto::Builder::new(TokioExecutor::new())
+                        .serve_connection_with_upgrades(io, service)
+                        .await
+                    {
+                        warn!("Error serving connection: {}", err);
                     }
-                    Err(e) => {
-                        warn!("Failed to accept connection: {}", e);
-                    }
-                }
+                });
             }
-            _ = signal::ctrl_c() => {
-                info!("Shutting down mock server...");
-                break;
+            Err(e) => {
+                warn!("Failed to accept connection: {}", e);
             }
         }
     }
+}
 
-    Ok(())
+/// 在随机本地端口启动一个只有默认路由的 mock server（测试用）
+#[cfg(test)]
+pub async fn spawn_local() -> Result<SocketAddr> {
+    spawn_local_with(Vec::new()).await
+}
+
+/// 在随机本地端口启动带指定路由的 mock server（测试用）
+#[cfg(test)]
+pub async fn spawn_local_with(routes: Vec<RouteConfig>) -> Result<SocketAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let state = Arc::new(MockServerState {
+        routes: parse_routes(Some(routes))?,
+    });
+    tokio::spawn(serve(listener, state));
+    Ok(addr)
 }
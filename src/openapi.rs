@@ -0,0 +1,681 @@
+use crate::curl_parser::CurlCommand;
+use anyhow::{anyhow, Result};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::Value;
+use std::path::Path;
+
+const METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// 路径和查询参数中保留的字符（RFC 3986 unreserved）
+const COMPONENT_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// 生成的请求体中 schema 的最大嵌套深度（防止循环引用）
+const MAX_DEPTH: usize = 6;
+
+/// OpenAPI 导入选项
+#[derive(Debug, Clone, Default)]
+pub struct OpenApiOptions {
+    /// 覆盖文档中的 servers
+    pub server: Option<String>,
+    /// 只导入带这些 tag 的操作
+    pub tags: Vec<String>,
+    /// 只导入这些 operationId
+    pub operations: Vec<String>,
+}
+
+/// 参数值：文档中的示例（需要编码）或模板占位符
+enum Generated {
+    Example(Value),
+    Template { text: String, quoted: bool },
+}
+
+impl Generated {
+    /// 用在 URL 或表单中的值
+    fn encoded(&self) -> String {
+        match self {
+            Generated::Example(value) => encode(&plain(value)),
+            Generated::Template { text, .. } => text.clone(),
+        }
+    }
+
+    /// 用在请求头中的值
+    fn plain(&self) -> String {
+        match self {
+            Generated::Example(value) => plain(value),
+            Generated::Template { text, .. } => text.clone(),
+        }
+    }
+
+    /// 用在 JSON 请求体中的值
+    fn json(&self) -> String {
+        match self {
+            Generated::Example(value) => value.to_string(),
+            Generated::Template { text, quoted: true } => Value::String(text.clone()).to_string(),
+            Generated::Template {
+                text,
+                quoted: false,
+            } => text.clone(),
+        }
+    }
+}
+
+/// 从 OpenAPI 3 文档（YAML 或 JSON）生成请求
+///
+/// 每个操作生成一条命令，以 operationId（没有时为 `METHOD /path`）命名，按名称统计。
+/// 参数和请求体优先使用文档中的示例，否则按 schema 类型生成模板变量
+/// （如 `{{random:1-1000}}`、`{{uuid}}`），每次请求重新取值。
+pub fn parse_openapi_file(path: &Path, options: &OpenApiOptions) -> Result<Vec<CurlCommand>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read OpenAPI document {}: {}", path.display(), e))?;
+    let doc: Value = serde_yaml::from_str(&content)
+        .map_err(|e| anyhow!("Invalid OpenAPI document {}: {}", path.display(), e))?;
+    parse_openapi(&doc, options)
+}
+
+fn parse_openapi(doc: &Value, options: &OpenApiOptions) -> Result<Vec<CurlCommand>> {
+    let version = doc.get("openapi").and_then(Value::as_str).unwrap_or("");
+    if !version.starts_with('3') {
+        return Err(anyhow!(
+            "Only OpenAPI 3 documents are supported (found {})",
+            if version.is_empty() {
+                "no `openapi` field"
+            } else {
+                version
+            }
+        ));
+    }
+
+    let base = server_url(doc, options)?;
+    let paths = doc
+        .get("paths")
+        .and_then(Value::as_object)
+        .ok_or_else(|| anyhow!("OpenAPI document has no paths"))?;
+
+    let mut commands = Vec::new();
+    for (path, item) in paths {
+        let item = resolve(doc, item)?;
+        let shared_params = item.get("parameters").and_then(Value::as_array);
+
+        for method in METHODS {
+            let Some(operation) = item.get(*method) else {
+                continue;
+            };
+            let operation_id = operation.get("operationId").and_then(Value::as_str);
+            if !selected(operation, operation_id, options) {
+                continue;
+            }
+
+            let mut cmd = build_command(doc, &base, path, method, shared_params, operation)?;
+            cmd.name = Some(match operation_id {
+                Some(id) => id.to_string(),
+                None => format!("{} {}", method.to_uppercase(), path),
+            });
+            commands.push(cmd);
+        }
+    }
+
+    if commands.is_empty() {
+        return Err(anyhow!("No OpenAPI operations match the selection"));
+    }
+
+    Ok(commands)
+}
+
+fn selected(operation: &Value, operation_id: Option<&str>, options: &OpenApiOptions) -> bool {
+    let tag_ok = options.tags.is_empty()
+        || operation
+            .get("tags")
+            .and_then(Value::as_array)
+            .is_some_and(|tags| {
+                tags.iter()
+                    .filter_map(Value::as_str)
+                    .any(|tag| options.tags.iter().any(|t| t == tag))
+            });
+    let id_ok = options.operations.is_empty()
+        || operation_id.is_some_and(|id| options.operations.iter().any(|o| o == id));
+    tag_ok && id_ok
+}
+
+/// 基础 URL：`--openapi-server` 或第一个 server（代入变量默认值）
+fn server_url(doc: &Value, options: &OpenApiOptions) -> Result<String> {
+    if let Some(server) = &options.server {
+        return Ok(server.trim_end_matches('/').to_string());
+    }
+
+    let server = doc
+        .get("servers")
+        .and_then(Value::as_array)
+        .and_then(|servers| servers.first());
+    let mut url = server
+        .and_then(|server| server.get("url"))
+        .and_then(Value::as_str)
+        .unwrap_or("/")
+        .to_string();
+    if let Some(variables) = server
+        .and_then(|server| server.get("variables"))
+        .and_then(Value::as_object)
+    {
+        for (name, variable) in variables {
+            if let Some(default) = variable.get("default").and_then(Value::as_str) {
+                url = url.replace(&format!("{{{}}}", name), default);
+            }
+        }
+    }
+
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(anyhow!(
+            "OpenAPI server URL `{}` is not absolute; pass --openapi-server",
+            url
+        ));
+    }
+    Ok(url.trim_end_matches('/').to_string())
+}
+
+fn build_command(
+    doc: &Value,
+    base: &str,
+    path: &str,
+    method: &str,
+    shared_params: Option<&Vec<Value>>,
+    operation: &Value,
+) -> Result<CurlCommand> {
+    // 操作级参数覆盖路径级的同名参数
+    let mut params: Vec<&Value> = Vec::new();
+    let own_params = operation.get("parameters").and_then(Value::as_array);
+    for param in shared_params
+        .into_iter()
+        .flatten()
+        .chain(own_params.into_iter().flatten())
+    {
+        let param = resolve(doc, param)?;
+        let key = (param.get("name"), param.get("in"));
+        params.retain(|p| (p.get("name"), p.get("in")) != key);
+        params.push(param);
+    }
+
+    let mut url_path = path.to_string();
+    let mut query = Vec::new();
+    let mut headers = Vec::new();
+    let mut cookies = Vec::new();
+    for param in params {
+        let name = param.get("name").and_then(Value::as_str).unwrap_or("");
+        let location = param.get("in").and_then(Value::as_str).unwrap_or("");
+        let required = param
+            .get("required")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let value = param_value(doc, param, name)?;
+
+        match location {
+            "path" => {
+                let value = value.unwrap_or_else(|| Generated::Template {
+                    text: format!("{}-{{{{random:1-1000}}}}", encode(name)),
+                    quoted: true,
+                });
+                url_path = url_path.replace(&format!("{{{}}}", name), &value.encoded());
+            }
+            // 可选参数只在文档给出示例时带上
+            "query" | "header" | "cookie" => {
+                let value = match value {
+                    Some(value) if required || is_example(&value) => value,
+                    _ => continue,
+                };
+                match location {
+                    "query" => query.push(format!("{}={}", encode(name), value.encoded())),
+                    "header" => headers.push((name.to_string(), value.plain())),
+                    _ => cookies.push(format!("{}={}", name, value.plain())),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut url = format!("{}{}", base, url_path);
+    if !query.is_empty() {
+        url.push('?');
+        url.push_str(&query.join("&"));
+    }
+
+    let mut cmd = CurlCommand::new(url);
+    cmd.method = method.to_uppercase();
+    for (name, value) in headers {
+        cmd.headers.append(name, value);
+    }
+    if !cookies.is_empty() {
+        cmd.headers.append("Cookie", cookies.join("; "));
+    }
+
+    if let Some(body) = operation.get("requestBody") {
+        let body = resolve(doc, body)?;
+        if let Some((content_type, payload)) = request_body(doc, body)? {
+            cmd.headers.set_default("Content-Type", content_type);
+            cmd.body = Some(payload);
+        }
+    }
+
+    Ok(cmd)
+}
+
+fn is_example(value: &Generated) -> bool {
+    matches!(value, Generated::Example(_))
+}
+
+/// 参数值：example → examples 的第一个 → schema 生成
+fn param_value(doc: &Value, param: &Value, name: &str) -> Result<Option<Generated>> {
+    if let Some(example) = param.get("example") {
+        return Ok(Some(Generated::Example(example.clone())));
+    }
+    if let Some(example) = first_example(doc, param.get("examples"))? {
+        return Ok(Some(Generated::Example(example)));
+    }
+    match param.get("schema") {
+        Some(schema) => Ok(Some(scalar(doc, resolve(doc, schema)?, name))),
+        None => Ok(None),
+    }
+}
+
+fn first_example(doc: &Value, examples: Option<&Value>) -> Result<Option<Value>> {
+    let Some(first) = examples
+        .and_then(Value::as_object)
+        .and_then(|examples| examples.values().next())
+    else {
+        return Ok(None);
+    };
+    Ok(resolve(doc, first)?.get("value").cloned())
+}
+
+/// 请求体：优先 JSON，其次表单；示例优先，否则按 schema 生成
+fn request_body(doc: &Value, body: &Value) -> Result<Option<(String, String)>> {
+    let Some(content) = body.get("content").and_then(Value::as_object) else {
+        return Ok(None);
+    };
+
+    let json = content
+        .iter()
+        .find(|(media, _)| media.starts_with("application/json") || media.ends_with("+json"));
+    if let Some((media, spec)) = json {
+        let example = match spec.get("example") {
+            Some(example) => Some(example.clone()),
+            None => first_example(doc, spec.get("examples"))?,
+        };
+        let payload = match example {
+            Some(example) => example.to_string(),
+            None => match spec.get("schema") {
+                Some(schema) => json_template(doc, schema, "", 0)?,
+                None => "{}".to_string(),
+            },
+        };
+        return Ok(Some((media.clone(), payload)));
+    }
+
+    if let Some(spec) = content.get("application/x-www-form-urlencoded") {
+        let schema = match spec.get("schema") {
+            Some(schema) => resolve(doc, schema)?,
+            None => return Ok(None),
+        };
+        let mut pairs = Vec::new();
+        for (name, property) in properties(doc, schema)? {
+            let value = scalar(doc, resolve(doc, property)?, &name);
+            pairs.push(format!("{}={}", encode(&name), value.encoded()));
+        }
+        return Ok(Some((
+            "application/x-www-form-urlencoded".to_string(),
+            pairs.join("&"),
+        )));
+    }
+
+    // 其他类型只在有字符串示例时使用
+    for (media, spec) in content {
+        if let Some(Value::String(example)) = spec.get("example") {
+            return Ok(Some((media.clone(), example.clone())));
+        }
+    }
+    Ok(None)
+}
+
+/// 按 schema 生成带模板占位符的 JSON 文本
+fn json_template(doc: &Value, schema: &Value, name: &str, depth: usize) -> Result<String> {
+    let schema = resolve(doc, schema)?;
+    if depth > MAX_DEPTH {
+        return Ok("null".to_string());
+    }
+    if let Some(example) = schema.get("example") {
+        return Ok(example.to_string());
+    }
+    if let Some(first) = ["oneOf", "anyOf"]
+        .iter()
+        .find_map(|key| schema.get(*key).and_then(Value::as_array)?.first())
+    {
+        return json_template(doc, first, name, depth + 1);
+    }
+
+    let kind = schema_type(schema);
+    if kind == Some("object") || schema.get("properties").is_some() || schema.get("allOf").is_some()
+    {
+        let mut fields = Vec::new();
+        for (key, property) in properties(doc, schema)? {
+            let value = json_template(doc, property, &key, depth + 1)?;
+            fields.push(format!("{}:{}", Value::String(key), value));
+        }
+        return Ok(format!("{{{}}}", fields.join(",")));
+    }
+    if kind == Some("array") {
+        let item = match schema.get("items") {
+            Some(items) => json_template(doc, items, name, depth + 1)?,
+            None => String::new(),
+        };
+        return Ok(format!("[{}]", item));
+    }
+
+    Ok(scalar(doc, schema, name).json())
+}
+
+/// 对象的属性（合并 allOf 中各部分的属性）
+fn properties<'a>(doc: &'a Value, schema: &'a Value) -> Result<Vec<(String, &'a Value)>> {
+    let mut result: Vec<(String, &Value)> = Vec::new();
+    if let Some(parts) = schema.get("allOf").and_then(Value::as_array) {
+        for part in parts {
+            for (name, property) in properties(doc, resolve(doc, part)?)? {
+                result.retain(|(existing, _)| *existing != name);
+                result.push((name, property));
+            }
+        }
+    }
+    if let Some(props) = schema.get("properties").and_then(Value::as_object) {
+        for (name, property) in props {
+            result.retain(|(existing, _)| existing != name);
+            result.push((name.clone(), property));
+        }
+    }
+    Ok(result)
+}
+
+/// 标量值：example → default → enum → 按类型和 format 生成模板
+fn scalar(doc: &Value, schema: &Value, name: &str) -> Generated {
+    let schema = resolve(doc, schema).unwrap_or(schema);
+    if let Some(example) = schema.get("example").or_else(|| schema.get("default")) {
+        return Generated::Example(example.clone());
+    }
+
+    let kind = schema_type(schema);
+    let quoted = !matches!(kind, Some("integer" | "number" | "boolean"));
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        let options: Vec<String> = values.iter().map(plain).collect();
+        // choice 用逗号分隔，选项本身含逗号或花括号时只取第一个
+        if options.len() > 1
+            && options
+                .iter()
+                .all(|o| !o.is_empty() && !o.contains([',', '{', '}']))
+        {
+            return Generated::Template {
+                text: format!("{{{{choice:{}}}}}", options.join(",")),
+                quoted,
+            };
+        }
+        if let Some(first) = values.first() {
+            return Generated::Example(first.clone());
+        }
+    }
+
+    let text = match kind {
+        Some("integer" | "number") => {
+            // random 的范围以 - 分隔，不支持负数
+            let min = schema
+                .get("minimum")
+                .and_then(Value::as_f64)
+                .unwrap_or(1.0)
+                .max(0.0) as i64;
+            let max = schema
+                .get("maximum")
+                .and_then(Value::as_f64)
+                .map(|max| max as i64)
+                .unwrap_or(min + 999)
+                .max(min);
+            format!("{{{{random:{}-{}}}}}", min, max)
+        }
+        Some("boolean") => "{{choice:true,false}}".to_string(),
+        _ => match schema.get("format").and_then(Value::as_str) {
+            Some("uuid") => "{{uuid}}".to_string(),
+            Some("date") => "{{timestamp:date}}".to_string(),
+            Some("date-time") => "{{timestamp:rfc3339}}".to_string(),
+            Some("email") => "user{{random:1-100000}}@example.com".to_string(),
+            _ => {
+                let prefix = if name.is_empty() { "value" } else { name };
+                format!("{}-{{{{random:1-100000}}}}", encode(prefix))
+            }
+        },
+    };
+    Generated::Template { text, quoted }
+}
+
+/// schema 的类型（3.1 中 type 可以是数组，取第一个非 null 的类型）
+fn schema_type(schema: &Value) -> Option<&str> {
+    match schema.get("type")? {
+        Value::String(kind) => Some(kind),
+        Value::Array(kinds) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|kind| *kind != "null"),
+        _ => None,
+    }
+}
+
+/// 解析文档内的 `$ref`（`#/components/...`）
+fn resolve<'a>(doc: &'a Value, mut value: &'a Value) -> Result<&'a Value> {
+    for _ in 0..16 {
+        let Some(reference) = value.get("$ref").and_then(Value::as_str) else {
+            return Ok(value);
+        };
+        let pointer = reference
+            .strip_prefix('#')
+            .ok_or_else(|| anyhow!("Unsupported external $ref: {}", reference))?;
+        value = doc
+            .pointer(pointer)
+            .ok_or_else(|| anyhow!("Unresolved $ref: {}", reference))?;
+    }
+    Err(anyhow!("$ref chain too deep"))
+}
+
+/// 示例值的文本形式：字符串不带引号，数组用逗号连接（form 风格）
+fn plain(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(plain).collect::<Vec<_>>().join(","),
+        other => other.to_string(),
+    }
+}
+
+fn encode(s: &str) -> String {
+    utf8_percent_encode(s, COMPONENT_SET).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r##"
+openapi: 3.0.3
+info: {title: Pets, version: "1.0"}
+servers:
+  - url: https://{env}.example.com/v1/
+    variables:
+      env: {default: api}
+paths:
+  /pets:
+    get:
+      operationId: listPets
+      tags: [pets]
+      parameters:
+        - {name: limit, in: query, schema: {type: integer, minimum: 1, maximum: 50}}
+        - {name: status, in: query, required: true, schema: {type: string, enum: [available, sold]}}
+        - {name: sort, in: query, schema: {type: string}}
+        - {name: X-Request-Id, in: header, required: true, schema: {type: string, format: uuid}}
+    post:
+      operationId: createPet
+      tags: [pets, admin]
+      requestBody:
+        content:
+          application/json:
+            schema: {$ref: "#/components/schemas/NewPet"}
+  /pets/{petId}:
+    parameters:
+      - $ref: "#/components/parameters/PetId"
+    get:
+      tags: [pets]
+      parameters:
+        - {name: fields, in: query, example: [name, tag]}
+    delete:
+      operationId: deletePet
+      tags: [admin]
+      parameters:
+        - {name: petId, in: path, required: true, example: "a b"}
+  /login:
+    post:
+      operationId: login
+      requestBody:
+        content:
+          application/x-www-form-urlencoded:
+            schema:
+              properties:
+                user: {type: string, example: demo}
+                remember: {type: boolean}
+components:
+  parameters:
+    PetId: {name: petId, in: path, required: true, schema: {type: integer, format: int64}}
+  schemas:
+    Pet:
+      type: object
+      properties:
+        name: {type: string}
+        tag: {type: string, example: dog}
+    NewPet:
+      allOf:
+        - $ref: "#/components/schemas/Pet"
+        - type: object
+          properties:
+            id: {type: string, format: uuid}
+            age: {type: integer, maximum: 20}
+            vaccinated: {type: boolean}
+            born: {type: string, format: date}
+            owners:
+              type: array
+              items: {type: string, format: email}
+"##;
+
+    fn commands(options: &OpenApiOptions) -> Vec<CurlCommand> {
+        let doc: Value = serde_yaml::from_str(SPEC).unwrap();
+        parse_openapi(&doc, options).unwrap()
+    }
+
+    fn named<'a>(commands: &'a [CurlCommand], name: &str) -> &'a CurlCommand {
+        commands
+            .iter()
+            .find(|cmd| cmd.name.as_deref() == Some(name))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parameters_from_examples_and_schema() {
+        let commands = commands(&OpenApiOptions::default());
+        assert_eq!(commands.len(), 5);
+
+        let list = named(&commands, "listPets");
+        assert_eq!(list.method, "GET");
+        assert_eq!(
+            list.url,
+            "https://api.example.com/v1/pets?status={{choice:available,sold}}"
+        );
+        assert_eq!(list.headers.get("X-Request-Id"), Some("{{uuid}}"));
+
+        // 没有 operationId 时以方法和路径命名
+        let get = named(&commands, "GET /pets/{petId}");
+        assert_eq!(
+            get.url,
+            "https://api.example.com/v1/pets/{{random:1-1000}}?fields=name%2Ctag"
+        );
+
+        // 操作级参数覆盖路径级参数，示例值会被编码
+        let delete = named(&commands, "deletePet");
+        assert_eq!(delete.method, "DELETE");
+        assert_eq!(delete.url, "https://api.example.com/v1/pets/a%20b");
+    }
+
+    #[test]
+    fn test_request_bodies() {
+        let commands = commands(&OpenApiOptions::default());
+
+        let create = named(&commands, "createPet");
+        assert_eq!(create.headers.get("Content-Type"), Some("application/json"));
+        assert_eq!(
+            create.body.as_deref().unwrap(),
+            r#"{"name":"name-{{random:1-100000}}","tag":"dog","id":"{{uuid}}","age":{{random:1-20}},"vaccinated":{{choice:true,false}},"born":"{{timestamp:date}}","owners":["user{{random:1-100000}}@example.com"]}"#
+        );
+
+        let login = named(&commands, "login");
+        assert_eq!(
+            login.headers.get("Content-Type"),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(
+            login.body.as_deref().unwrap(),
+            "user=demo&remember={{choice:true,false}}"
+        );
+    }
+
+    #[test]
+    fn test_selection_and_server_override() {
+        let options = OpenApiOptions {
+            server: Some("http://localhost:8080/".to_string()),
+            tags: vec!["admin".to_string()],
+            operations: Vec::new(),
+        };
+        let names: Vec<_> = commands(&options)
+            .into_iter()
+            .map(|cmd| (cmd.name.unwrap(), cmd.url))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                (
+                    "createPet".to_string(),
+                    "http://localhost:8080/pets".to_string()
+                ),
+                (
+                    "deletePet".to_string(),
+                    "http://localhost:8080/pets/a%20b".to_string()
+                ),
+            ]
+        );
+
+        let options = OpenApiOptions {
+            tags: vec!["pets".to_string()],
+            operations: vec!["createPet".to_string(), "login".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(commands(&options).len(), 1);
+
+        let doc: Value = serde_yaml::from_str(SPEC).unwrap();
+        let options = OpenApiOptions {
+            operations: vec!["missing".to_string()],
+            ..Default::default()
+        };
+        assert!(parse_openapi(&doc, &options).is_err());
+    }
+
+    #[test]
+    fn test_relative_server_requires_override() {
+        let doc: Value =
+            serde_yaml::from_str("openapi: 3.1.0\nservers: [{url: /api}]\npaths: {}").unwrap();
+        let err = parse_openapi(&doc, &OpenApiOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("--openapi-server"));
+
+        let doc: Value = serde_yaml::from_str("swagger: '2.0'\npaths: {}").unwrap();
+        assert!(parse_openapi(&doc, &OpenApiOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,617 @@
+use crate::curl_parser::{base64_encode, CurlCommand};
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::path::Path;
+
+/// Postman 动态变量与内置模板函数的对应关系
+const DYNAMIC_VARIABLES: &[(&str, &str)] = &[
+    ("{{$guid}}", "{{uuid}}"),
+    ("{{$randomUUID}}", "{{uuid}}"),
+    ("{{$timestamp}}", "{{timestamp}}"),
+    ("{{$isoTimestamp}}", "{{timestamp:rfc3339}}"),
+    ("{{$randomInt}}", "{{random:0-1000}}"),
+];
+
+/// multipart 请求体使用的固定分隔符
+const FORM_BOUNDARY: &str = "----quickurlFormBoundary7MA4YWxkTrZu0gW";
+
+/// 导入的 Postman 集合
+pub struct PostmanImport {
+    /// 每个请求一条命令，以 `文件夹 / 请求名` 命名
+    pub commands: Vec<CurlCommand>,
+    /// 集合变量（环境中的同名变量优先），注册为模板变量
+    pub variables: Vec<(String, String)>,
+}
+
+/// 从 Postman 集合（v2.0 / v2.1）和可选的环境文件导入请求
+///
+/// URL、请求头和请求体中的 `{{var}}` 保留给模板引擎按变量替换；只有认证信息中的变量
+/// 在导入时替换（Basic 认证要先拼接再编码）。`{{$guid}}`、`{{$timestamp}}` 等动态变量
+/// 换成对应的模板函数。
+pub fn parse_postman_file(collection: &Path, environment: Option<&Path>) -> Result<PostmanImport> {
+    let collection = read_json(collection, "Postman collection")?;
+    let environment = environment
+        .map(|path| read_json(path, "Postman environment"))
+        .transpose()?;
+    parse_postman(&collection, environment.as_ref())
+}
+
+fn read_json(path: &Path, what: &str) -> Result<Value> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read {} {}: {}", what, path.display(), e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| anyhow!("Invalid {} {}: {}", what, path.display(), e))
+}
+
+fn parse_postman(collection: &Value, environment: Option<&Value>) -> Result<PostmanImport> {
+    let items = collection
+        .get("item")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("Postman collection has no items"))?;
+
+    let mut variables = Vec::new();
+    collect_variables(
+        collection.get("variable"),
+        "disabled",
+        false,
+        &mut variables,
+    );
+    if let Some(environment) = environment {
+        collect_variables(environment.get("values"), "enabled", true, &mut variables);
+    }
+    let variables = resolve_nested(variables);
+
+    let mut commands = Vec::new();
+    walk(
+        items,
+        &[],
+        collection.get("auth"),
+        &variables,
+        &mut commands,
+    )?;
+
+    if commands.is_empty() {
+        return Err(anyhow!("Postman collection contains no requests"));
+    }
+
+    Ok(PostmanImport {
+        commands,
+        variables,
+    })
+}
+
+/// 读取 `[{key, value}]` 形式的变量；`flag` 为 `disabled`（集合）或 `enabled`（环境）
+fn collect_variables(
+    list: Option<&Value>,
+    flag: &str,
+    enabled_when: bool,
+    variables: &mut Vec<(String, String)>,
+) {
+    for entry in list.and_then(Value::as_array).into_iter().flatten() {
+        let active = entry
+            .get(flag)
+            .and_then(Value::as_bool)
+            .is_none_or(|value| value == enabled_when);
+        let Some(key) = entry.get("key").and_then(Value::as_str) else {
+            continue;
+        };
+        if !active {
+            continue;
+        }
+        let value = entry.get("value").map(text).unwrap_or_default();
+        variables.retain(|(existing, _)| existing != key);
+        variables.push((key.to_string(), value));
+    }
+}
+
+/// 变量值可以引用其他变量（如 `{{host}}/v1`），在导入时展开
+fn resolve_nested(variables: Vec<(String, String)>) -> Vec<(String, String)> {
+    variables
+        .iter()
+        .map(|(key, value)| (key.clone(), substitute(value, &variables)))
+        .collect()
+}
+
+/// 替换文本中已知的 `{{var}}`，未知的保持原样；嵌套引用最多展开几层
+pub fn substitute(text: &str, variables: &[(String, String)]) -> String {
+    let mut result = text.to_string();
+    for _ in 0..5 {
+        let mut next = result.clone();
+        for (key, value) in variables {
+            next = next.replace(&format!("{{{{{}}}}}", key), value);
+        }
+        if next == result {
+            break;
+        }
+        result = next;
+    }
+    result
+}
+
+fn map_dynamic(text: &str) -> String {
+    DYNAMIC_VARIABLES
+        .iter()
+        .fold(text.to_string(), |text, (from, to)| text.replace(from, to))
+}
+
+/// 遍历文件夹，子项继承上层的认证设置
+fn walk(
+    items: &[Value],
+    folders: &[&str],
+    inherited_auth: Option<&Value>,
+    variables: &[(String, String)],
+    commands: &mut Vec<CurlCommand>,
+) -> Result<()> {
+    for item in items {
+        let name = item.get("name").and_then(Value::as_str).unwrap_or("");
+        let auth = match item.get("auth") {
+            Some(auth) if auth_type(auth) != Some("inherit") => Some(auth),
+            _ => inherited_auth,
+        };
+
+        if let Some(children) = item.get("item").and_then(Value::as_array) {
+            let mut path = folders.to_vec();
+            path.push(name);
+            walk(children, &path, auth, variables, commands)?;
+        } else if let Some(request) = item.get("request") {
+            let mut cmd = to_command(request, auth, variables)
+                .map_err(|e| anyhow!("Postman request `{}`: {}", name, e))?;
+            let mut path = folders.to_vec();
+            path.push(name);
+            cmd.name = Some(path.join(" / "));
+            commands.push(cmd);
+        }
+    }
+    Ok(())
+}
+
+fn to_command(
+    request: &Value,
+    inherited_auth: Option<&Value>,
+    variables: &[(String, String)],
+) -> Result<CurlCommand> {
+    // 请求可以直接写成 URL 字符串
+    let (url, method) = match request {
+        Value::String(url) => (url.clone(), "GET"),
+        _ => (
+            request.get("url").map(url_of).unwrap_or_default(),
+            request
+                .get("method")
+                .and_then(Value::as_str)
+                .unwrap_or("GET"),
+        ),
+    };
+    if url.is_empty() {
+        return Err(anyhow!("missing URL"));
+    }
+    // Postman 允许省略协议
+    let mut url = map_dynamic(&url);
+    if !url.contains("://") && !url.starts_with("{{") {
+        url = format!("http://{}", url);
+    }
+
+    let mut cmd = CurlCommand::new(url);
+    cmd.method = method.to_uppercase();
+
+    for header in request
+        .get("header")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        if is_disabled(header) {
+            continue;
+        }
+        let Some(key) = header.get("key").and_then(Value::as_str) else {
+            continue;
+        };
+        let value = header.get("value").map(text).unwrap_or_default();
+        cmd.headers.append(key, map_dynamic(&value));
+    }
+
+    let auth = match request.get("auth") {
+        Some(auth) if auth_type(auth) != Some("inherit") => Some(auth),
+        _ => inherited_auth,
+    };
+    if let Some(auth) = auth {
+        apply_auth(&mut cmd, auth, variables);
+    }
+
+    if let Some(body) = request.get("body") {
+        apply_body(&mut cmd, body)?;
+    }
+
+    Ok(cmd)
+}
+
+fn is_disabled(entry: &Value) -> bool {
+    entry
+        .get("disabled")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// URL 可以是字符串，或带 `raw` / `host` / `path` / `query` 的对象
+fn url_of(url: &Value) -> String {
+    if let Value::String(raw) = url {
+        return raw.clone();
+    }
+    if let Some(raw) = url.get("raw").and_then(Value::as_str) {
+        return raw.to_string();
+    }
+
+    let join = |key: &str, sep: &str| match url.get(key) {
+        Some(Value::Array(parts)) => parts.iter().map(text).collect::<Vec<_>>().join(sep),
+        Some(value) => text(value),
+        None => String::new(),
+    };
+    let mut result = String::new();
+    if let Some(protocol) = url.get("protocol").and_then(Value::as_str) {
+        result.push_str(protocol);
+        result.push_str("://");
+    }
+    result.push_str(&join("host", "."));
+    if let Some(port) = url.get("port") {
+        result.push(':');
+        result.push_str(&text(port));
+    }
+    let path = join("path", "/");
+    if !path.is_empty() {
+        result.push('/');
+        result.push_str(path.trim_start_matches('/'));
+    }
+    let query: Vec<String> = url
+        .get("query")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|param| !is_disabled(param))
+        .filter_map(|param| {
+            let key = param.get("key").and_then(Value::as_str)?;
+            let value = param.get("value").map(text).unwrap_or_default();
+            Some(format!("{}={}", key, value))
+        })
+        .collect();
+    if !query.is_empty() {
+        result.push('?');
+        result.push_str(&query.join("&"));
+    }
+    result
+}
+
+fn auth_type(auth: &Value) -> Option<&str> {
+    auth.get("type").and_then(Value::as_str)
+}
+
+/// 认证参数：v2.1 是 `[{key, value}]` 数组，v2.0 是对象
+fn auth_param(auth: &Value, kind: &str, key: &str) -> Option<String> {
+    match auth.get(kind)? {
+        Value::Array(params) => params
+            .iter()
+            .find(|param| param.get("key").and_then(Value::as_str) == Some(key))
+            .and_then(|param| param.get("value"))
+            .map(text),
+        Value::Object(params) => params.get(key).map(text),
+        _ => None,
+    }
+}
+
+/// bearer、basic 和 apikey 认证；noauth 和其他类型不添加任何内容
+fn apply_auth(cmd: &mut CurlCommand, auth: &Value, variables: &[(String, String)]) {
+    let param = |kind: &str, key: &str| {
+        auth_param(auth, kind, key)
+            .map(|value| substitute(&value, variables))
+            .unwrap_or_default()
+    };
+
+    match auth_type(auth) {
+        Some("bearer") => {
+            let token = param("bearer", "token");
+            cmd.headers
+                .set_default("Authorization", format!("Bearer {}", token));
+        }
+        Some("basic") => {
+            let credentials = format!(
+                "{}:{}",
+                param("basic", "username"),
+                param("basic", "password")
+            );
+            cmd.headers.set_default(
+                "Authorization",
+                format!("Basic {}", base64_encode(&credentials)),
+            );
+        }
+        Some("apikey") => {
+            let key = match param("apikey", "key") {
+                key if key.is_empty() => "X-API-Key".to_string(),
+                key => key,
+            };
+            let value = param("apikey", "value");
+            if auth_param(auth, "apikey", "in").as_deref() == Some("query") {
+                let sep = if cmd.url.contains('?') { '&' } else { '?' };
+                cmd.url = format!("{}{}{}={}", cmd.url, sep, key, value);
+            } else {
+                cmd.headers.set_default(&key, value);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// raw、urlencoded、formdata（仅文本字段）和 graphql 请求体
+fn apply_body(cmd: &mut CurlCommand, body: &Value) -> Result<()> {
+    let mode = body.get("mode").and_then(Value::as_str).unwrap_or("raw");
+    let fields = |key: &str| -> Vec<(String, String)> {
+        body.get(key)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter(|field| !is_disabled(field))
+            .filter(|field| field.get("type").and_then(Value::as_str) != Some("file"))
+            .filter_map(|field| {
+                let key = field.get("key").and_then(Value::as_str)?;
+                let value = field.get("value").map(text).unwrap_or_default();
+                Some((key.to_string(), map_dynamic(&value)))
+            })
+            .collect()
+    };
+
+    let (content_type, payload) = match mode {
+        "raw" => {
+            let Some(raw) = body.get("raw").and_then(Value::as_str) else {
+                return Ok(());
+            };
+            let language = body
+                .pointer("/options/raw/language")
+                .and_then(Value::as_str)
+                .unwrap_or("text");
+            let content_type = match language {
+                "json" => "application/json",
+                "xml" => "application/xml",
+                "html" => "text/html",
+                "javascript" => "application/javascript",
+                _ => "text/plain",
+            };
+            (content_type.to_string(), map_dynamic(raw))
+        }
+        "urlencoded" => {
+            let pairs: Vec<String> = fields("urlencoded")
+                .iter()
+                .map(|(key, value)| format!("{}={}", form_encode(key), form_encode(value)))
+                .collect();
+            (
+                "application/x-www-form-urlencoded".to_string(),
+                pairs.join("&"),
+            )
+        }
+        "formdata" => {
+            let mut payload = String::new();
+            for (key, value) in fields("formdata") {
+                payload.push_str(&format!(
+                    "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                    FORM_BOUNDARY, key, value
+                ));
+            }
+            payload.push_str(&format!("--{}--\r\n", FORM_BOUNDARY));
+            (
+                format!("multipart/form-data; boundary={}", FORM_BOUNDARY),
+                payload,
+            )
+        }
+        "graphql" => {
+            let graphql = body.get("graphql").cloned().unwrap_or(Value::Null);
+            let query = graphql.get("query").map(text).unwrap_or_default();
+            // variables 在集合中保存为 JSON 文本
+            let variables = match graphql.get("variables") {
+                Some(Value::String(s)) if !s.trim().is_empty() => serde_json::from_str(s)
+                    .map_err(|e| anyhow!("invalid GraphQL variables: {}", e))?,
+                Some(Value::Object(map)) => Value::Object(map.clone()),
+                _ => Value::Object(Default::default()),
+            };
+            let payload = serde_json::json!({ "query": query, "variables": variables });
+            (
+                "application/json".to_string(),
+                map_dynamic(&payload.to_string()),
+            )
+        }
+        // file / binary 等无法从集合中还原
+        _ => return Ok(()),
+    };
+
+    cmd.headers.set_default("Content-Type", content_type);
+    cmd.body = Some(payload);
+    Ok(())
+}
+
+/// 表单编码，保留 `{{...}}` 模板占位符不编码
+fn form_encode(s: &str) -> String {
+    let mut result = String::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        result.extend(url::form_urlencoded::byte_serialize(
+            &rest.as_bytes()[..start],
+        ));
+        result.push_str(&rest[start..start + len + 2]);
+        rest = &rest[start + len + 2..];
+    }
+    result.extend(url::form_urlencoded::byte_serialize(rest.as_bytes()));
+    result
+}
+
+/// 变量、请求头等的值可能是字符串、数字或布尔值
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLLECTION: &str = r#"{
+      "info": {"name": "Shop", "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"},
+      "auth": {"type": "bearer", "bearer": [{"key": "token", "value": "{{token}}", "type": "string"}]},
+      "variable": [
+        {"key": "baseUrl", "value": "https://{{host}}/v1"},
+        {"key": "host", "value": "shop.example.com"},
+        {"key": "token", "value": "collection-token"},
+        {"key": "unused", "value": "x", "disabled": true}
+      ],
+      "item": [
+        {
+          "name": "Products",
+          "item": [
+            {
+              "name": "List products",
+              "request": {
+                "method": "GET",
+                "header": [
+                  {"key": "Accept", "value": "application/json"},
+                  {"key": "X-Debug", "value": "1", "disabled": true}
+                ],
+                "url": {"raw": "{{baseUrl}}/products?page={{$randomInt}}", "host": ["{{baseUrl}}"], "path": ["products"]}
+              }
+            },
+            {
+              "name": "Create product",
+              "request": {
+                "method": "POST",
+                "header": [],
+                "body": {"mode": "raw", "raw": "{\"id\": \"{{$guid}}\", \"name\": \"{{name}}\"}", "options": {"raw": {"language": "json"}}},
+                "url": {"host": ["{{baseUrl}}"], "path": ["products"], "query": [{"key": "draft", "value": "true"}, {"key": "x", "value": "1", "disabled": true}]}
+              }
+            }
+          ]
+        },
+        {
+          "name": "Login",
+          "auth": {"type": "basic", "basic": [{"key": "username", "value": "demo"}, {"key": "password", "value": "{{password}}"}]},
+          "request": {
+            "method": "POST",
+            "body": {"mode": "urlencoded", "urlencoded": [{"key": "remember me", "value": "yes & more"}, {"key": "ts", "value": "{{$timestamp}}"}]},
+            "url": "{{baseUrl}}/login"
+          }
+        },
+        {
+          "name": "Health",
+          "request": {"method": "GET", "auth": {"type": "noauth"}, "url": "status.example.com/health"}
+        },
+        {
+          "name": "Search",
+          "request": {
+            "method": "POST",
+            "auth": {"type": "apikey", "apikey": [{"key": "key", "value": "api_key"}, {"key": "value", "value": "k"}, {"key": "in", "value": "query"}]},
+            "body": {"mode": "graphql", "graphql": {"query": "{ products { id } }", "variables": "{\"first\": 10}"}},
+            "url": "{{baseUrl}}/graphql"
+          }
+        }
+      ]
+    }"#;
+
+    const ENVIRONMENT: &str = r#"{
+      "name": "Staging",
+      "values": [
+        {"key": "host", "value": "staging.example.com", "enabled": true},
+        {"key": "token", "value": "env-token", "enabled": true},
+        {"key": "password", "value": "secret", "enabled": true},
+        {"key": "name", "value": "ignored", "enabled": false}
+      ]
+    }"#;
+
+    fn import() -> PostmanImport {
+        let collection: Value = serde_json::from_str(COLLECTION).unwrap();
+        let environment: Value = serde_json::from_str(ENVIRONMENT).unwrap();
+        parse_postman(&collection, Some(&environment)).unwrap()
+    }
+
+    #[test]
+    fn test_items_folders_and_variables() {
+        let import = import();
+        let names: Vec<_> = import
+            .commands
+            .iter()
+            .map(|cmd| cmd.name.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "Products / List products",
+                "Products / Create product",
+                "Login",
+                "Health",
+                "Search",
+            ]
+        );
+
+        // 环境变量覆盖集合变量，嵌套引用在导入时展开
+        let get = |key: &str| {
+            import
+                .variables
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(get("baseUrl"), Some("https://staging.example.com/v1"));
+        assert_eq!(get("token"), Some("env-token"));
+        assert_eq!(get("unused"), None);
+        assert_eq!(get("name"), None);
+
+        let list = &import.commands[0];
+        assert_eq!(list.url, "{{baseUrl}}/products?page={{random:0-1000}}");
+        assert_eq!(
+            list.headers.iter().collect::<Vec<_>>(),
+            [
+                ("Accept", "application/json"),
+                ("Authorization", "Bearer env-token"),
+            ]
+        );
+
+        let create = &import.commands[1];
+        assert_eq!(create.method, "POST");
+        assert_eq!(create.url, "{{baseUrl}}/products?draft=true");
+        assert_eq!(create.headers.get("Content-Type"), Some("application/json"));
+        assert_eq!(
+            create.body.as_deref(),
+            Some(r#"{"id": "{{uuid}}", "name": "{{name}}"}"#)
+        );
+    }
+
+    #[test]
+    fn test_auth_and_body_modes() {
+        let import = import();
+
+        let login = &import.commands[2];
+        assert_eq!(
+            login.headers.get("Authorization"),
+            Some(format!("Basic {}", base64_encode("demo:secret")).as_str())
+        );
+        assert_eq!(
+            login.body.as_deref(),
+            Some("remember+me=yes+%26+more&ts={{timestamp}}")
+        );
+
+        let health = &import.commands[3];
+        assert_eq!(health.url, "http://status.example.com/health");
+        assert!(health.headers.is_empty());
+
+        let search = &import.commands[4];
+        assert_eq!(search.url, "{{baseUrl}}/graphql?api_key=k");
+        assert!(!search.headers.contains("Authorization"));
+        let body: Value = serde_json::from_str(search.body.as_deref().unwrap()).unwrap();
+        assert_eq!(body["query"], "{ products { id } }");
+        assert_eq!(body["variables"]["first"], 10);
+    }
+
+    #[test]
+    fn test_empty_collection() {
+        let collection: Value = serde_json::from_str(r#"{"info": {}, "item": []}"#).unwrap();
+        assert!(parse_postman(&collection, None).is_err());
+        assert!(parse_postman(&Value::Null, None).is_err());
+    }
+}
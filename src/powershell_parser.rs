@@ -0,0 +1,537 @@
+use crate::curl_parser::CurlCommand;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// 发送请求的 cmdlet 及其别名
+const REQUEST_CMDLETS: &[&str] = &["invoke-webrequest", "invoke-restmethod", "iwr", "irm"];
+
+/// Chrome 旧版导出中混在 -Headers 里的 HTTP/2 伪首部（不带冒号）
+const PSEUDO_HEADERS: &[&str] = &["authority", "method", "path", "scheme"];
+
+/// 解析一条 PowerShell 请求（devtools "Copy as PowerShell"）
+pub fn parse_powershell(text: &str) -> Result<CurlCommand> {
+    let mut requests = parse_powershell_script(text)?;
+    match requests.len() {
+        0 => Err(anyhow!(
+            "No Invoke-WebRequest / Invoke-RestMethod call found"
+        )),
+        1 => requests.remove(0).1,
+        n => Err(anyhow!(
+            "Expected a single PowerShell request but found {} (use --parse-curl-file for several)",
+            n
+        )),
+    }
+}
+
+/// 解析 PowerShell 脚本中的所有请求（"Copy all as PowerShell"），返回所在行号和结果
+///
+/// 支持 `Invoke-WebRequest` / `Invoke-RestMethod`（及 `iwr` / `irm`）的常用参数、
+/// `@{...}` 请求头表、反引号续行和转义，以及 `$session` 中设置的 UserAgent 和 Cookie。
+pub fn parse_powershell_script(text: &str) -> Result<Vec<(usize, Result<CurlCommand>)>> {
+    let statements = Lexer::new(text).statements()?;
+    let mut sessions: HashMap<String, Session> = HashMap::new();
+    let mut requests = Vec::new();
+
+    for (line, tokens) in statements {
+        let Some(Token::Word(first)) = tokens.first() else {
+            continue;
+        };
+        if REQUEST_CMDLETS.contains(&first.to_lowercase().as_str()) {
+            requests.push((line, to_command(&tokens[1..], &sessions)));
+            continue;
+        }
+        // `$session.UserAgent = "..."`、`$session.Cookies.Add(...)`
+        let Some((variable, member)) = first.strip_prefix('$').and_then(|v| v.split_once('.'))
+        else {
+            continue;
+        };
+        let session = sessions.entry(variable.to_lowercase()).or_default();
+        match member.to_lowercase().as_str() {
+            "useragent" => {
+                if let [_, Token::Equals, Token::Str(agent), ..] = tokens.as_slice() {
+                    session.user_agent = Some(agent.clone());
+                }
+            }
+            "cookies.add" => {
+                // `New-Object System.Net.Cookie("name", "value", "/", "domain")`
+                let strings = strings_in(&tokens[1..]);
+                if let [name, value, ..] = strings.as_slice() {
+                    session.cookies.push(format!("{}={}", name, value));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(requests)
+}
+
+/// `New-Object Microsoft.PowerShell.Commands.WebRequestSession` 中记录的状态
+#[derive(Default)]
+struct Session {
+    user_agent: Option<String>,
+    cookies: Vec<String>,
+}
+
+fn to_command(args: &[Token], sessions: &HashMap<String, Session>) -> Result<CurlCommand> {
+    let mut url: Option<String> = None;
+    let mut method: Option<String> = None;
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut content_type: Option<String> = None;
+    let mut user_agent: Option<String> = None;
+    let mut body: Option<String> = None;
+    let mut session: Option<&Session> = None;
+    let mut insecure = false;
+    let mut max_redirects: Option<usize> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        let value = args.get(i + 1);
+        let name = match &args[i] {
+            Token::Word(word) if word.starts_with('-') => word[1..].to_lowercase(),
+            // 第一个位置参数是 -Uri
+            other => {
+                if url.is_none() {
+                    url = other.as_string();
+                }
+                i += 1;
+                continue;
+            }
+        };
+        i += 2;
+        match name.as_str() {
+            "uri" => url = value.and_then(Token::as_string),
+            "method" => method = value.and_then(Token::as_string),
+            "contenttype" => content_type = value.and_then(Token::as_string),
+            "useragent" => user_agent = value.and_then(Token::as_string),
+            "body" => {
+                // `-Body ([System.Text.Encoding]::UTF8.GetBytes("..."))`
+                body = value.and_then(|token| strings_in(std::slice::from_ref(token)).pop())
+            }
+            "headers" => match value {
+                Some(Token::Hash(entries)) => headers = entries.clone(),
+                _ => return Err(anyhow!("-Headers must be a @{{...}} hashtable")),
+            },
+            "websession" => {
+                session = value
+                    .and_then(Token::as_word)
+                    .and_then(|v| v.strip_prefix('$'))
+                    .and_then(|v| sessions.get(&v.to_lowercase()))
+            }
+            "maximumredirection" => {
+                max_redirects = value
+                    .and_then(Token::as_string)
+                    .and_then(|v| v.parse().ok())
+            }
+            "skipcertificatecheck" => {
+                insecure = true;
+                i -= 1;
+            }
+            // 其余是不带值的开关（-UseBasicParsing 等）
+            _ => i -= 1,
+        }
+    }
+
+    let url = url.ok_or_else(|| anyhow!("missing -Uri"))?;
+    let mut cmd = CurlCommand::new(url);
+    cmd.method = method.map_or_else(|| "GET".to_string(), |m| m.to_uppercase());
+    for (name, value) in headers {
+        if !PSEUDO_HEADERS.contains(&name.to_lowercase().as_str()) {
+            cmd.headers.append(name, value);
+        }
+    }
+    if let Some(content_type) = content_type {
+        cmd.headers.set_default("Content-Type", content_type);
+    }
+    if let Some(agent) = user_agent.or_else(|| session.and_then(|s| s.user_agent.clone())) {
+        cmd.headers.set_default("User-Agent", agent);
+    }
+    if let Some(session) = session.filter(|s| !s.cookies.is_empty()) {
+        cmd.headers
+            .set_default("Cookie", session.cookies.join("; "));
+    }
+    cmd.body = body;
+    cmd.transport.insecure = insecure;
+    // Invoke-WebRequest 默认跟随最多 5 次重定向
+    cmd.transport.follow_redirects = Some(max_redirects.unwrap_or(5)).filter(|&n| n > 0);
+
+    Ok(cmd)
+}
+
+/// 按出现顺序收集（包括括号内的）所有字符串
+fn strings_in(tokens: &[Token]) -> Vec<String> {
+    let mut strings = Vec::new();
+    for token in tokens {
+        match token {
+            Token::Str(s) => strings.push(s.clone()),
+            Token::Group(inner) => strings.extend(strings_in(inner)),
+            _ => {}
+        }
+    }
+    strings
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// 裸词：cmdlet、参数名、变量、类型等
+    Word(String),
+    /// 引号字符串（已处理转义）
+    Str(String),
+    /// `@{ name = value; ... }`
+    Hash(Vec<(String, String)>),
+    /// `( ... )`
+    Group(Vec<Token>),
+    Equals,
+    Comma,
+}
+
+impl Token {
+    fn as_string(&self) -> Option<String> {
+        match self {
+            Token::Str(s) | Token::Word(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+
+    fn as_word(&self) -> Option<&str> {
+        match self {
+            Token::Word(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Lexer {
+    fn new(text: &str) -> Self {
+        Self {
+            chars: text.chars().collect(),
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    /// 按换行和 `;` 拆分语句（反引号续行），返回每条语句的起始行号和词法单元
+    fn statements(mut self) -> Result<Vec<(usize, Vec<Token>)>> {
+        let mut statements = Vec::new();
+        loop {
+            let line = {
+                self.skip_blank(true);
+                self.line
+            };
+            if self.pos >= self.chars.len() {
+                return Ok(statements);
+            }
+            let tokens = self.tokens(None)?;
+            if !tokens.is_empty() {
+                statements.push((line, tokens));
+            }
+        }
+    }
+
+    /// 读取词法单元直到语句结束或遇到 `close`
+    fn tokens(&mut self, close: Option<char>) -> Result<Vec<Token>> {
+        let mut tokens = Vec::new();
+        loop {
+            // 括号内换行不结束语句
+            self.skip_blank(close.is_some());
+            let Some(c) = self.peek(0) else {
+                return match close {
+                    Some(close) => Err(anyhow!("missing `{}`", close)),
+                    None => Ok(tokens),
+                };
+            };
+            match c {
+                '\n' | ';' => {
+                    self.advance();
+                    return Ok(tokens);
+                }
+                c if Some(c) == close => {
+                    self.pos += 1;
+                    return Ok(tokens);
+                }
+                '"' | '\'' => tokens.push(Token::Str(self.string()?)),
+                '@' if matches!(self.peek(1), Some('"' | '\'')) => {
+                    tokens.push(Token::Str(self.here_string()?))
+                }
+                '@' if self.peek(1) == Some('{') => {
+                    self.pos += 2;
+                    tokens.push(Token::Hash(self.hashtable()?));
+                }
+                '(' => {
+                    self.pos += 1;
+                    tokens.push(Token::Group(self.tokens(Some(')'))?));
+                }
+                '=' => {
+                    self.pos += 1;
+                    tokens.push(Token::Equals);
+                }
+                ',' => {
+                    self.pos += 1;
+                    tokens.push(Token::Comma);
+                }
+                ')' | '}' => return Err(anyhow!("line {}: unexpected `{}`", self.line, c)),
+                _ => tokens.push(Token::Word(self.word())),
+            }
+        }
+    }
+
+    /// `@{` 之后的哈希表内容，键值对之间用换行或 `;` 分隔
+    fn hashtable(&mut self) -> Result<Vec<(String, String)>> {
+        let mut entries = Vec::new();
+        loop {
+            self.skip_blank(true);
+            while matches!(self.peek(0), Some(';')) {
+                self.pos += 1;
+                self.skip_blank(true);
+            }
+            match self.peek(0) {
+                None => return Err(anyhow!("missing `}}` in hashtable")),
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(entries);
+                }
+                _ => {}
+            }
+            let key = self.scalar()?;
+            self.skip_blank(false);
+            if self.peek(0) != Some('=') {
+                return Err(anyhow!("line {}: expected `=` after `{}`", self.line, key));
+            }
+            self.pos += 1;
+            self.skip_blank(false);
+            let value = self.scalar()?;
+            entries.push((key, value));
+        }
+    }
+
+    fn scalar(&mut self) -> Result<String> {
+        match self.peek(0) {
+            Some('"' | '\'') => self.string(),
+            _ => {
+                let word = self.word();
+                if word.is_empty() {
+                    return Err(anyhow!("line {}: expected a value", self.line));
+                }
+                Ok(word)
+            }
+        }
+    }
+
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.peek(0) {
+            if c.is_whitespace() || "();,={}\"'".contains(c) {
+                break;
+            }
+            // `[Type]::Member` 中的方括号属于同一个词
+            if c == '`' {
+                if let Some(next) = self.peek(1) {
+                    self.pos += 2;
+                    word.push(next);
+                    continue;
+                }
+            }
+            word.push(c);
+            self.pos += 1;
+        }
+        if word.is_empty() {
+            // 无法识别的单个字符，避免死循环
+            if let Some(c) = self.peek(0) {
+                word.push(c);
+                self.pos += 1;
+            }
+        }
+        word
+    }
+
+    /// 双引号字符串（反引号转义、`""`）或单引号字符串（`''`）
+    fn string(&mut self) -> Result<String> {
+        let quote = self.chars[self.pos];
+        let start_line = self.line;
+        self.pos += 1;
+        let mut result = String::new();
+        while let Some(c) = self.peek(0) {
+            self.advance();
+            if c == quote {
+                // 连续两个引号表示引号本身
+                if self.peek(0) == Some(quote) {
+                    self.pos += 1;
+                    result.push(quote);
+                    continue;
+                }
+                return Ok(result);
+            }
+            if c == '`' && quote == '"' {
+                let Some(escaped) = self.peek(0) else { break };
+                self.advance();
+                result.push(match escaped {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    '0' => '\0',
+                    'a' => '\u{7}',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'v' => '\u{b}',
+                    'e' => '\u{1b}',
+                    other => other,
+                });
+                continue;
+            }
+            result.push(c);
+        }
+        Err(anyhow!("line {}: unterminated string", start_line))
+    }
+
+    /// `@"` ... `"@` here-string（内容从下一行开始，到单独一行的 `"@` 结束）
+    fn here_string(&mut self) -> Result<String> {
+        let quote = self.chars[self.pos + 1];
+        let start_line = self.line;
+        self.pos += 2;
+        while let Some(c) = self.peek(0) {
+            self.advance();
+            if c == '\n' {
+                break;
+            }
+        }
+        let mut lines = Vec::new();
+        let mut current = String::new();
+        while let Some(c) = self.peek(0) {
+            self.advance();
+            if c == '\n' {
+                lines.push(std::mem::take(&mut current));
+                continue;
+            }
+            current.push(c);
+            if current == format!("{}@", quote) {
+                return Ok(lines.join("\n").trim_end_matches('\r').to_string());
+            }
+        }
+        Err(anyhow!("line {}: unterminated here-string", start_line))
+    }
+
+    /// 跳过空白、注释和反引号续行；`newlines` 为真时也跳过换行
+    fn skip_blank(&mut self, newlines: bool) {
+        while let Some(c) = self.peek(0) {
+            match c {
+                '\n' if newlines => self.advance(),
+                '\n' => return,
+                '`' if matches!(self.peek(1), Some('\n')) => {
+                    self.pos += 1;
+                    self.advance();
+                }
+                '`' if self.peek(1) == Some('\r') && self.peek(2) == Some('\n') => {
+                    self.pos += 2;
+                    self.advance();
+                }
+                '#' => {
+                    while self.peek(0).is_some_and(|c| c != '\n') {
+                        self.pos += 1;
+                    }
+                }
+                c if c.is_whitespace() => self.advance(),
+                _ => return,
+            }
+        }
+    }
+
+    fn advance(&mut self) {
+        if self.peek(0) == Some('\n') {
+            self.line += 1;
+        }
+        self.pos += 1;
+    }
+
+    fn peek(&self, ahead: usize) -> Option<char> {
+        self.chars.get(self.pos + ahead).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chrome_copy_as_powershell() {
+        let snippet = "$session = New-Object Microsoft.PowerShell.Commands.WebRequestSession\r
+$session.UserAgent = \"Mozilla/5.0 (Windows NT 10.0; Win64; x64)\"\r
+$session.Cookies.Add((New-Object System.Net.Cookie(\"sid\", \"abc123\", \"/\", \"api.example.com\")))\r
+$session.Cookies.Add((New-Object System.Net.Cookie(\"theme\", \"dark\", \"/\", \"api.example.com\")))\r
+Invoke-WebRequest -UseBasicParsing -Uri \"https://api.example.com/v1/items\" `\r
+-Method \"POST\" `\r
+-WebSession $session `\r
+-Headers @{\r
+\"authority\"=\"api.example.com\"\r
+  \"method\"=\"POST\"\r
+  \"accept\"=\"application/json\"\r
+  \"x-price\"=\"`$5\"\r
+} `\r
+-ContentType \"application/json\" `\r
+-Body \"{`\"name`\":`\"it's`\",`\"n`\":1}\"\r
+";
+        let cmd = parse_powershell(snippet).unwrap();
+        assert_eq!(cmd.url, "https://api.example.com/v1/items");
+        assert_eq!(cmd.method, "POST");
+        assert_eq!(cmd.body.as_deref(), Some(r#"{"name":"it's","n":1}"#));
+        assert_eq!(
+            cmd.headers.iter().collect::<Vec<_>>(),
+            [
+                ("accept", "application/json"),
+                ("x-price", "$5"),
+                ("Content-Type", "application/json"),
+                ("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64)"),
+                ("Cookie", "sid=abc123; theme=dark"),
+            ]
+        );
+        assert_eq!(cmd.transport.follow_redirects, Some(5));
+    }
+
+    #[test]
+    fn test_script_with_several_requests() {
+        let script = r#"# Copy all as PowerShell
+iwr 'https://example.com/it''s' -Headers @{ Accept = 'text/html'; "X-A" = "1" }
+Invoke-RestMethod -Uri "https://example.com/bytes" -Method Put -SkipCertificateCheck -MaximumRedirection 0 `
+  -Body ([System.Text.Encoding]::UTF8.GetBytes("caf$([char]233)"))
+$body = @"
+{"a": 1}
+"@
+Get-Date; irm -Uri "https://example.com/here" -Method POST -Body $body
+Invoke-WebRequest -Method GET
+"#;
+        let requests = parse_powershell_script(script).unwrap();
+        assert_eq!(requests.len(), 4);
+
+        let (line, first) = &requests[0];
+        assert_eq!(*line, 2);
+        let first = first.as_ref().unwrap();
+        assert_eq!(first.url, "https://example.com/it's");
+        assert_eq!(first.headers.get("accept"), Some("text/html"));
+        assert_eq!(first.headers.get("x-a"), Some("1"));
+
+        let put = requests[1].1.as_ref().unwrap();
+        assert_eq!(put.method, "PUT");
+        assert!(put.transport.insecure);
+        assert_eq!(put.transport.follow_redirects, None);
+        // 子表达式不会被求值，按字面保留
+        assert_eq!(put.body.as_deref(), Some("caf$([char]233)"));
+
+        // 变量引用的请求体无法还原
+        let here = requests[2].1.as_ref().unwrap();
+        assert_eq!(here.body, None);
+
+        assert!(requests[3].1.is_err());
+        assert_eq!(requests[3].0, 9);
+    }
+
+    #[test]
+    fn test_lexer_errors() {
+        assert!(parse_powershell_script("iwr \"https://example.com/").is_err());
+        assert!(parse_powershell("Get-Date").is_err());
+    }
+}
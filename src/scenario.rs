@@ -0,0 +1,568 @@
+use crate::cli::parse_duration_string;
+use crate::curl_parser::{parse_curl_command, CurlCommand};
+use crate::engine::{client_state_index, connection_pools, run_connections, BenchmarkConfig};
+use crate::http_client::{CapturedResponse, ClientState};
+use crate::stats::{FlowOutcome, RequestResult, SharedStats, Statistics};
+use crate::template::{CommandTemplate, TemplateEngine, TemplateState};
+use anyhow::{anyhow, Result};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 场景文件（YAML 或 JSON）
+#[derive(Deserialize)]
+struct ScenarioFile {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    variables: BTreeMap<String, Value>,
+    steps: Vec<StepFile>,
+}
+
+#[derive(Deserialize)]
+struct StepFile {
+    #[serde(default)]
+    name: Option<String>,
+    curl: String,
+    /// 变量名 → `json:$.path`、`regex:pattern`、`header:Name`、`cookie:name`
+    #[serde(default)]
+    extract: BTreeMap<String, String>,
+    /// 步骤之后的思考时间：`500ms` 或 `100ms-2s`
+    #[serde(default)]
+    think: Option<String>,
+}
+
+/// 按顺序执行的多步骤用户流程
+pub struct Scenario {
+    pub name: Option<String>,
+    /// 场景文件中的固定变量，注册为模板变量（--var 可覆盖）
+    pub variables: Vec<(String, String)>,
+    pub steps: Vec<Step>,
+}
+
+pub struct Step {
+    /// 统计报告中的步骤名称（在场景内唯一）
+    pub name: String,
+    pub command: CurlCommand,
+    /// 从响应中提取、供后续步骤使用的变量
+    pub extract: Vec<(String, Extractor)>,
+    pub think: Option<ThinkTime>,
+}
+
+/// 从响应中提取一个值
+pub enum Extractor {
+    /// JSON 响应体中的路径（`$.items[0].id`）
+    Json(Vec<PathSegment>),
+    /// 响应体的正则匹配，有捕获组时取第一个捕获组
+    Regex(Regex),
+    /// 响应头（名称不区分大小写）
+    Header(String),
+    /// Set-Cookie 中的 cookie
+    Cookie(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// 步骤之后的停顿，在范围内均匀随机
+#[derive(Debug, PartialEq)]
+pub struct ThinkTime {
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl ThinkTime {
+    fn parse(text: &str) -> Result<Self> {
+        let (min, max) = match text.split_once('-') {
+            Some((min, max)) => (parse_duration_string(min)?, parse_duration_string(max)?),
+            None => {
+                let duration = parse_duration_string(text)?;
+                (duration, duration)
+            }
+        };
+        if min > max {
+            return Err(anyhow!("think time range {} is reversed", text));
+        }
+        Ok(Self { min, max })
+    }
+
+    fn sample(&self, rng: &mut impl Rng) -> Duration {
+        if self.min == self.max {
+            return self.min;
+        }
+        rng.gen_range(self.min..=self.max)
+    }
+}
+
+impl Extractor {
+    fn parse(definition: &str) -> Result<Self> {
+        let (kind, argument) = definition
+            .split_once(':')
+            .ok_or_else(|| anyhow!("expected json:, regex:, header: or cookie:"))?;
+        match kind.trim() {
+            "json" => Ok(Self::Json(parse_json_path(argument.trim())?)),
+            "regex" => Ok(Self::Regex(Regex::new(argument)?)),
+            "header" => Ok(Self::Header(argument.trim().to_string())),
+            "cookie" => Ok(Self::Cookie(argument.trim().to_string())),
+            other => Err(anyhow!("unknown extractor `{}`", other)),
+        }
+    }
+
+    /// 提取失败（路径不存在、没有匹配）时返回 None
+    fn extract(&self, response: &CapturedResponse, json: Option<&Value>) -> Option<String> {
+        match self {
+            Self::Json(path) => {
+                let value = path
+                    .iter()
+                    .try_fold(json?, |value, segment| match segment {
+                        PathSegment::Key(key) => value.get(key),
+                        PathSegment::Index(index) => value.get(index),
+                    })?;
+                match value {
+                    Value::String(text) => Some(text.clone()),
+                    Value::Null => None,
+                    other => Some(other.to_string()),
+                }
+            }
+            Self::Regex(regex) => {
+                let body = String::from_utf8_lossy(&response.body);
+                let caps = regex.captures(&body)?;
+                caps.get(1)
+                    .or_else(|| caps.get(0))
+                    .map(|m| m.as_str().to_string())
+            }
+            Self::Header(name) => response.header(name).map(str::to_string),
+            Self::Cookie(name) => response.cookies.iter().rev().find_map(|cookie| {
+                let pair = cookie.split(';').next()?;
+                let (key, value) = pair.split_once('=')?;
+                (key.trim() == name).then(|| value.trim().to_string())
+            }),
+        }
+    }
+}
+
+/// 解析 `$.a.b[0]["c d"]` 形式的 JSON 路径（`$` 可以省略）
+fn parse_json_path(path: &str) -> Result<Vec<PathSegment>> {
+    let invalid = || anyhow!("invalid JSON path `{}`", path);
+    let mut rest = path.strip_prefix('$').unwrap_or(path);
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or_else(invalid)?;
+            let inner = after[..end].trim();
+            let quoted = inner
+                .strip_prefix('"')
+                .and_then(|s| s.strip_suffix('"'))
+                .or_else(|| inner.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')));
+            segments.push(match quoted {
+                Some(key) => PathSegment::Key(key.to_string()),
+                None => PathSegment::Index(inner.parse().map_err(|_| invalid())?),
+            });
+            rest = &after[end + 1..];
+        } else {
+            let after = rest.strip_prefix('.').unwrap_or(rest);
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            if end == 0 {
+                return Err(invalid());
+            }
+            segments.push(PathSegment::Key(after[..end].to_string()));
+            rest = &after[end..];
+        }
+    }
+    Ok(segments)
+}
+
+/// 读取场景文件（`.json` 按 JSON 解析，其他按 YAML）
+///
+/// 每个步骤是一条 curl 命令，可以用 `extract` 从响应中提取变量，用 `think`
+/// 设置之后的停顿；URL、请求头和请求体中的 `{{变量}}` 使用本虚拟用户提取到的值。
+pub fn parse_scenario_file(path: &Path) -> Result<Scenario> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read scenario {}: {}", path.display(), e))?;
+    let file: ScenarioFile = if path.extension().and_then(|s| s.to_str()) == Some("json") {
+        serde_json::from_str(&content)?
+    } else {
+        serde_yaml::from_str(&content)?
+    };
+    build_scenario(file)
+}
+
+fn build_scenario(file: ScenarioFile) -> Result<Scenario> {
+    if file.steps.is_empty() {
+        return Err(anyhow!("Scenario has no steps"));
+    }
+
+    let mut steps: Vec<Step> = Vec::new();
+    for (index, step) in file.steps.into_iter().enumerate() {
+        let context = |e: anyhow::Error| anyhow!("step {}: {}", index + 1, e);
+        let command = parse_curl_command(&step.curl).map_err(context)?;
+        let name = step
+            .name
+            .or_else(|| command.name.clone())
+            .unwrap_or_else(|| format!("{} {}", command.method, command.url));
+        if steps.iter().any(|existing| existing.name == name) {
+            return Err(context(anyhow!(
+                "duplicate step name `{}` (give the steps distinct names)",
+                name
+            )));
+        }
+        let extract = step
+            .extract
+            .into_iter()
+            .map(|(variable, definition)| {
+                let extractor = Extractor::parse(&definition)
+                    .map_err(|e| anyhow!("extract `{}`: {}", variable, e))?;
+                Ok((variable, extractor))
+            })
+            .collect::<Result<Vec<_>>>()
+            .map_err(context)?;
+        let think = step
+            .think
+            .as_deref()
+            .map(ThinkTime::parse)
+            .transpose()
+            .map_err(context)?;
+        steps.push(Step {
+            name,
+            command,
+            extract,
+            think,
+        });
+    }
+
+    let variables = file
+        .variables
+        .into_iter()
+        .map(|(name, value)| match value {
+            Value::String(text) => (name, text),
+            other => (name, other.to_string()),
+        })
+        .collect();
+
+    Ok(Scenario {
+        name: file.name,
+        variables,
+        steps,
+    })
+}
+
+/// 每个连接是一个虚拟用户，循环执行整个流程
+///
+/// 每轮流程开始时清空提取的变量；某个步骤出错或提取失败时本轮流程失败，从第一步重新开始。
+pub fn run_workers(
+    scenario: Scenario,
+    config: &BenchmarkConfig,
+    template_engine: &TemplateEngine,
+    shared_stats: Option<SharedStats>,
+) -> Result<Statistics> {
+    let commands: Vec<CurlCommand> = scenario
+        .steps
+        .iter()
+        .map(|step| step.command.clone())
+        .collect();
+    let templates: Arc<Vec<CommandTemplate>> = Arc::new(
+        commands
+            .iter()
+            .map(|cmd| template_engine.compile_command(cmd))
+            .collect::<Result<_>>()?,
+    );
+    let (pools, pool_index) = connection_pools(&commands, config)?;
+    let pools = Arc::new(pools);
+    let (state_index, state_count) = client_state_index(&commands, &pool_index);
+    let pool_index = Arc::new(pool_index);
+    let state_index = Arc::new(state_index);
+    let steps = Arc::new(scenario.steps);
+    let rate = config.rate;
+
+    run_connections(config, shared_stats, move |ctx| {
+        let steps = steps.clone();
+        let templates = templates.clone();
+        let pool_index = pool_index.clone();
+        let state_index = state_index.clone();
+        let clients: Vec<_> = pools.iter().map(|pool| pool.get_client()).collect();
+
+        async move {
+            // 每个连接池中的每个来源一个客户端状态，登录与业务接口在不同主机时各用各的连接
+            let mut client_states: Vec<ClientState> =
+                (0..state_count).map(|_| ClientState::new()).collect();
+            // 思考时间和模板各用一个随机数生成器，由连接的种子导出
+            let mut rng = StdRng::seed_from_u64(ctx.seed);
+            let mut template_state = TemplateState::new(rng.gen());
+
+            'flow: while Instant::now() < ctx.end_time {
+                template_state.locals.clear();
+                // 一个流程中的所有步骤使用同一组变量值和数据文件的同一行
+                template_state.next_request();
+                let mut flow_latency = Duration::ZERO;
+
+                for (index, step) in steps.iter().enumerate() {
+                    // 测试结束时中断的流程不计入结果
+                    if Instant::now() >= ctx.end_time {
+                        break 'flow;
+                    }
+                    let template = &templates[index];
+                    let method = template.method.render(&mut template_state);
+                    let url = template.url.render(&mut template_state);
+                    let headers = template.headers(&mut template_state);
+                    let body = template
+                        .body
+                        .as_ref()
+                        .map(|b| b.render(&mut template_state));
+                    if template_state.exhausted {
+                        break 'flow;
+                    }
+
+                    let client = &clients[pool_index[index]];
+                    let client_state = &mut client_states[state_index[index]];
+                    let start = Instant::now();
+                    let result = client
+                        .request_capture(client_state, &method, &url, &headers, body.as_deref())
+                        .await;
+                    let duration = start.elapsed();
+                    flow_latency += duration;
+
+                    let (status_code, bytes_read, error) = match result {
+                        Ok((status, len_bytes, response)) => {
+                            let error =
+                                extract_variables(step, &response, &mut template_state.locals)
+                                    .err();
+                            (Some(status), len_bytes, error)
+                        }
+                        Err(e) => (None, 0, Some(e.to_string())),
+                    };
+                    let failed = error.is_some();
+                    let flow = (failed || index + 1 == steps.len()).then_some(FlowOutcome {
+                        latency: flow_latency,
+                        completed: !failed,
+                    });
+
+                    let _ = ctx.tx.send(RequestResult {
+                        duration,
+                        status_code,
+                        bytes_read,
+                        error,
+                        endpoint: Some(step.name.clone()),
+                        source_connects: std::mem::take(&mut client_state.source_connects),
+                        flow,
+                        ..Default::default()
+                    });
+
+                    // Rate limiting
+                    if rate > 0 {
+                        let delay = Duration::from_secs_f64(1.0 / rate as f64);
+                        tokio::time::sleep(delay).await;
+                    }
+                    if let Some(think) = &step.think {
+                        let due = (Instant::now() + think.sample(&mut rng)).min(ctx.end_time);
+                        tokio::time::sleep_until(due.into()).await;
+                    }
+                    if failed {
+                        continue 'flow;
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// 按步骤的 `extract` 提取变量；任何一个提取失败时返回错误信息
+fn extract_variables(
+    step: &Step,
+    response: &CapturedResponse,
+    locals: &mut HashMap<String, String>,
+) -> std::result::Result<(), String> {
+    if step.extract.is_empty() {
+        return Ok(());
+    }
+    let json: Option<Value> = step
+        .extract
+        .iter()
+        .any(|(_, extractor)| matches!(extractor, Extractor::Json(_)))
+        .then(|| serde_json::from_slice(&response.body).ok())
+        .flatten();
+    for (variable, extractor) in &step.extract {
+        match extractor.extract(response, json.as_ref()) {
+            Some(value) => {
+                locals.insert(variable.clone(), value);
+            }
+            None => return Err(format!("Failed to extract `{}`", variable)),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENARIO: &str = r#"
+name: checkout
+variables:
+  base: http://localhost:8080
+  page_size: 20
+steps:
+  - name: login
+    curl: >-
+      curl -X POST {{base}}/login -H 'Content-Type: application/json'
+      -d '{"user":"demo"}'
+    extract:
+      token: json:$.data.token
+      sid: cookie:session
+    think: 100ms-300ms
+  - curl: >-
+      curl '{{base}}/items?size={{page_size}}' -H 'Authorization: Bearer {{token}}'
+    extract:
+      item: json:items[0]["id"]
+      trace: header:x-trace-id
+  - name: item
+    curl: curl {{base}}/items/{{item}} -b 'session={{sid}}'
+    extract:
+      price: 'regex:"price":\s*([0-9.]+)'
+    think: 1s
+"#;
+
+    #[test]
+    fn test_parse_scenario() {
+        let file: ScenarioFile = serde_yaml::from_str(SCENARIO).unwrap();
+        let scenario = build_scenario(file).unwrap();
+        assert_eq!(scenario.name.as_deref(), Some("checkout"));
+        assert_eq!(
+            scenario.variables,
+            [
+                ("base".to_string(), "http://localhost:8080".to_string()),
+                ("page_size".to_string(), "20".to_string()),
+            ]
+        );
+
+        let names: Vec<_> = scenario.steps.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["login", "GET {{base}}/items?size={{page_size}}", "item"]
+        );
+
+        let login = &scenario.steps[0];
+        assert_eq!(login.command.method, "POST");
+        assert_eq!(login.command.body.as_deref(), Some(r#"{"user":"demo"}"#));
+        assert_eq!(
+            login.think,
+            Some(ThinkTime {
+                min: Duration::from_millis(100),
+                max: Duration::from_millis(300),
+            })
+        );
+        let variables: Vec<_> = login.extract.iter().map(|(v, _)| v.as_str()).collect();
+        assert_eq!(variables, ["sid", "token"]);
+        assert_eq!(
+            scenario.steps[2].command.headers.get("Cookie"),
+            Some("session={{sid}}")
+        );
+    }
+
+    #[test]
+    fn test_extractors() {
+        let response = CapturedResponse {
+            headers: vec![("x-trace-id".to_string(), "abc".to_string())],
+            cookies: vec![
+                "session=old; Path=/".to_string(),
+                "theme=dark".to_string(),
+                "session=s3cr3t; HttpOnly".to_string(),
+            ],
+            body: br#"{"data":{"token":"t-1"},"items":[{"id":42,"price": 9.5}],"none":null}"#
+                .to_vec(),
+        };
+        let json: Value = serde_json::from_slice(&response.body).unwrap();
+        let extract = |definition: &str| {
+            Extractor::parse(definition)
+                .unwrap()
+                .extract(&response, Some(&json))
+        };
+
+        assert_eq!(extract("json:$.data.token").as_deref(), Some("t-1"));
+        assert_eq!(extract("json:items[0].id").as_deref(), Some("42"));
+        assert_eq!(
+            extract("json:$['data']").as_deref(),
+            Some(r#"{"token":"t-1"}"#)
+        );
+        assert_eq!(extract("json:$.none"), None);
+        assert_eq!(extract("json:$.items[3]"), None);
+        assert_eq!(
+            extract(r#"regex:"price":\s*([0-9.]+)"#).as_deref(),
+            Some("9.5")
+        );
+        assert_eq!(extract("regex:t-\\d").as_deref(), Some("t-1"));
+        assert_eq!(extract("header:X-Trace-Id").as_deref(), Some("abc"));
+        assert_eq!(extract("cookie:session").as_deref(), Some("s3cr3t"));
+        assert_eq!(extract("cookie:missing"), None);
+
+        assert!(Extractor::parse("xpath://a").is_err());
+        assert!(Extractor::parse("json:$.a[x]").is_err());
+    }
+
+    #[test]
+    fn test_invalid_scenarios() {
+        let parse = |yaml: &str| build_scenario(serde_yaml::from_str(yaml).unwrap());
+        assert!(parse("steps: []").is_err());
+
+        let err = parse("steps:\n  - curl: curl http://a/x\n  - curl: curl http://a/x\n")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("step 2"), "{}", err);
+
+        let err = parse("steps:\n  - curl: curl http://a/x\n    think: 2s-1s\n")
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("reversed"), "{}", err);
+    }
+
+    #[test]
+    fn test_steps_on_separate_hosts() {
+        // 登录在认证服务，业务接口在另一台主机；发错主机的请求会得到 mock server 默认的 200 回显
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let routes = |routes: serde_json::Value| -> Vec<crate::mock_server::RouteConfig> {
+            serde_json::from_value(routes).unwrap()
+        };
+        let auth = rt
+            .block_on(crate::mock_server::spawn_local_with(routes(serde_json::json!([
+                {"path": "/login", "method": "POST", "status_code": 201, "response": r#"{"token":"t-1"}"#}
+            ]))))
+            .unwrap();
+        let api = rt
+            .block_on(crate::mock_server::spawn_local_with(routes(
+                serde_json::json!([
+                    {"path": "/orders", "status_code": 202, "echo": true}
+                ]),
+            )))
+            .unwrap();
+
+        let file = serde_json::json!({
+            "steps": [
+                {
+                    "curl": format!("curl -X POST http://{}/login", auth),
+                    "extract": {"token": "json:$.token"},
+                },
+                {
+                    "curl": format!("curl http://{}/orders -H 'Authorization: Bearer {{{{token}}}}'", api),
+                    "extract": {"sent": "json:$.headers.authorization"},
+                },
+            ]
+        });
+        let scenario = build_scenario(serde_json::from_value(file).unwrap()).unwrap();
+        let config = BenchmarkConfig::for_test(2, Duration::from_millis(300));
+        let stats = run_workers(scenario, &config, &TemplateEngine::new(), None).unwrap();
+
+        assert!(stats.flows_completed > 0);
+        assert_eq!(stats.flows_failed, 0, "{:?}", stats.errors);
+        let mut codes: Vec<_> = stats.status_codes.keys().copied().collect();
+        codes.sort();
+        assert_eq!(codes, [201, 202]);
+    }
+}
@@ -0,0 +1,770 @@
+//! 命令行分词
+//!
+//! 按 shell 规则把复制来的命令拆成参数列表，覆盖浏览器开发者工具 "Copy as cURL" 的两种格式：
+//! - POSIX（bash）：单引号、双引号、`$'...'` ANSI-C 字符串、反斜杠转义和 `\` 续行
+//! - Windows cmd：`^` 转义和 `^` 续行，之后再按 MS C 运行库的规则处理引号和 `\"`
+//!
+//! 未加引号的换行、`;`、`&`、`&&`、`||` 分隔多条命令（"Copy all as cURL" 的格式），
+//! 管道 `|` 之后的部分被丢弃。命令文件先按续行和引号拆成单条命令再分词（[`split_file`]），
+//! 一条命令的错误不影响其他命令。
+
+use anyhow::{anyhow, Result};
+
+/// 一条命令的参数及其起始行号（从 1 开始）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellCommand {
+    pub line: usize,
+    pub args: Vec<String>,
+}
+
+/// 命令行方言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    Posix,
+    Cmd,
+}
+
+impl Dialect {
+    /// 根据 `^"` 引号或行尾的 `^` 续行识别 cmd 格式，否则按 POSIX 处理
+    pub fn detect(input: &str) -> Self {
+        let cmd = input.contains("^\"") || input.lines().any(|line| line.trim_end().ends_with('^'));
+        if cmd {
+            Dialect::Cmd
+        } else {
+            Dialect::Posix
+        }
+    }
+
+    /// 拆分命令，`first_line` 是输入第一行的行号
+    fn split(self, input: &str, first_line: usize) -> Result<Vec<ShellCommand>> {
+        match self {
+            Dialect::Posix => split_posix(input, first_line),
+            Dialect::Cmd => split_cmd(input, first_line),
+        }
+    }
+}
+
+/// 自动识别方言并拆分出所有命令
+pub fn split_commands(input: &str) -> Result<Vec<ShellCommand>> {
+    Dialect::detect(input).split(input, 1)
+}
+
+/// 拆分命令文件，每条命令单独分词
+///
+/// 只有行尾的续行符（POSIX 的 `\`、cmd 的 `^`）和未闭合的引号让命令延续到下一行，
+/// 引号未闭合时以 `curl` 开头的行仍然开始新的命令。
+/// 返回每条命令的起始行号和参数；分词出错的命令返回错误，不影响其他命令。
+pub fn split_file(input: &str) -> Vec<(usize, Result<Vec<String>>)> {
+    let dialect = Dialect::detect(input);
+    let mut commands = Vec::new();
+    for (line, source) in command_sources(input, dialect) {
+        match dialect.split(&source, line) {
+            Ok(split) => commands.extend(split.into_iter().map(|c| (c.line, Ok(c.args)))),
+            Err(e) => commands.push((line, Err(e))),
+        }
+    }
+    commands
+}
+
+/// 把输入拆成各条命令的源文本（起始行号，文本）
+fn command_sources(input: &str, dialect: Dialect) -> Vec<(usize, String)> {
+    let mut scanner = LineScanner {
+        dialect,
+        quote: None,
+        literal: false,
+    };
+    let mut sources: Vec<(usize, String)> = Vec::new();
+    let mut open = false;
+
+    for (index, line) in input.split('\n').enumerate() {
+        if scanner.quote.is_some() && line.split_whitespace().next() == Some("curl") {
+            scanner.quote = None;
+            open = false;
+        }
+        match sources.last_mut() {
+            Some((_, text)) if open => {
+                text.push('\n');
+                text.push_str(line);
+            }
+            _ => sources.push((index + 1, line.to_string())),
+        }
+        open = scanner.continues(line);
+    }
+
+    sources
+}
+
+/// 逐行跟踪引号和续行状态
+struct LineScanner {
+    dialect: Dialect,
+    /// 未闭合的引号：`'`、`"`，或 `$` 表示 `$'...'`
+    quote: Option<char>,
+    /// cmd 续行后的第一个字符按字面保留（可能就是换行）
+    literal: bool,
+}
+
+impl LineScanner {
+    /// 扫描一行（不含换行符），返回命令是否延续到下一行
+    fn continues(&mut self, line: &str) -> bool {
+        let chars: Vec<char> = line.trim_end_matches('\r').chars().collect();
+        let mut i = 0;
+        if self.literal {
+            self.literal = false;
+            if chars.is_empty() {
+                return true;
+            }
+            i = 1;
+        }
+
+        while i < chars.len() {
+            match (self.dialect, self.quote, chars[i]) {
+                (Dialect::Posix, None, '\\') => {
+                    if i + 1 == chars.len() {
+                        return true;
+                    }
+                    i += 1;
+                }
+                (Dialect::Cmd, None, '^') => {
+                    if i + 1 == chars.len() {
+                        self.literal = true;
+                        return true;
+                    }
+                    i += 1;
+                }
+                (Dialect::Posix, None, '\'') => self.quote = Some('\''),
+                (Dialect::Posix, None, '$') if chars.get(i + 1) == Some(&'\'') => {
+                    self.quote = Some('$');
+                    i += 1;
+                }
+                (_, None, '"') => self.quote = Some('"'),
+                (Dialect::Posix, None, '#') if i == 0 || chars[i - 1].is_whitespace() => break,
+                (Dialect::Cmd, None, '#') if chars[..i].iter().all(|c| c.is_whitespace()) => break,
+                (Dialect::Posix, Some('"' | '$'), '\\') => i += 1,
+                (_, Some('\'' | '$'), '\'') | (_, Some('"'), '"') => self.quote = None,
+                _ => {}
+            }
+            i += 1;
+        }
+
+        match self.dialect {
+            Dialect::Posix => self.quote.is_some(),
+            // cmd 的引号不跨行
+            Dialect::Cmd => {
+                self.quote = None;
+                false
+            }
+        }
+    }
+}
+
+/// 分词过程中的命令、参数累积
+struct Builder {
+    commands: Vec<ShellCommand>,
+    args: Vec<String>,
+    /// 当前参数；`Some("")` 表示已经开始的空参数（如 `''`）
+    word: Option<String>,
+    line: usize,
+    start_line: usize,
+    /// 处在管道之后，丢弃参数直到命令结束
+    discard: bool,
+}
+
+impl Builder {
+    fn new(line: usize) -> Self {
+        Self {
+            commands: Vec::new(),
+            args: Vec::new(),
+            word: None,
+            line,
+            start_line: line,
+            discard: false,
+        }
+    }
+
+    fn word(&mut self) -> &mut String {
+        if self.word.is_none() && self.args.is_empty() {
+            self.start_line = self.line;
+        }
+        self.word.get_or_insert_with(String::new)
+    }
+
+    fn push(&mut self, ch: char) {
+        self.word().push(ch);
+    }
+
+    fn end_word(&mut self) {
+        if let Some(word) = self.word.take() {
+            if !self.discard {
+                self.args.push(word);
+            }
+        }
+    }
+
+    fn end_command(&mut self) {
+        self.end_word();
+        if !self.args.is_empty() {
+            self.commands.push(ShellCommand {
+                line: self.start_line,
+                args: std::mem::take(&mut self.args),
+            });
+        }
+        self.discard = false;
+    }
+
+    fn pipe(&mut self) {
+        self.end_word();
+        self.discard = true;
+    }
+
+    fn finish(mut self) -> Vec<ShellCommand> {
+        self.end_command();
+        self.commands
+    }
+}
+
+/// POSIX shell 分词
+fn split_posix(input: &str, first_line: usize) -> Result<Vec<ShellCommand>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut b = Builder::new(first_line);
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+        match ch {
+            '\\' => match chars.get(i + 1) {
+                // 续行
+                Some('\n') => {
+                    b.line += 1;
+                    i += 1;
+                }
+                Some('\r') if chars.get(i + 2) == Some(&'\n') => {
+                    b.line += 1;
+                    i += 2;
+                }
+                Some(&next) => {
+                    b.push(next);
+                    i += 1;
+                }
+                None => return Err(anyhow!("Unterminated line continuation")),
+            },
+            '\'' => {
+                let start = b.line;
+                b.word();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        Some('\'') => break,
+                        Some(&c) => {
+                            if c == '\n' {
+                                b.line += 1;
+                            }
+                            b.push(c);
+                        }
+                        None => return Err(anyhow!("Unterminated ' quote on line {}", start)),
+                    }
+                    i += 1;
+                }
+            }
+            '$' if chars.get(i + 1) == Some(&'\'') => {
+                let start = b.line;
+                let (value, end) = ansi_c_string(&chars, i + 2)
+                    .ok_or_else(|| anyhow!("Unterminated $' quote on line {}", start))??;
+                b.line += value.matches('\n').count();
+                b.word().push_str(&value);
+                i = end;
+            }
+            '"' => {
+                i = double_quoted(&chars, i + 1, &mut b)?;
+            }
+            // $"..." 是本地化字符串，按普通双引号处理
+            '$' if chars.get(i + 1) == Some(&'"') => {
+                i = double_quoted(&chars, i + 2, &mut b)?;
+            }
+            '#' if b.word.is_none() => {
+                while i + 1 < chars.len() && chars[i + 1] != '\n' {
+                    i += 1;
+                }
+            }
+            '\n' => {
+                b.end_command();
+                b.line += 1;
+            }
+            ';' => b.end_command(),
+            '&' => {
+                if chars.get(i + 1) == Some(&'&') {
+                    i += 1;
+                }
+                b.end_command();
+            }
+            '|' => {
+                if chars.get(i + 1) == Some(&'|') {
+                    i += 1;
+                    b.end_command();
+                } else {
+                    b.pipe();
+                }
+            }
+            c if c.is_whitespace() => b.end_word(),
+            c => b.push(c),
+        }
+        i += 1;
+    }
+
+    Ok(b.finish())
+}
+
+/// 读取双引号字符串，`start` 指向开引号之后；返回闭引号的位置
+///
+/// 双引号内反斜杠只转义 `$`、`` ` ``、`"`、`\` 和换行。
+fn double_quoted(chars: &[char], start: usize, b: &mut Builder) -> Result<usize> {
+    let first_line = b.line;
+    b.word();
+    let mut i = start;
+    loop {
+        match chars.get(i) {
+            Some('"') => return Ok(i),
+            Some('\\') => match chars.get(i + 1) {
+                Some(&c @ ('$' | '`' | '"' | '\\')) => {
+                    b.push(c);
+                    i += 1;
+                }
+                Some('\n') => {
+                    b.line += 1;
+                    i += 1;
+                }
+                _ => b.push('\\'),
+            },
+            Some(&c) => {
+                if c == '\n' {
+                    b.line += 1;
+                }
+                b.push(c);
+            }
+            None => return Err(anyhow!("Unterminated \" quote on line {}", first_line)),
+        }
+        i += 1;
+    }
+}
+
+/// 解码 `$'...'` 字符串，`start` 指向开引号之后；返回内容和闭引号的位置
+///
+/// 没有闭引号时返回 None。`\xHH` 和八进制转义产生原始字节，若结果不是合法 UTF-8
+/// （Firefox 会把 é 导出为 `\xe9`），则把这些字节按 Latin-1 解释。
+fn ansi_c_string(chars: &[char], start: usize) -> Option<Result<(String, usize)>> {
+    let end = start + find_ansi_c_end(&chars[start..])?;
+    let body = &chars[start..end];
+    let decoded = decode_ansi_c(body, false).and_then(|bytes| match String::from_utf8(bytes) {
+        Ok(value) => Ok(value),
+        Err(_) => decode_ansi_c(body, true).map(|bytes| String::from_utf8_lossy(&bytes).into()),
+    });
+    Some(decoded.map(|value| (value, end)))
+}
+
+fn find_ansi_c_end(chars: &[char]) -> Option<usize> {
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '\'' => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+fn decode_ansi_c(chars: &[char], latin1: bool) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let push_byte = |out: &mut Vec<u8>, byte: u32| {
+        if latin1 {
+            push_char(out, char::from(byte as u8));
+        } else {
+            out.push(byte as u8);
+        }
+    };
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        if c != '\\' {
+            push_char(&mut out, c);
+            continue;
+        }
+        let Some(&e) = chars.get(i) else {
+            push_char(&mut out, '\\');
+            break;
+        };
+        i += 1;
+        match e {
+            'a' => out.push(0x07),
+            'b' => out.push(0x08),
+            'e' | 'E' => out.push(0x1b),
+            'f' => out.push(0x0c),
+            'n' => out.push(b'\n'),
+            'r' => out.push(b'\r'),
+            't' => out.push(b'\t'),
+            'v' => out.push(0x0b),
+            '\\' | '\'' | '"' | '?' => push_char(&mut out, e),
+            'c' => {
+                // \cx：控制字符
+                if let Some(&x) = chars.get(i) {
+                    push_char(&mut out, char::from((x as u8) & 0x1f));
+                    i += 1;
+                }
+            }
+            '0'..='7' => {
+                let (value, used) = read_digits(&chars[i - 1..], 8, 3);
+                push_byte(&mut out, value);
+                i += used - 1;
+            }
+            'x' | 'u' | 'U' => {
+                let max = match e {
+                    'x' => 2,
+                    'u' => 4,
+                    _ => 8,
+                };
+                let (value, used) = read_digits(&chars[i..], 16, max);
+                if used == 0 {
+                    push_char(&mut out, '\\');
+                    push_char(&mut out, e);
+                    continue;
+                }
+                i += used;
+                if e == 'x' {
+                    push_byte(&mut out, value);
+                } else {
+                    let c = char::from_u32(value)
+                        .ok_or_else(|| anyhow!("Invalid unicode escape \\{}{:x}", e, value))?;
+                    push_char(&mut out, c);
+                }
+            }
+            other => {
+                push_char(&mut out, '\\');
+                push_char(&mut out, other);
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn push_char(out: &mut Vec<u8>, c: char) {
+    let mut buf = [0u8; 4];
+    out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+}
+
+/// 读取最多 `max` 位数字，返回数值和位数
+fn read_digits(chars: &[char], radix: u32, max: usize) -> (u32, usize) {
+    let mut value = 0;
+    let mut used = 0;
+    while used < max {
+        match chars.get(used).and_then(|c| c.to_digit(radix)) {
+            Some(d) => value = value * radix + d,
+            None => break,
+        }
+        used += 1;
+    }
+    (value, used)
+}
+
+/// Windows cmd 分词
+///
+/// 第一层模拟 cmd.exe：`^` 转义下一个字符（`^` 加换行是续行，续行后的字符按字面保留，
+/// 所以 `^` 加两个换行得到一个换行字符），双引号内 `^` 不生效；未转义的 `&`、`|` 和换行分隔命令。
+/// 第二层把每条命令行交给 [`split_crt`] 拆分参数。
+///
+/// 浏览器导出的命令把每个参数包在 `^"...^"` 中，并把所有反斜杠加倍；
+/// 这种输入按导出规则还原反斜杠，而不是按 C 运行库规则保留不在引号前的反斜杠。
+fn split_cmd(input: &str, first_line: usize) -> Result<Vec<ShellCommand>> {
+    let exported = input.contains("^\"");
+    let chars: Vec<char> = input.chars().collect();
+    let mut commands = Vec::new();
+    let mut current = String::new();
+    let mut line = first_line;
+    let mut start_line = first_line;
+    let mut in_quote = false;
+    let mut discard = false;
+    let mut i = 0;
+
+    let mut finish = |current: &mut String, discard: &mut bool, start_line: usize| {
+        let args = split_crt(current, exported);
+        if !args.is_empty() {
+            commands.push(ShellCommand {
+                line: start_line,
+                args,
+            });
+        }
+        current.clear();
+        *discard = false;
+    };
+
+    while i < chars.len() {
+        let ch = chars[i];
+        if current.trim().is_empty() && !discard {
+            start_line = line;
+        }
+        match ch {
+            '^' if !in_quote => {
+                i += 1;
+                if chars.get(i) == Some(&'\r') {
+                    i += 1;
+                }
+                if chars.get(i) == Some(&'\n') {
+                    line += 1;
+                    i += 1;
+                    if chars.get(i) == Some(&'\r') && chars.get(i + 1) == Some(&'\n') {
+                        i += 1;
+                    }
+                }
+                if let Some(&next) = chars.get(i) {
+                    if next == '\n' {
+                        line += 1;
+                    }
+                    if !discard {
+                        current.push(next);
+                    }
+                }
+            }
+            '"' => {
+                in_quote = !in_quote;
+                if !discard {
+                    current.push(ch);
+                }
+            }
+            '#' if !in_quote && current.trim().is_empty() => {
+                while i + 1 < chars.len() && chars[i + 1] != '\n' {
+                    i += 1;
+                }
+            }
+            '\n' => {
+                line += 1;
+                in_quote = false;
+                finish(&mut current, &mut discard, start_line);
+            }
+            '&' if !in_quote => {
+                if chars.get(i + 1) == Some(&'&') {
+                    i += 1;
+                }
+                finish(&mut current, &mut discard, start_line);
+            }
+            '|' if !in_quote => {
+                if chars.get(i + 1) == Some(&'|') {
+                    i += 1;
+                    finish(&mut current, &mut discard, start_line);
+                } else {
+                    discard = true;
+                }
+            }
+            '\r' => {}
+            c => {
+                if !discard {
+                    current.push(c);
+                }
+            }
+        }
+        i += 1;
+    }
+    finish(&mut current, &mut discard, start_line);
+
+    Ok(commands)
+}
+
+/// 按 MS C 运行库规则拆分命令行参数
+///
+/// 空格和制表符分隔参数；`"` 切换引号状态，引号内的 `""` 表示一个引号；
+/// 连续 n 个反斜杠后跟 `"` 时产生 n/2 个反斜杠，n 为奇数时该引号按字面保留；
+/// 其他位置的反斜杠按字面保留，`halve_backslashes` 为 true 时同样减半。
+pub fn split_crt(line: &str, halve_backslashes: bool) -> Vec<String> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut args = Vec::new();
+    let mut word: Option<String> = None;
+    let mut in_quote = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+        match ch {
+            ' ' | '\t' if !in_quote => {
+                if let Some(word) = word.take() {
+                    args.push(word);
+                }
+            }
+            '\\' => {
+                let mut n = 0;
+                while chars.get(i + n) == Some(&'\\') {
+                    n += 1;
+                }
+                let current = word.get_or_insert_with(String::new);
+                if chars.get(i + n) == Some(&'"') {
+                    current.extend(std::iter::repeat_n('\\', n / 2));
+                    if n % 2 == 1 {
+                        current.push('"');
+                        i += n;
+                    } else {
+                        i += n - 1;
+                    }
+                } else {
+                    let count = if halve_backslashes { n.div_ceil(2) } else { n };
+                    current.extend(std::iter::repeat_n('\\', count));
+                    i += n - 1;
+                }
+            }
+            '"' => {
+                let current = word.get_or_insert_with(String::new);
+                if in_quote && chars.get(i + 1) == Some(&'"') {
+                    current.push('"');
+                    i += 1;
+                } else {
+                    in_quote = !in_quote;
+                }
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+        i += 1;
+    }
+    if let Some(word) = word {
+        args.push(word);
+    }
+
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(input: &str) -> Vec<String> {
+        let commands = split_commands(input).unwrap();
+        assert_eq!(commands.len(), 1, "{:?}", commands);
+        commands.into_iter().next().unwrap().args
+    }
+
+    #[test]
+    fn test_posix_quoting() {
+        assert_eq!(
+            args(r#"curl 'a b' "c \"d\" \x" e\ f '' "$HOME""#),
+            ["curl", "a b", r#"c "d" \x"#, "e f", "", "$HOME"]
+        );
+        // 单引号内没有转义
+        assert_eq!(args(r#"curl '{\"a\":1}'"#), ["curl", r#"{\"a\":1}"#]);
+        // 单引号中嵌入单引号的写法
+        assert_eq!(args(r#"curl 'it'\''s'"#), ["curl", "it's"]);
+    }
+
+    #[test]
+    fn test_posix_continuations_and_separators() {
+        let commands = split_commands(
+            "curl 'https://a' \\\n  -H 'x: 1' ;\ncurl b # note\n\ncurl c | jq . && curl d",
+        )
+        .unwrap();
+        let lines: Vec<_> = commands.iter().map(|c| c.line).collect();
+        let args: Vec<_> = commands.into_iter().map(|c| c.args).collect();
+        assert_eq!(
+            args,
+            vec![
+                vec!["curl", "https://a", "-H", "x: 1"],
+                vec!["curl", "b"],
+                vec!["curl", "c"],
+                vec!["curl", "d"],
+            ]
+        );
+        assert_eq!(lines, [1, 3, 5, 5]);
+    }
+
+    #[test]
+    fn test_ansi_c_strings() {
+        assert_eq!(
+            args(r"curl $'O\'Brien\n\t\\é\x41\101☺'"),
+            ["curl", "O'Brien\n\t\\éAA☺"]
+        );
+        // UTF-8 字节序列
+        assert_eq!(args(r"curl $'\xc3\xa9'"), ["curl", "é"]);
+        // Firefox 导出的单字节 Latin-1
+        assert_eq!(args(r"curl $'caf\xe9\x21'"), ["curl", "café!"]);
+    }
+
+    #[test]
+    fn test_unterminated_quotes() {
+        assert!(split_commands("curl 'abc").is_err());
+        assert!(split_commands("curl \"abc").is_err());
+        assert!(split_commands("curl $'abc").is_err());
+        assert!(split_commands("curl abc \\").is_err());
+    }
+
+    #[test]
+    fn test_split_file() {
+        let commands = split_file(
+            "curl a \\\n  -H 'x: 1'\n\ncurl 'b\ncurl \"c\n d\" # it's\n-X POST\ncurl $'e\\'\n' f\ncurl g \\",
+        );
+        let lines: Vec<_> = commands.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, [1, 4, 5, 7, 8, 10]);
+        let args: Vec<_> = commands
+            .into_iter()
+            .map(|(_, args)| args.map_err(|e| e.to_string()))
+            .collect();
+        assert_eq!(
+            args[0],
+            Ok(vec!["curl".into(), "a".into(), "-H".into(), "x: 1".into()])
+        );
+        assert_eq!(args[1], Err("Unterminated ' quote on line 4".to_string()));
+        assert_eq!(args[2], Ok(vec!["curl".into(), "c\n d".into()]));
+        assert_eq!(args[3], Ok(vec!["-X".into(), "POST".into()]));
+        assert_eq!(args[4], Ok(vec!["curl".into(), "e'\n".into(), "f".into()]));
+        assert_eq!(args[5], Err("Unterminated line continuation".to_string()));
+
+        // cmd：`^` 续行后的空行是请求体中的换行
+        let commands = split_file("curl ^\"a^\" ^\n  --data-raw ^\"x^\n\ny^\"\ncurl ^\"b^\"");
+        let args: Vec<_> = commands
+            .into_iter()
+            .map(|(line, args)| (line, args.unwrap()))
+            .collect();
+        assert_eq!(
+            args,
+            [
+                (
+                    1,
+                    vec![
+                        "curl".to_string(),
+                        "a".into(),
+                        "--data-raw".into(),
+                        "x\ny".into()
+                    ]
+                ),
+                (5, vec!["curl".to_string(), "b".into()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cmd_caret_escapes() {
+        let input = "curl ^\"https://a/?x=1^&y=2^\" ^\n  -H ^\"x: ^\\^\"q^\\^\"^\" ^\n  --data-raw ^\"a^\n\nb %^PATH%^\"";
+        assert_eq!(Dialect::detect(input), Dialect::Cmd);
+        assert_eq!(
+            args(input),
+            [
+                "curl",
+                "https://a/?x=1&y=2",
+                "-H",
+                "x: \"q\"",
+                "--data-raw",
+                "a\nb %PATH%"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cmd_separators() {
+        let commands = split_commands("curl ^\"a^\" &\r\ncurl \"b&c\" | findstr x").unwrap();
+        let args: Vec<_> = commands.into_iter().map(|c| c.args).collect();
+        assert_eq!(args, vec![vec!["curl", "a"], vec!["curl", "b&c"]]);
+    }
+
+    #[test]
+    fn test_crt_backslashes() {
+        assert_eq!(
+            split_crt(r#"a\\b "c\"d" e\\\"f "g\\" "h""i""#, false),
+            ["a\\\\b", "c\"d", "e\\\"f", "g\\", "h\"i"]
+        );
+        assert_eq!(split_crt(r#""a\\nb\\\"c""#, true), ["a\\nb\\\"c"]);
+    }
+}
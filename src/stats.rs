@@ -1,15 +1,39 @@
+use crate::net::SourceConnect;
+use crate::streaming::StreamTiming;
 use hdrhistogram::Histogram;
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct RequestResult {
     pub duration: Duration,
     pub status_code: Option<u16>,
     pub bytes_read: usize,
     pub error: Option<String>,
     pub endpoint: Option<String>,
+    /// The error happened while establishing the connection
+    pub connect_error: bool,
+    /// grpc-status of a gRPC call
+    pub grpc_status: Option<u32>,
+    /// The request was never sent (e.g. its message failed to encode), so it has no latency
+    pub not_sent: bool,
+    /// Event timings of a streaming response
+    pub stream: Option<StreamTiming>,
+    /// Connections opened from a bound source address during this request
+    pub source_connects: Vec<SourceConnect>,
+    /// The scenario iteration that ended with this request
+    pub flow: Option<FlowOutcome>,
+}
+
+/// 一次场景流程的结果（随流程的最后一个请求上报）
+#[derive(Debug, Clone, Copy)]
+pub struct FlowOutcome {
+    /// 各步骤响应时间之和（不含思考时间）
+    pub latency: Duration,
+    /// 所有步骤都成功完成
+    pub completed: bool,
 }
 
 #[derive(Debug)]
@@ -19,11 +43,33 @@ pub struct Statistics {
     pub total_requests: u64,
     pub successful_requests: u64,
     pub failed_requests: u64,
+    pub connect_errors: u64,
     pub total_bytes: u64,
     pub latency_histogram: Histogram<u64>,
     pub status_codes: HashMap<u16, u64>,
+    pub grpc_status_codes: HashMap<u32, u64>,
     pub errors: HashMap<String, u64>,
     pub endpoint_stats: HashMap<String, EndpointStats>,
+    pub total_events: u64,
+    pub first_event_histogram: Histogram<u64>,
+    pub event_gap_histogram: Histogram<u64>,
+    pub source_stats: HashMap<IpAddr, SourceStats>,
+    /// 每个端点预期的流量占比（由负载策略和权重决定）
+    pub intended_shares: HashMap<String, f64>,
+    pub flows_completed: u64,
+    pub flows_failed: u64,
+    pub flow_histogram: Histogram<u64>,
+    /// 场景步骤名称，按这个顺序输出每个步骤的统计
+    pub step_names: Vec<String>,
+    /// 随机数种子，`--seed` 传入同一个值即可重现请求组合
+    pub seed: Option<u64>,
+}
+
+/// 每个本地源地址的建连统计
+#[derive(Debug, Clone, Default)]
+pub struct SourceStats {
+    pub connections: u64,
+    pub failures: u64,
 }
 
 #[derive(Debug, Clone)]
@@ -59,9 +105,11 @@ impl EndpointStats {
 
         self.total_bytes += result.bytes_read as u64;
 
-        let _ = self
-            .latency_histogram
-            .record(result.duration.as_micros() as u64);
+        if !result.not_sent {
+            let _ = self
+                .latency_histogram
+                .record(result.duration.as_micros() as u64);
+        }
     }
 
     pub fn avg_latency(&self) -> Duration {
@@ -88,34 +136,88 @@ impl Statistics {
             total_requests: 0,
             successful_requests: 0,
             failed_requests: 0,
+            connect_errors: 0,
             total_bytes: 0,
             latency_histogram: Histogram::<u64>::new(3).unwrap(),
             status_codes: HashMap::new(),
+            grpc_status_codes: HashMap::new(),
             errors: HashMap::new(),
             endpoint_stats: HashMap::new(),
+            total_events: 0,
+            first_event_histogram: Histogram::<u64>::new(3).unwrap(),
+            event_gap_histogram: Histogram::<u64>::new(3).unwrap(),
+            source_stats: HashMap::new(),
+            intended_shares: HashMap::new(),
+            flows_completed: 0,
+            flows_failed: 0,
+            flow_histogram: Histogram::<u64>::new(3).unwrap(),
+            step_names: Vec::new(),
+            seed: None,
         }
     }
 
     pub fn record(&mut self, result: RequestResult) {
         self.total_requests += 1;
 
-        if result.error.is_some() {
+        if let Some(error_msg) = &result.error {
             self.failed_requests += 1;
-            let error_msg = result.error.as_ref().unwrap().clone();
-            *self.errors.entry(error_msg).or_insert(0) += 1;
+            *self.errors.entry(error_msg.clone()).or_insert(0) += 1;
         } else {
             self.successful_requests += 1;
         }
 
+        if result.connect_error {
+            self.connect_errors += 1;
+        }
+
         if let Some(status) = result.status_code {
             *self.status_codes.entry(status).or_insert(0) += 1;
         }
 
+        if let Some(status) = result.grpc_status {
+            *self.grpc_status_codes.entry(status).or_insert(0) += 1;
+        }
+
         self.total_bytes += result.bytes_read as u64;
 
-        let _ = self
-            .latency_histogram
-            .record(result.duration.as_micros() as u64);
+        // 连接失败和没有发出的请求没有有效的延迟数据
+        if !result.connect_error && !result.not_sent {
+            let _ = self
+                .latency_histogram
+                .record(result.duration.as_micros() as u64);
+        }
+
+        // Record streaming event timings
+        if let Some(stream) = &result.stream {
+            self.total_events += stream.events;
+            if let Some(first_event) = stream.first_event {
+                let _ = self
+                    .first_event_histogram
+                    .record(first_event.as_micros() as u64);
+            }
+            for gap in &stream.event_gaps {
+                let _ = self.event_gap_histogram.record(gap.as_micros() as u64);
+            }
+        }
+
+        // Record per-source-address connections
+        for connect in &result.source_connects {
+            let source = self.source_stats.entry(connect.address).or_default();
+            source.connections += 1;
+            if connect.failed {
+                source.failures += 1;
+            }
+        }
+
+        // Record scenario flows
+        if let Some(flow) = &result.flow {
+            if flow.completed {
+                self.flows_completed += 1;
+                let _ = self.flow_histogram.record(flow.latency.as_micros() as u64);
+            } else {
+                self.flows_failed += 1;
+            }
+        }
 
         // Record per-endpoint stats
         if let Some(endpoint) = &result.endpoint {
@@ -185,11 +287,21 @@ impl Statistics {
             );
         }
 
+        if self.connect_errors > 0 {
+            println!("  {} connection failures", self.connect_errors);
+        }
+
         println!("Requests/sec:   {:.2}", self.requests_per_sec());
         println!(
             "Transfer/sec:   {:.2}MB",
             self.bytes_per_sec() / 1024.0 / 1024.0
         );
+        if let Some(seed) = self.seed {
+            println!(
+                "Seed:           {} (--seed {} repeats this run)",
+                seed, seed
+            );
+        }
 
         // Print latency stats
         println!("\nLatency Stats:");
@@ -242,6 +354,74 @@ impl Statistics {
             }
         }
 
+        // Print streaming stats
+        if !self.first_event_histogram.is_empty() {
+            println!("\nStream Stats:");
+            println!(
+                "  Events:          {} ({:.2}/sec, {:.2}/stream)",
+                self.total_events,
+                self.total_events as f64 / duration.as_secs_f64(),
+                self.total_events as f64 / self.first_event_histogram.len() as f64
+            );
+            print_histogram_line("First event:", &self.first_event_histogram);
+            if !self.event_gap_histogram.is_empty() {
+                print_histogram_line("Event gap:", &self.event_gap_histogram);
+            }
+        }
+
+        // Print scenario flow stats
+        let flows = self.flows_completed + self.flows_failed;
+        if flows > 0 {
+            println!("\nFlow Stats:");
+            println!(
+                "  Completed:       {} ({:.2}/sec)",
+                self.flows_completed,
+                self.flows_completed as f64 / duration.as_secs_f64()
+            );
+            if self.flows_failed > 0 {
+                println!(
+                    "  Failed:          {} ({:.2}%)",
+                    self.flows_failed,
+                    self.flows_failed as f64 / flows as f64 * 100.0
+                );
+            }
+            if !self.flow_histogram.is_empty() {
+                print_histogram_line("Flow latency:", &self.flow_histogram);
+            }
+        }
+
+        // Print gRPC status distribution
+        if !self.grpc_status_codes.is_empty() {
+            println!("\ngRPC Status Distribution:");
+            let mut codes: Vec<_> = self.grpc_status_codes.iter().collect();
+            codes.sort_by_key(|&(code, _)| code);
+            for (code, count) in codes {
+                let percentage = (*count as f64 / self.total_requests as f64) * 100.0;
+                println!(
+                    "  [{} {}] {} ({:.2}%)",
+                    code,
+                    crate::grpc::status_name(*code),
+                    count,
+                    percentage
+                );
+            }
+        }
+
+        // Print per-source-address connections
+        if !self.source_stats.is_empty() {
+            println!("\nSource Addresses:");
+            let mut sources: Vec<_> = self.source_stats.iter().collect();
+            sources.sort_by_key(|&(address, _)| address);
+            for (address, stats) in sources {
+                println!(
+                    "  {:<40} {} connections, {} failures",
+                    address.to_string(),
+                    stats.connections,
+                    stats.failures
+                );
+            }
+        }
+
         // Print errors
         if !self.errors.is_empty() {
             println!("\nError Summary:");
@@ -252,10 +432,32 @@ impl Statistics {
 
         // Print per-endpoint stats
         if self.endpoint_stats.len() > 1 {
-            println!("\n=== Per-Endpoint Statistics ===");
-            for (endpoint, stats) in &self.endpoint_stats {
+            // 场景按步骤顺序输出，其他情况按端点
+            let endpoints: Vec<(&String, &EndpointStats)> = if self.step_names.is_empty() {
+                println!("\n=== Per-Endpoint Statistics ===");
+                self.endpoint_stats.iter().collect()
+            } else {
+                println!("\n=== Per-Step Statistics ===");
+                self.step_names
+                    .iter()
+                    .filter_map(|name| self.endpoint_stats.get_key_value(name))
+                    .collect()
+            };
+            for (endpoint, stats) in endpoints {
                 println!("\n[{}]", endpoint);
                 println!("  Requests:     {}", stats.requests);
+                let share = stats.requests as f64 / self.total_requests.max(1) as f64 * 100.0;
+                match self.intended_shares.get(endpoint) {
+                    Some(intended) => println!(
+                        "  Share:        {:.1}% (intended {:.1}%)",
+                        share,
+                        intended * 100.0
+                    ),
+                    None if self.step_names.is_empty() => {
+                        println!("  Share:        {:.1}%", share)
+                    }
+                    None => {}
+                }
                 if stats.errors > 0 {
                     println!(
                         "  Errors:       {} ({:.1}%)",
@@ -298,6 +500,19 @@ impl Statistics {
     }
 }
 
+/// 打印一行延迟分布（avg/p50/p99/max，单位 ms）
+fn print_histogram_line(label: &str, histogram: &Histogram<u64>) {
+    let ms = |micros: u64| micros as f64 / 1000.0;
+    println!(
+        "  {:<16} avg={:.2}ms, p50={:.2}ms, p99={:.2}ms, max={:.2}ms",
+        label,
+        histogram.mean() / 1000.0,
+        ms(histogram.value_at_percentile(50.0)),
+        ms(histogram.value_at_percentile(99.0)),
+        ms(histogram.max())
+    );
+}
+
 pub type SharedStats = Arc<Mutex<Statistics>>;
 
 pub fn create_shared_stats() -> SharedStats {
@@ -310,7 +525,6 @@ pub struct StatisticsSnapshot {
     pub total_requests: u64,
     pub successful_requests: u64,
     pub failed_requests: u64,
-    pub total_bytes: u64,
     pub avg_latency_ms: f64,
     pub min_latency_ms: f64,
     pub max_latency_ms: f64,
@@ -320,7 +534,6 @@ pub struct StatisticsSnapshot {
     pub p95_latency_ms: f64,
     pub p99_latency_ms: f64,
     pub status_codes: HashMap<u16, u64>,
-    pub errors: HashMap<String, u64>,
     pub endpoint_stats: HashMap<String, EndpointStatsSnapshot>,
 }
 
@@ -329,11 +542,9 @@ pub struct EndpointStatsSnapshot {
     pub url: String,
     pub requests: u64,
     pub errors: u64,
-    pub total_bytes: u64,
     pub avg_latency_ms: f64,
     pub min_latency_ms: f64,
     pub max_latency_ms: f64,
-    pub status_codes: HashMap<u16, u64>,
 }
 
 impl StatisticsSnapshot {
@@ -343,7 +554,6 @@ impl StatisticsSnapshot {
             total_requests: 0,
             successful_requests: 0,
             failed_requests: 0,
-            total_bytes: 0,
             avg_latency_ms: 0.0,
             min_latency_ms: 0.0,
             max_latency_ms: 0.0,
@@ -353,7 +563,6 @@ impl StatisticsSnapshot {
             p95_latency_ms: 0.0,
             p99_latency_ms: 0.0,
             status_codes: HashMap::new(),
-            errors: HashMap::new(),
             endpoint_stats: HashMap::new(),
         }
     }
@@ -364,7 +573,6 @@ impl StatisticsSnapshot {
             total_requests: stats.total_requests,
             successful_requests: stats.successful_requests,
             failed_requests: stats.failed_requests,
-            total_bytes: stats.total_bytes,
             avg_latency_ms: stats.avg_latency().as_secs_f64() * 1000.0,
             min_latency_ms: Duration::from_micros(stats.latency_histogram.min()).as_secs_f64()
                 * 1000.0,
@@ -376,7 +584,6 @@ impl StatisticsSnapshot {
             p95_latency_ms: stats.percentile(95.0).as_secs_f64() * 1000.0,
             p99_latency_ms: stats.percentile(99.0).as_secs_f64() * 1000.0,
             status_codes: stats.status_codes.clone(),
-            errors: stats.errors.clone(),
             endpoint_stats: stats
                 .endpoint_stats
                 .iter()
@@ -387,11 +594,9 @@ impl StatisticsSnapshot {
                             url: url.clone(),
                             requests: ep_stats.requests,
                             errors: ep_stats.errors,
-                            total_bytes: ep_stats.total_bytes,
                             avg_latency_ms: ep_stats.avg_latency().as_secs_f64() * 1000.0,
                             min_latency_ms: ep_stats.min_latency().as_secs_f64() * 1000.0,
                             max_latency_ms: ep_stats.max_latency().as_secs_f64() * 1000.0,
-                            status_codes: ep_stats.status_codes.clone(),
                         },
                     )
                 })
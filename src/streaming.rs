@@ -0,0 +1,200 @@
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// 流式响应的分帧方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// text/event-stream：空行结束一个事件，`:` 开头的注释行不计入
+    Sse,
+    /// 每个非空行是一个事件（NDJSON、chunked 文本等）
+    Lines,
+}
+
+impl FromStr for StreamFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "sse" | "event-stream" => Ok(Self::Sse),
+            "lines" | "ndjson" => Ok(Self::Lines),
+            _ => Err(anyhow!(
+                "Unknown stream format: {} (expected sse or lines)",
+                s
+            )),
+        }
+    }
+}
+
+/// 增量解析响应体，统计完成的事件数
+#[derive(Debug)]
+pub struct EventParser {
+    format: StreamFormat,
+    /// 尚未遇到换行符的残余数据
+    pending: Vec<u8>,
+    /// 当前 SSE 事件块中是否有 data 字段
+    has_data: bool,
+}
+
+impl EventParser {
+    pub fn new(format: StreamFormat) -> Self {
+        Self {
+            format,
+            pending: Vec::new(),
+            has_data: false,
+        }
+    }
+
+    /// 输入一段响应数据，返回其中完成的事件数
+    pub fn feed(&mut self, chunk: &[u8]) -> u64 {
+        let mut events = 0;
+        let mut rest = chunk;
+
+        while let Some(pos) = rest.iter().position(|&b| b == b'\n') {
+            let events_in_line = if self.pending.is_empty() {
+                self.line(&rest[..pos])
+            } else {
+                let mut line = std::mem::take(&mut self.pending);
+                line.extend_from_slice(&rest[..pos]);
+                self.line(&line)
+            };
+            events += events_in_line;
+            rest = &rest[pos + 1..];
+        }
+
+        self.pending.extend_from_slice(rest);
+        events
+    }
+
+    /// 响应结束：按行分帧时，最后一个没有换行符的行也算一个事件
+    /// （SSE 规范要求丢弃未以空行结束的事件）
+    pub fn finish(&mut self) -> u64 {
+        let line = std::mem::take(&mut self.pending);
+        match self.format {
+            StreamFormat::Lines => self.line(&line),
+            StreamFormat::Sse => 0,
+        }
+    }
+
+    fn line(&mut self, line: &[u8]) -> u64 {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+        match self.format {
+            StreamFormat::Lines => u64::from(!line.iter().all(u8::is_ascii_whitespace)),
+            StreamFormat::Sse => {
+                if line.is_empty() {
+                    let dispatched = self.has_data;
+                    self.has_data = false;
+                    u64::from(dispatched)
+                } else {
+                    let field = line.split(|&b| b == b':').next().unwrap_or_default();
+                    if field == b"data" {
+                        self.has_data = true;
+                    }
+                    0
+                }
+            }
+        }
+    }
+}
+
+/// 一次流式请求的事件时间统计
+#[derive(Debug, Clone, Default)]
+pub struct StreamTiming {
+    /// 从发出请求到收到第一个事件
+    pub first_event: Option<Duration>,
+    /// 相邻事件之间的间隔
+    pub event_gaps: Vec<Duration>,
+    pub events: u64,
+}
+
+/// 在读取响应体时记录事件到达时间
+#[derive(Debug)]
+pub struct StreamRecorder {
+    parser: EventParser,
+    start: Instant,
+    last_event: Option<Instant>,
+    timing: StreamTiming,
+}
+
+impl StreamRecorder {
+    pub fn new(format: StreamFormat, start: Instant) -> Self {
+        Self {
+            parser: EventParser::new(format),
+            start,
+            last_event: None,
+            timing: StreamTiming::default(),
+        }
+    }
+
+    /// 处理一段响应数据
+    pub fn on_chunk(&mut self, chunk: &[u8]) {
+        let events = self.parser.feed(chunk);
+        self.record(events);
+    }
+
+    pub fn events(&self) -> u64 {
+        self.timing.events
+    }
+
+    /// 响应结束，返回统计结果
+    pub fn finish(mut self) -> StreamTiming {
+        let events = self.parser.finish();
+        self.record(events);
+        self.timing
+    }
+
+    fn record(&mut self, events: u64) {
+        if events == 0 {
+            return;
+        }
+
+        // 同一个数据块中的多个事件视为同时到达
+        let now = Instant::now();
+        for _ in 0..events {
+            match self.last_event {
+                None => self.timing.first_event = Some(now.duration_since(self.start)),
+                Some(last) => self.timing.event_gaps.push(now.duration_since(last)),
+            }
+            self.last_event = Some(now);
+        }
+        self.timing.events += events;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_events() {
+        let mut parser = EventParser::new(StreamFormat::Sse);
+        assert_eq!(
+            parser.feed(b"data: one\n\n: heartbeat\n\nevent: x\ndata: t"),
+            1
+        );
+        assert_eq!(parser.feed(b"wo\r\n\r\nid: 3\n\n"), 1);
+        assert_eq!(parser.feed(b"data: incomplete\n"), 0);
+        assert_eq!(parser.finish(), 0);
+    }
+
+    #[test]
+    fn test_line_events() {
+        let mut parser = EventParser::new(StreamFormat::Lines);
+        assert_eq!(parser.feed(b"{\"a\":1}\n{\"a\""), 1);
+        assert_eq!(parser.feed(b":2}\n\n"), 1);
+        assert_eq!(parser.feed(b"{\"a\":3}"), 0);
+        assert_eq!(parser.finish(), 1);
+    }
+
+    #[test]
+    fn test_recorder_timing() {
+        let mut recorder = StreamRecorder::new(StreamFormat::Lines, Instant::now());
+        recorder.on_chunk(b"a\nb\n");
+        recorder.on_chunk(b"c\n");
+        let timing = recorder.finish();
+        assert_eq!(timing.events, 3);
+        assert!(timing.first_event.is_some());
+        assert_eq!(timing.event_gaps.len(), 2);
+    }
+}
@@ -1,30 +1,401 @@
+use crate::cli::parse_size_string;
+use crate::curl_parser::{base64_encode, urlencode, CurlCommand};
+use crate::feeder::Feeder;
+use crate::headers::Headers;
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
-use rand::Rng;
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use rand::distributions::WeightedIndex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, Exp, Normal, Zipf};
 use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::sync::atomic::AtomicU64;
-use std::sync::Arc;
-use uuid::Uuid;
+use std::fmt::Write;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use uuid::Builder;
 
 lazy_static::lazy_static! {
     static ref TEMPLATE_REGEX: Regex = Regex::new(r"\{\{([^}]+)\}\}").unwrap();
 }
 
+/// 可以直接写在 `{{...}}` 中的函数
+const INLINE_FUNCTIONS: [&str; 15] = [
+    "random:",
+    "timestamp:",
+    "now:",
+    "sequence:",
+    "choice:",
+    "weighted:",
+    "zipf:",
+    "normal:",
+    "exponential:",
+    "string:",
+    "hex:",
+    "payload:",
+    "fake:",
+    "env:",
+    "file:",
+];
+
+/// 对参数求值后再编码或计算摘要的函数，只能写在 `{{...}}` 中
+const TRANSFORMS: [(&str, Transform); 5] = [
+    ("base64:", Transform::Base64),
+    ("urlencode:", Transform::UrlEncode),
+    ("md5:", Transform::Md5),
+    ("sha256:", Transform::Sha256),
+    ("hmac:", Transform::Hmac),
+];
+
+const ALPHANUMERIC: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+const HEX_DIGITS: &[u8] = b"0123456789abcdef";
+/// `string`、`hex`、`payload` 生成的最大长度（64 MiB），每个请求都会重新生成
+const MAX_RANDOM_LEN: usize = 64 * 1024 * 1024;
+
+const FIRST_NAMES: [&str; 20] = [
+    "James", "Mary", "Wei", "Fatima", "Carlos", "Aiko", "Olivia", "Liam", "Priya", "Noah", "Emma",
+    "Mateo", "Sofia", "Yusuf", "Hana", "Lucas", "Chloe", "Ivan", "Amara", "Elena",
+];
+const LAST_NAMES: [&str; 20] = [
+    "Smith", "Garcia", "Wang", "Khan", "Müller", "Tanaka", "Johnson", "Rossi", "Silva", "Kim",
+    "Brown", "Nguyen", "Martin", "Novak", "Okafor", "Lopez", "Ivanov", "Cohen", "Patel", "Berg",
+];
+/// RFC 2606 保留的域名，不会把邮件发给真实用户
+const EMAIL_DOMAINS: [&str; 3] = ["example.com", "example.org", "example.net"];
+
+/// `{{fresh:name}}`：不使用本请求中已经生成的值，重新生成一个
+const FRESH_PREFIX: &str = "fresh:";
+
 #[derive(Clone)]
 pub struct TemplateEngine {
     variables: HashMap<String, VariableType>,
-    sequences: Arc<HashMap<String, AtomicU64>>,
+    /// 全局序列的计数器（所有连接共享），按变量名或内联模板文本区分
+    sequences: Arc<RwLock<HashMap<String, Arc<AtomicU64>>>>,
+    /// 数据文件 unique 顺序的打乱种子
+    seed: u64,
+}
+
+/// 每个连接（虚拟用户）自己的模板状态
+pub struct TemplateState {
+    /// 本连接的随机数生成器（random、choice、uuid 等）
+    rng: StdRng,
+    /// 优先于模板变量的值（如场景中提取的变量）
+    pub locals: HashMap<String, String>,
+    /// 按连接计数的序列已经生成的个数
+    sequences: HashMap<String, u64>,
+    /// 当前请求中变量已经生成的值，同一请求中的 `{{name}}` 使用同一个值
+    values: HashMap<String, String>,
+    /// 当前请求已经取到的数据文件行（按变量名），保证同一请求中的字段来自同一行
+    rows: HashMap<String, usize>,
+    /// 某个数据文件的行已经用完，连接应停止发送请求
+    pub exhausted: bool,
+}
+
+impl TemplateState {
+    /// 相同的种子生成相同的随机值序列
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            locals: HashMap::new(),
+            sequences: HashMap::new(),
+            values: HashMap::new(),
+            rows: HashMap::new(),
+            exhausted: false,
+        }
+    }
+
+    /// 开始一个新请求：之后用到的变量重新生成，数据文件变量取新的一行
+    pub fn next_request(&mut self) {
+        self.values.clear();
+        self.rows.clear();
+    }
 }
 
 #[derive(Clone)]
 enum VariableType {
-    Random { min: i64, max: i64 },
+    Random {
+        min: i64,
+        max: i64,
+    },
     Uuid,
-    Timestamp { format: TimestampFormat },
-    Sequence { start: u64 },
-    Choice { options: Vec<String> },
-    Static { value: String },
+    Timestamp {
+        format: TimestampFormat,
+    },
+    Sequence(Sequence),
+    Skewed(Skewed),
+    /// `choice:` 的 `weights` 为 None，每个选项的概率相同；`weighted:` 按权重选择
+    Choice {
+        options: Vec<String>,
+        weights: Option<WeightedIndex<u32>>,
+    },
+    /// `string:N`、`hex:N`、`payload:SIZE`
+    RandomString {
+        alphabet: &'static [u8],
+        len: usize,
+    },
+    Fake(Fake),
+    Static {
+        value: String,
+    },
+    Feeder(Arc<Feeder>),
+}
+
+impl VariableType {
+    /// 格式正确但取值无效的定义（如 `random:10-1`）报错，生成时不必再检查
+    fn validate(&self) -> Result<()> {
+        match self {
+            VariableType::Random { min, max } if min > max => Err(anyhow!(
+                "Invalid random range {}-{}: min is greater than max",
+                min,
+                max
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `fake:KIND` 生成的测试数据
+#[derive(Clone, Copy)]
+enum Fake {
+    FirstName,
+    LastName,
+    Name,
+    Username,
+    Email,
+    Ipv4,
+    Ipv6,
+}
+
+impl Fake {
+    fn parse(kind: &str) -> Result<Self> {
+        match kind {
+            "first_name" => Ok(Fake::FirstName),
+            "last_name" => Ok(Fake::LastName),
+            "name" => Ok(Fake::Name),
+            "username" => Ok(Fake::Username),
+            "email" => Ok(Fake::Email),
+            "ipv4" | "ip" => Ok(Fake::Ipv4),
+            "ipv6" => Ok(Fake::Ipv6),
+            _ => Err(anyhow!(
+                "Unknown fake data kind: {} (expected first_name, last_name, name, username, email, ipv4 or ipv6)",
+                kind
+            )),
+        }
+    }
+
+    fn generate(self, rng: &mut impl Rng) -> String {
+        let first = FIRST_NAMES[rng.gen_range(0..FIRST_NAMES.len())];
+        let last = LAST_NAMES[rng.gen_range(0..LAST_NAMES.len())];
+        match self {
+            Fake::FirstName => first.to_string(),
+            Fake::LastName => last.to_string(),
+            Fake::Name => format!("{} {}", first, last),
+            Fake::Username => format!("{}{}", first.to_lowercase(), rng.gen_range(1..10000)),
+            Fake::Email => format!(
+                "{}.{}{}@{}",
+                first.to_lowercase(),
+                last.to_lowercase(),
+                rng.gen_range(1..1000),
+                EMAIL_DOMAINS[rng.gen_range(0..EMAIL_DOMAINS.len())]
+            ),
+            // 避开 0.x、组播和保留地址段
+            Fake::Ipv4 => Ipv4Addr::new(
+                rng.gen_range(1..=223),
+                rng.gen(),
+                rng.gen(),
+                rng.gen_range(1..=254),
+            )
+            .to_string(),
+            Fake::Ipv6 => Ipv6Addr::from(rng.gen::<u128>()).to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Transform {
+    Base64,
+    UrlEncode,
+    Md5,
+    Sha256,
+    /// HMAC-SHA256，`hmac:KEY:ARG`
+    Hmac,
+}
+
+impl Transform {
+    fn apply(self, key: Option<&str>, input: &str) -> String {
+        match self {
+            Transform::Base64 => base64_encode(input),
+            Transform::UrlEncode => urlencode(input),
+            Transform::Md5 => to_hex(&Md5::digest(input)),
+            Transform::Sha256 => to_hex(&Sha256::digest(input)),
+            Transform::Hmac => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(key.unwrap_or_default().as_bytes())
+                    .expect("HMAC accepts keys of any length");
+                mac.update(input.as_bytes());
+                to_hex(&mac.finalize().into_bytes())
+            }
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut out, b| {
+            let _ = write!(out, "{:02x}", b);
+            out
+        })
+}
+
+/// `sequence:start[:step=N][:max=N][:scope=global|connection]`
+#[derive(Clone, Debug, PartialEq)]
+struct Sequence {
+    start: u64,
+    step: u64,
+    /// 超过上限后回到 start（包含上限）
+    max: Option<u64>,
+    per_connection: bool,
+}
+
+impl Sequence {
+    fn parse(definition: &str) -> Result<Self> {
+        let mut parts = definition.split(':');
+        let start = parts.next().unwrap_or_default();
+        let mut sequence = Self {
+            start: start
+                .parse()
+                .map_err(|_| anyhow!("Invalid sequence start: {}", start))?,
+            step: 1,
+            max: None,
+            per_connection: false,
+        };
+        for option in parts {
+            let number = |value: &str| {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| anyhow!("Invalid sequence option: {}", option))
+            };
+            match option.split_once('=') {
+                Some(("step", value)) => sequence.step = number(value)?,
+                Some(("max", value)) => sequence.max = Some(number(value)?),
+                Some(("scope", "global")) => sequence.per_connection = false,
+                Some(("scope", "connection")) => sequence.per_connection = true,
+                _ => return Err(anyhow!("Unknown sequence option: {}", option)),
+            }
+        }
+        if sequence.step == 0 {
+            return Err(anyhow!("Sequence step must be at least 1"));
+        }
+        if sequence.max.is_some_and(|max| max < sequence.start) {
+            return Err(anyhow!("Sequence max is below its start"));
+        }
+        Ok(sequence)
+    }
+
+    /// 第 n 个值（从 0 开始），有上限时循环
+    fn value(&self, n: u64) -> u64 {
+        let n = match self.max {
+            Some(max) => n % ((max - self.start) / self.step + 1),
+            None => n,
+        };
+        self.start.wrapping_add(n.wrapping_mul(self.step))
+    }
+}
+
+/// 非均匀分布的整数，用于模拟热点 key
+///
+/// `zipf:min-max[:s=N]`、`normal:mean,sd[:min=N][:max=N]`、`exponential:mean[:min=N][:max=N]`
+#[derive(Clone)]
+struct Skewed {
+    shape: Shape,
+    /// 结果限定在这个范围内（normal、exponential）
+    min: Option<i64>,
+    max: Option<i64>,
+}
+
+#[derive(Clone)]
+enum Shape {
+    /// 第 k 个值出现的概率与 1/k^s 成正比，`min` 最常出现
+    Zipf {
+        min: i64,
+        zipf: Zipf<f64>,
+    },
+    Normal(Normal<f64>),
+    Exponential(Exp<f64>),
+}
+
+impl Skewed {
+    fn parse(kind: &str, definition: &str) -> Result<Self> {
+        let mut parts = definition.split(':');
+        let params = parts.next().unwrap_or_default();
+        let invalid = || anyhow!("Invalid {} parameters: {}", kind, params);
+        let mut exponent = 1.0;
+        let mut min = None;
+        let mut max = None;
+        for option in parts {
+            let invalid_option = || anyhow!("Invalid {} option: {}", kind, option);
+            match (kind, option.split_once('=')) {
+                ("zipf", Some(("s", value))) => {
+                    exponent = value.parse().map_err(|_| invalid_option())?
+                }
+                ("normal" | "exponential", Some(("min", value))) => {
+                    min = Some(value.parse().map_err(|_| invalid_option())?)
+                }
+                ("normal" | "exponential", Some(("max", value))) => {
+                    max = Some(value.parse().map_err(|_| invalid_option())?)
+                }
+                _ => return Err(anyhow!("Unknown {} option: {}", kind, option)),
+            }
+        }
+        if matches!((min, max), (Some(min), Some(max)) if max < min) {
+            return Err(anyhow!("{} max is below its min", kind));
+        }
+
+        let shape = match kind {
+            "zipf" => {
+                let (low, high) = params.split_once('-').ok_or_else(invalid)?;
+                let low: i64 = low.parse().map_err(|_| invalid())?;
+                let high: i64 = high.parse().map_err(|_| invalid())?;
+                if high < low {
+                    return Err(invalid());
+                }
+                let zipf = Zipf::new((high - low) as u64 + 1, exponent)
+                    .map_err(|e| anyhow!("Invalid zipf exponent {}: {}", exponent, e))?;
+                Shape::Zipf { min: low, zipf }
+            }
+            "normal" => {
+                let (mean, sd) = params.split_once(',').ok_or_else(invalid)?;
+                let mean: f64 = mean.parse().map_err(|_| invalid())?;
+                let sd: f64 = sd.parse().map_err(|_| invalid())?;
+                if sd < 0.0 {
+                    return Err(invalid());
+                }
+                Shape::Normal(Normal::new(mean, sd).map_err(|_| invalid())?)
+            }
+            _ => {
+                let mean: f64 = params.parse().map_err(|_| invalid())?;
+                if mean <= 0.0 {
+                    return Err(invalid());
+                }
+                Shape::Exponential(Exp::new(1.0 / mean).map_err(|_| invalid())?)
+            }
+        };
+        Ok(Self { shape, min, max })
+    }
+
+    fn sample(&self, rng: &mut impl Rng) -> i64 {
+        let value = match &self.shape {
+            Shape::Zipf { min, zipf } => return min + zipf.sample(rng) as i64 - 1,
+            Shape::Normal(normal) => normal.sample(rng),
+            Shape::Exponential(exp) => exp.sample(rng),
+        };
+        (value.round() as i64).clamp(self.min.unwrap_or(i64::MIN), self.max.unwrap_or(i64::MAX))
+    }
 }
 
 #[derive(Clone)]
@@ -41,26 +412,31 @@ impl TemplateEngine {
     pub fn new() -> Self {
         Self {
             variables: HashMap::new(),
-            sequences: Arc::new(HashMap::new()),
+            sequences: Arc::new(RwLock::new(HashMap::new())),
+            seed: rand::random(),
         }
     }
 
-    pub fn add_variable(&mut self, name: String, definition: &str) -> Result<()> {
-        let var_type = Self::parse_variable_definition(definition)?;
-
-        // Initialize sequence counter if needed
-        if let VariableType::Sequence { start } = &var_type {
-            let sequences = Arc::get_mut(&mut self.sequences).unwrap();
-            sequences.insert(name.clone(), AtomicU64::new(*start));
-        }
+    /// 设置随机数种子；在添加变量之前调用
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
 
+    /// 添加 `--var` 定义的变量；同名序列在所有使用它的地方共用一个计数器
+    pub fn add_variable(&mut self, name: String, definition: &str) -> Result<()> {
+        let var_type = self.parse_variable_definition(definition)?;
+        var_type.validate()?;
         self.variables.insert(name, var_type);
         Ok(())
     }
 
-    fn parse_variable_definition(def: &str) -> Result<VariableType> {
-        if def.starts_with("random:") {
-            let range = &def[7..];
+    /// 添加固定值变量（不解析 `random:` 等定义，用于导入的集合变量）
+    pub fn add_static(&mut self, name: String, value: String) {
+        self.variables.insert(name, VariableType::Static { value });
+    }
+
+    fn parse_variable_definition(&self, def: &str) -> Result<VariableType> {
+        if let Some(range) = def.strip_prefix("random:") {
             let parts: Vec<&str> = range.split('-').collect();
             if parts.len() != 2 {
                 return Err(anyhow!(
@@ -72,28 +448,102 @@ impl TemplateEngine {
             Ok(VariableType::Random { min, max })
         } else if def == "uuid" {
             Ok(VariableType::Uuid)
-        } else if def.starts_with("timestamp:") || def.starts_with("now:") {
-            let format_str = if def.starts_with("timestamp:") {
-                &def[10..]
-            } else {
-                &def[4..]
-            };
+        } else if let Some(format_str) = def
+            .strip_prefix("timestamp:")
+            .or_else(|| def.strip_prefix("now:"))
+        {
             let format = Self::parse_timestamp_format(format_str)?;
             Ok(VariableType::Timestamp { format })
         } else if def == "timestamp" || def == "now" {
             Ok(VariableType::Timestamp {
                 format: TimestampFormat::Unix,
             })
-        } else if def.starts_with("sequence:") {
-            let start: u64 = def[9..].parse()?;
-            Ok(VariableType::Sequence { start })
-        } else if def.starts_with("choice:") {
-            let options_str = &def[7..];
+        } else if let Some(sequence) = def.strip_prefix("sequence:") {
+            Ok(VariableType::Sequence(Sequence::parse(sequence)?))
+        } else if let Some(source) = def.strip_prefix("csv:") {
+            Ok(VariableType::Feeder(Arc::new(Feeder::load(
+                source, false, self.seed,
+            )?)))
+        } else if let Some(source) = def.strip_prefix("jsonl:") {
+            Ok(VariableType::Feeder(Arc::new(Feeder::load(
+                source, true, self.seed,
+            )?)))
+        } else if let Some(definition) = def.strip_prefix("zipf:") {
+            Ok(VariableType::Skewed(Skewed::parse("zipf", definition)?))
+        } else if let Some(definition) = def.strip_prefix("normal:") {
+            Ok(VariableType::Skewed(Skewed::parse("normal", definition)?))
+        } else if let Some(definition) = def.strip_prefix("exponential:") {
+            Ok(VariableType::Skewed(Skewed::parse(
+                "exponential",
+                definition,
+            )?))
+        } else if let Some(len) = def.strip_prefix("string:") {
+            let len = len
+                .parse()
+                .map_err(|_| anyhow!("Invalid string length: {}", len))?;
+            Self::random_string(ALPHANUMERIC, len)
+        } else if let Some(len) = def.strip_prefix("hex:") {
+            let len = len
+                .parse()
+                .map_err(|_| anyhow!("Invalid hex length: {}", len))?;
+            Self::random_string(HEX_DIGITS, len)
+        } else if let Some(size) = def.strip_prefix("payload:") {
+            Self::random_string(ALPHANUMERIC, parse_size_string(size)?)
+        } else if let Some(kind) = def.strip_prefix("fake:") {
+            Ok(VariableType::Fake(Fake::parse(kind)?))
+        } else if let Some(name) = def.strip_prefix("env:") {
+            let value = std::env::var(name)
+                .map_err(|_| anyhow!("Environment variable {} is not set", name))?;
+            Ok(VariableType::Static { value })
+        } else if let Some(path) = def.strip_prefix("file:") {
+            // 与 shell 的 $(cat file) 一样去掉末尾的换行
+            let value = std::fs::read_to_string(path)
+                .map_err(|e| anyhow!("Failed to read {}: {}", path, e))?;
+            Ok(VariableType::Static {
+                value: value.trim_end_matches(['\r', '\n']).to_string(),
+            })
+        } else if let Some((prefix, _)) = TRANSFORMS
+            .iter()
+            .find(|(prefix, _)| def.starts_with(prefix))
+        {
+            Err(anyhow!(
+                "{} can only be used inside a template, e.g. {{{{{}name}}}}",
+                prefix.trim_end_matches(':'),
+                prefix
+            ))
+        } else if let Some(options_str) = def.strip_prefix("choice:") {
+            // 选项原样使用（`host:8080` 等值中的冒号不是权重）
             let options: Vec<String> = options_str.split(',').map(|s| s.to_string()).collect();
             if options.is_empty() {
                 return Err(anyhow!("Choice must have at least one option"));
             }
-            Ok(VariableType::Choice { options })
+            Ok(VariableType::Choice {
+                options,
+                weights: None,
+            })
+        } else if let Some(options_str) = def.strip_prefix("weighted:") {
+            // 每个选项都是 `value=N`，按最后一个 `=` 拆分
+            let mut options = Vec::new();
+            let mut weights = Vec::new();
+            for option in options_str.split(',') {
+                let (value, weight) = option
+                    .rsplit_once('=')
+                    .and_then(|(value, weight)| Some((value, weight.parse::<u32>().ok()?)))
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Invalid weighted option `{}`. Expected: value=WEIGHT",
+                            option
+                        )
+                    })?;
+                options.push(value.to_string());
+                weights.push(weight);
+            }
+            let weights = WeightedIndex::new(&weights)
+                .map_err(|_| anyhow!("Weights must not all be zero"))?;
+            Ok(VariableType::Choice {
+                options,
+                weights: Some(weights),
+            })
         } else {
             Ok(VariableType::Static {
                 value: def.to_string(),
@@ -101,6 +551,16 @@ impl TemplateEngine {
         }
     }
 
+    fn random_string(alphabet: &'static [u8], len: usize) -> Result<VariableType> {
+        if len > MAX_RANDOM_LEN {
+            return Err(anyhow!(
+                "Random text of {} bytes exceeds the maximum of 64m",
+                len
+            ));
+        }
+        Ok(VariableType::RandomString { alphabet, len })
+    }
+
     fn parse_timestamp_format(format: &str) -> Result<TimestampFormat> {
         match format {
             "unix" => Ok(TimestampFormat::Unix),
@@ -113,76 +573,420 @@ impl TemplateEngine {
         }
     }
 
-    pub fn process(&self, text: &str) -> String {
-        TEMPLATE_REGEX
-            .replace_all(text, |caps: &regex::Captures| {
-                let template = &caps[1];
-                self.evaluate_template(template)
-                    .unwrap_or_else(|_| format!("{{{{{}}}}}", template))
-            })
-            .to_string()
+    /// 编译模板：`{{...}}` 在启动时解析一次，发送请求时只需拼接片段
+    ///
+    /// 取值无效的内联函数（如 `{{random:10-1}}`）报错。
+    pub fn compile(&self, text: &str) -> Result<Template> {
+        let mut segments = Vec::new();
+        let mut last = 0;
+        for caps in TEMPLATE_REGEX.captures_iter(text) {
+            let whole = caps.get(0).unwrap();
+            if whole.start() > last {
+                segments.push(Segment::Literal(text[last..whole.start()].to_string()));
+            }
+            let placeholder = self
+                .compile_placeholder(&caps[1])
+                .map_err(|e| anyhow!("Invalid template {}: {}", whole.as_str(), e))?;
+            segments.push(Segment::Placeholder(placeholder));
+            last = whole.end();
+        }
+        if last < text.len() || segments.is_empty() {
+            segments.push(Segment::Literal(text[last..].to_string()));
+        }
+        Ok(Template {
+            segments,
+            capacity: text.len(),
+        })
+    }
+
+    /// 编译一条命令中可能带模板的部分
+    pub fn compile_command(&self, cmd: &CurlCommand) -> Result<CommandTemplate> {
+        let header_values = if cmd.headers.iter().any(|(_, value)| value.contains("{{")) {
+            cmd.headers
+                .iter()
+                .map(|(_, value)| self.compile(value))
+                .collect::<Result<_>>()?
+        } else {
+            Vec::new()
+        };
+        Ok(CommandTemplate {
+            method: self.compile(&cmd.method)?,
+            url: self.compile(&cmd.url)?,
+            headers: cmd.headers.clone(),
+            header_values,
+            body: cmd
+                .body
+                .as_deref()
+                .map(|body| self.compile(body))
+                .transpose()?,
+        })
+    }
+
+    /// 文本中无法解析的 `{{...}}`（不含外层括号，按出现顺序），这些占位符会原样发送
+    pub fn unresolved(&self, text: &str) -> Result<Vec<String>> {
+        Ok(self
+            .compile(text)?
+            .unresolved()
+            .map(str::to_string)
+            .collect())
     }
 
-    fn evaluate_template(&self, template: &str) -> Result<String> {
+    fn compile_placeholder(&self, text: &str) -> Result<Placeholder> {
+        let (name, fresh) = match text.strip_prefix(FRESH_PREFIX) {
+            Some(name) => (name, true),
+            None => (text, false),
+        };
+        Ok(Placeholder {
+            text: text.to_string(),
+            source: self.compile_source(name, fresh)?,
+        })
+    }
+
+    fn compile_source(&self, name: &str, fresh: bool) -> Result<Source> {
         // Check if it's a variable reference
-        if let Some(var_type) = self.variables.get(template) {
-            return self.generate_value(var_type);
+        if let Some(var_type) = self.variables.get(name) {
+            return Ok(match var_type {
+                VariableType::Static { value } => Source::Static(value.clone()),
+                VariableType::Feeder(feeder) => Source::Field {
+                    name: name.to_string(),
+                    feeder: feeder.clone(),
+                    field: None,
+                    fresh,
+                },
+                _ => Source::Generate {
+                    generator: self.generator(name, var_type.clone()),
+                    bind: (!fresh).then(|| name.to_string()),
+                },
+            });
+        }
+
+        // `{{user.id}}`: a field of a data file variable
+        if let Some((variable, field)) = name.split_once('.') {
+            if let Some(VariableType::Feeder(feeder)) = self.variables.get(variable) {
+                return Ok(Source::Field {
+                    name: variable.to_string(),
+                    feeder: feeder.clone(),
+                    field: Some(field.to_string()),
+                    fresh,
+                });
+            }
+        }
+
+        // `{{sha256:body}}`: encode or hash the value of the argument
+        if let Some((transform, argument)) = TRANSFORMS
+            .iter()
+            .find_map(|(prefix, transform)| Some((*transform, name.strip_prefix(prefix)?)))
+        {
+            let (key, input) = match transform {
+                Transform::Hmac => match argument.split_once(':') {
+                    Some((key, input)) => (Some(key), input),
+                    None => return Ok(Source::Unknown),
+                },
+                _ => (None, argument),
+            };
+            return Ok(Source::Transform {
+                transform,
+                key: key
+                    .map(|key| self.compile_argument(key).map(Box::new))
+                    .transpose()?,
+                input: Box::new(self.compile_argument(input)?),
+            });
         }
 
         // Check if it's an inline function
-        if template.starts_with("random:") {
-            let var_type = Self::parse_variable_definition(template)?;
-            return self.generate_value(&var_type);
-        } else if template == "uuid" {
-            return Ok(Uuid::new_v4().to_string());
-        } else if template.starts_with("timestamp:") || template.starts_with("now:") {
-            let var_type = Self::parse_variable_definition(template)?;
-            return self.generate_value(&var_type);
-        } else if template == "timestamp" || template == "now" {
-            return Ok(Utc::now().timestamp().to_string());
-        } else if template.starts_with("sequence:") {
-            let var_type = Self::parse_variable_definition(template)?;
-            return self.generate_value(&var_type);
-        } else if template.starts_with("choice:") {
-            let var_type = Self::parse_variable_definition(template)?;
-            return self.generate_value(&var_type);
-        }
-
-        Err(anyhow!("Unknown template: {}", template))
-    }
-
-    fn generate_value(&self, var_type: &VariableType) -> Result<String> {
-        match var_type {
-            VariableType::Random { min, max } => {
-                let mut rng = rand::thread_rng();
-                let value = rng.gen_range(*min..=*max);
-                Ok(value.to_string())
+        let inline = matches!(name, "uuid" | "timestamp" | "now")
+            || INLINE_FUNCTIONS
+                .iter()
+                .any(|prefix| name.starts_with(prefix));
+        match self.parse_variable_definition(name) {
+            // env: and file: are read once here
+            Ok(VariableType::Static { value }) if inline => Ok(Source::Static(value)),
+            Ok(var_type) if inline => {
+                var_type.validate()?;
+                Ok(Source::Generate {
+                    generator: self.generator(name, var_type),
+                    bind: None,
+                })
+            }
+            _ => Ok(Source::Unknown),
+        }
+    }
+
+    /// 函数的参数：变量、数据文件字段或其他函数时取其值，否则是字面文本
+    fn compile_argument(&self, text: &str) -> Result<Placeholder> {
+        let mut argument = self.compile_placeholder(text)?;
+        if matches!(argument.source, Source::Unknown) {
+            argument.source = Source::Static(text.to_string());
+        }
+        Ok(argument)
+    }
+
+    /// `key` 区分序列的计数器（变量名或内联模板文本）
+    fn generator(&self, key: &str, var_type: VariableType) -> Generator {
+        let counter = match &var_type {
+            VariableType::Sequence(sequence) if !sequence.per_connection => {
+                Some(self.sequence_counter(key))
+            }
+            _ => None,
+        };
+        Generator {
+            key: key.to_string(),
+            var_type,
+            counter,
+        }
+    }
+
+    /// 全局序列的计数器，第一次使用时创建
+    fn sequence_counter(&self, key: &str) -> Arc<AtomicU64> {
+        if let Some(counter) = self.sequences.read().unwrap().get(key) {
+            return counter.clone();
+        }
+        self.sequences
+            .write()
+            .unwrap()
+            .entry(key.to_string())
+            .or_default()
+            .clone()
+    }
+}
+
+/// 编译后的模板：字面文本和占位符片段
+pub struct Template {
+    segments: Vec<Segment>,
+    /// 渲染结果的预估长度
+    capacity: usize,
+}
+
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+struct Placeholder {
+    /// 括号中的原文：用于查找 `locals`，无法解析时原样输出
+    text: String,
+    source: Source,
+}
+
+enum Source {
+    Static(String),
+    /// `bind` 是命名变量的名称，同一请求中只生成一次；内联函数和 `fresh:` 为 None
+    Generate {
+        generator: Generator,
+        bind: Option<String>,
+    },
+    /// 数据文件变量的字段（None 为默认列）
+    Field {
+        name: String,
+        feeder: Arc<Feeder>,
+        field: Option<String>,
+        fresh: bool,
+    },
+    /// 对参数的值编码或计算摘要（`key` 是 hmac 的密钥）
+    Transform {
+        transform: Transform,
+        key: Option<Box<Placeholder>>,
+        input: Box<Placeholder>,
+    },
+    Unknown,
+}
+
+struct Generator {
+    key: String,
+    var_type: VariableType,
+    /// 全局序列的计数器（编译时取得）
+    counter: Option<Arc<AtomicU64>>,
+}
+
+impl Template {
+    /// 渲染模板；没有占位符时直接借用原文本
+    ///
+    /// `state` 是当前连接的状态，其中的 `locals` 优先于模板变量。命名变量在一个请求中
+    /// 只生成一次（直到 `state.next_request()`），内联函数和 `{{fresh:name}}` 每次出现都重新生成。
+    pub fn render(&self, state: &mut TemplateState) -> Cow<'_, str> {
+        if let [Segment::Literal(text)] = self.segments.as_slice() {
+            return Cow::Borrowed(text);
+        }
+        let mut out = String::with_capacity(self.capacity);
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => out.push_str(text),
+                Segment::Placeholder(placeholder) => placeholder.render(state, &mut out),
             }
-            VariableType::Uuid => Ok(Uuid::new_v4().to_string()),
+        }
+        Cow::Owned(out)
+    }
+
+    /// 无法解析的占位符（不含外层括号）
+    pub fn unresolved(&self) -> impl Iterator<Item = &str> {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Placeholder(Placeholder {
+                text,
+                source: Source::Unknown,
+            }) => Some(text.as_str()),
+            _ => None,
+        })
+    }
+}
+
+impl Placeholder {
+    fn render(&self, state: &mut TemplateState, out: &mut String) {
+        if !state.locals.is_empty() {
+            if let Some(value) = state.locals.get(&self.text) {
+                out.push_str(value);
+                return;
+            }
+        }
+        let value = match &self.source {
+            Source::Static(value) => {
+                out.push_str(value);
+                return;
+            }
+            Source::Generate {
+                generator,
+                bind: Some(name),
+            } => {
+                if let Some(value) = state.values.get(name) {
+                    out.push_str(value);
+                    return;
+                }
+                let value = generator.generate(state);
+                out.push_str(&value);
+                state.values.insert(name.clone(), value);
+                return;
+            }
+            Source::Generate {
+                generator,
+                bind: None,
+            } => Some(generator.generate(state)),
+            Source::Field {
+                name,
+                feeder,
+                field,
+                fresh,
+            } => feeder_value(name, feeder, field.as_deref(), state, *fresh),
+            Source::Transform {
+                transform,
+                key,
+                input,
+            } => {
+                let key = key.as_ref().map(|key| {
+                    let mut text = String::new();
+                    key.render(state, &mut text);
+                    text
+                });
+                let mut text = String::new();
+                input.render(state, &mut text);
+                Some(transform.apply(key.as_deref(), &text))
+            }
+            Source::Unknown => None,
+        };
+        match value {
+            Some(value) => out.push_str(&value),
+            None => {
+                out.push_str("{{");
+                out.push_str(&self.text);
+                out.push_str("}}");
+            }
+        }
+    }
+}
+
+impl Generator {
+    fn generate(&self, state: &mut TemplateState) -> String {
+        match &self.var_type {
+            VariableType::Random { min, max } => state.rng.gen_range(*min..=*max).to_string(),
+            VariableType::Uuid => Builder::from_random_bytes(state.rng.gen())
+                .into_uuid()
+                .to_string(),
             VariableType::Timestamp { format } => {
                 let now: DateTime<Utc> = Utc::now();
-                let value = match format {
+                match format {
                     TimestampFormat::Unix => now.timestamp().to_string(),
                     TimestampFormat::UnixMs => now.timestamp_millis().to_string(),
                     TimestampFormat::Rfc3339 => now.to_rfc3339(),
                     TimestampFormat::Iso8601 => now.to_rfc3339(),
                     TimestampFormat::Date => now.format("%Y-%m-%d").to_string(),
                     TimestampFormat::Time => now.format("%H:%M:%S").to_string(),
+                }
+            }
+            VariableType::Sequence(sequence) => {
+                let n = match &self.counter {
+                    Some(counter) => counter.fetch_add(1, Ordering::Relaxed),
+                    None => {
+                        let counter = state.sequences.entry(self.key.clone()).or_insert(0);
+                        *counter += 1;
+                        *counter - 1
+                    }
                 };
-                Ok(value)
+                sequence.value(n).to_string()
             }
-            VariableType::Sequence { start } => {
-                // This is a simplified version - in real implementation,
-                // we'd need to track the counter per variable name
-                Ok(start.to_string())
+            VariableType::Skewed(skewed) => skewed.sample(&mut state.rng).to_string(),
+            VariableType::Choice { options, weights } => {
+                let index = match weights {
+                    Some(weights) => weights.sample(&mut state.rng),
+                    None => state.rng.gen_range(0..options.len()),
+                };
+                options[index].clone()
+            }
+            VariableType::RandomString { alphabet, len } => (0..*len)
+                .map(|_| alphabet[state.rng.gen_range(0..alphabet.len())] as char)
+                .collect(),
+            VariableType::Fake(fake) => fake.generate(&mut state.rng),
+            // 固定值和数据文件变量在编译时处理
+            VariableType::Static { value } => value.clone(),
+            VariableType::Feeder(feeder) => {
+                feeder_value(&self.key, feeder, None, state, false).unwrap_or_default()
             }
-            VariableType::Choice { options } => {
-                let mut rng = rand::thread_rng();
-                let idx = rng.gen_range(0..options.len());
-                Ok(options[idx].clone())
+        }
+    }
+}
+
+/// 数据文件变量的字段；同一请求中第一次用到该变量时取一行，`fresh` 时取一个新行
+fn feeder_value(
+    name: &str,
+    feeder: &Feeder,
+    field: Option<&str>,
+    state: &mut TemplateState,
+    fresh: bool,
+) -> Option<String> {
+    let row = match state.rows.get(name) {
+        Some(row) if !fresh => *row,
+        _ => {
+            let Some(row) = feeder.next_row(&mut state.rng) else {
+                state.exhausted = true;
+                return None;
+            };
+            if !fresh {
+                state.rows.insert(name.to_string(), row);
             }
-            VariableType::Static { value } => Ok(value.clone()),
+            row
         }
+    };
+    feeder.field(row, field)
+}
+
+/// 一条命令中带模板的部分（方法、URL、请求头的值和请求体），启动时编译一次
+pub struct CommandTemplate {
+    pub method: Template,
+    pub url: Template,
+    headers: Headers,
+    /// 与 `headers` 的顺序一致；没有请求头带模板时为空
+    header_values: Vec<Template>,
+    pub body: Option<Template>,
+}
+
+impl CommandTemplate {
+    /// 渲染请求头；没有模板时直接借用
+    pub fn headers(&self, state: &mut TemplateState) -> Cow<'_, Headers> {
+        if self.header_values.is_empty() {
+            return Cow::Borrowed(&self.headers);
+        }
+        let mut values = self.header_values.iter();
+        Cow::Owned(
+            self.headers
+                .map_values(|_| values.next().unwrap().render(state).into_owned()),
+        )
     }
 }
 
@@ -193,6 +997,8 @@ URL Template Variables
 ======================
 
 quickurl supports dynamic URL template variables for realistic load testing.
+Templates work in the URL, the method, header values and the body; placeholders
+that can't be resolved are listed once at startup and sent as-is.
 
 Built-in Template Functions:
 ----------------------------
@@ -217,12 +1023,66 @@ Built-in Template Functions:
    Example: {{{{timestamp:unix}}}} → 1640995200
 
 4. sequence - Incrementing numbers
-   Usage: {{{{sequence:start}}}}
+   Usage: {{{{sequence:start[:step=N][:max=N][:scope=global|connection]}}}}
+   Options:
+     - step: increment (default 1)
+     - max: wrap back to start after this value
+     - scope: one counter shared by all connections (global, default)
+              or one counter per connection
    Example: {{{{sequence:1}}}} → 1, 2, 3, ...
+   Example: {{{{sequence:0:step=10:max=30}}}} → 0, 10, 20, 30, 0, ...
+   A sequence defined with --var (--var id=sequence:1) uses one counter
+   everywhere {{{{id}}}} appears.
 
-5. choice - Random selection
-   Usage: {{{{choice:a,b,c}}}}
+5. choice / weighted - Random selection
+   Usage: {{{{choice:a,b,c}}}} or {{{{weighted:a=70,b=30}}}}
    Example: {{{{choice:GET,POST,PUT}}}} → POST
+   Example: {{{{weighted:GET=90,POST=10}}}} → GET nine times out of ten
+   choice options are used as written (host:8080 is one value); every
+   weighted option needs =WEIGHT, split at the last =.
+
+6. zipf / normal / exponential - Skewed integers for hot-key testing
+   Usage: {{{{zipf:min-max[:s=EXP]}}}}
+          {{{{normal:mean,sd[:min=N][:max=N]}}}}
+          {{{{exponential:mean[:min=N][:max=N]}}}}
+   zipf: min is the hottest key, min+1 the next, ... (s defaults to 1.0;
+         larger values concentrate traffic on fewer keys)
+   normal / exponential: rounded to an integer, clamped to min/max if given
+   Example: {{{{zipf:1-100000:s=1.2}}}} → 1, 3, 1, 57, 2, ...
+   Example: {{{{normal:500,100:min=1}}}} → 482
+
+7. string / hex / payload - Random text
+   Usage: {{{{string:N}}}} (letters and digits), {{{{hex:N}}}}, {{{{payload:SIZE}}}}
+   SIZE accepts k/m suffixes: {{{{payload:4k}}}} → 4096 random letters and digits
+   Lengths are limited to 64m.
+
+8. fake - Test data
+   Usage: {{{{fake:KIND}}}}, KIND is first_name, last_name, name, username,
+          email (always @example.com/.org/.net), ipv4 or ipv6
+   Example: {{{{fake:email}}}} → priya.kim417@example.org
+
+9. env / file - Values read once at startup
+   Usage: {{{{env:NAME}}}}, {{{{file:PATH}}}} (trailing newlines removed)
+   Example: -H 'Authorization: Bearer {{{{env:API_TOKEN}}}}'
+
+10. base64 / urlencode / md5 / sha256 / hmac - Encodings and digests
+   Usage: {{{{base64:ARG}}}}, {{{{urlencode:ARG}}}}, {{{{md5:ARG}}}}, {{{{sha256:ARG}}}},
+          {{{{hmac:KEY:ARG}}}} (HMAC-SHA256; digests are lowercase hex)
+   ARG (and KEY) is the value of a variable, a data file field or another
+   function when it names one, otherwise literal text:
+   Example: {{{{base64:user:pass}}}} → dXNlcjpwYXNz
+   Example: --var id=random:1-1000 ... '{{{{id}}}}' -H 'X-Sig: {{{{hmac:secret:id}}}}'
+   These work inside templates only, not as --var definitions.
+
+11. csv / jsonl - Rows from a data file (define with --var)
+   Usage: --var user=csv:PATH[:COLUMN][:order=...][:exhausted=...]
+          --var user=jsonl:PATH[:FIELD][:order=...][:exhausted=...]
+   Fields: {{{{user.id}}}}, nested JSONL fields {{{{user.address.city}}}};
+           {{{{user}}}} is COLUMN, the first CSV column or the whole JSONL line
+   Options:
+     - order: sequential (default), random, unique (shuffled, each row once)
+     - exhausted: stop (default for unique) or recycle
+   All fields in one request come from the same row.
 
 Basic Examples:
 --------------
@@ -242,7 +1102,10 @@ quickurl -c 5 -d 60s 'https://api.example.com/items?page={{{{sequence:1}}}}&limi
 Custom Variables:
 ----------------
 
-Define variables with --var option:
+Define variables with --var option. A variable takes one value per request:
+every {{{{user_id}}}} in the URL, headers and body of that request is the same
+number. {{{{fresh:user_id}}}} draws a new value instead; inline functions such as
+{{{{random:1-10}}}} draw a new value wherever they appear.
 
 quickurl --var user_id=random:1-10000 \
      --var method=choice:GET,POST,PUT \
@@ -268,18 +1131,37 @@ quickurl --var user_id=random:1-10000 \
 mod tests {
     use super::*;
 
+    fn process(engine: &TemplateEngine, text: &str, state: &mut TemplateState) -> String {
+        engine.compile(text).unwrap().render(state).into_owned()
+    }
+
     #[test]
     fn test_random_template() {
         let engine = TemplateEngine::new();
-        let result = engine.process("https://api.example.com/users/{{random:1-100}}");
+        let result = process(
+            &engine,
+            "https://api.example.com/users/{{random:1-100}}",
+            &mut TemplateState::new(0),
+        );
         assert!(result.starts_with("https://api.example.com/users/"));
         assert!(!result.contains("{{"));
+
+        // 最小值大于最大值在编译时报错，而不是发送请求时 panic
+        let err = engine.compile("/users/{{random:10-1}}").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Invalid template {{random:10-1}}: Invalid random range 10-1: min is greater than max"
+        );
+        let mut engine = TemplateEngine::new();
+        assert!(engine.add_variable("id".into(), "random:10-1").is_err());
+        engine.add_variable("id".into(), "random:5-5").unwrap();
+        assert_eq!(process(&engine, "{{id}}", &mut TemplateState::new(0)), "5");
     }
 
     #[test]
     fn test_uuid_template() {
         let engine = TemplateEngine::new();
-        let result = engine.process("session={{uuid}}");
+        let result = process(&engine, "session={{uuid}}", &mut TemplateState::new(0));
         assert!(result.starts_with("session="));
         assert!(!result.contains("{{"));
     }
@@ -290,7 +1172,388 @@ mod tests {
         engine
             .add_variable("user_id".to_string(), "random:1-1000")
             .unwrap();
-        let result = engine.process("https://api.example.com/users/{{user_id}}");
+        let result = process(
+            &engine,
+            "https://api.example.com/users/{{user_id}}",
+            &mut TemplateState::new(0),
+        );
         assert!(result.starts_with("https://api.example.com/users/"));
     }
+
+    #[test]
+    fn test_sequences() {
+        let mut engine = TemplateEngine::new();
+        engine
+            .add_variable("id".to_string(), "sequence:100:step=5")
+            .unwrap();
+        engine
+            .add_variable("slot".to_string(), "sequence:1:max=3:scope=connection")
+            .unwrap();
+        let mut first = TemplateState::new(0);
+        let mut second = TemplateState::new(0);
+
+        // 命名序列在所有连接共用一个计数器，每个请求取一个值
+        assert_eq!(process(&engine, "{{id}}/{{id}}", &mut first), "100/100");
+        assert_eq!(process(&engine, "{{id}}", &mut second), "105");
+        first.next_request();
+        assert_eq!(process(&engine, "{{id}}", &mut first), "110");
+
+        // 按连接计数，到上限后回到起点
+        let slots: Vec<_> = (0..4)
+            .map(|_| {
+                first.next_request();
+                process(&engine, "{{slot}}", &mut first)
+            })
+            .collect();
+        assert_eq!(slots, ["1", "2", "3", "1"]);
+        assert_eq!(process(&engine, "{{slot}}", &mut second), "1");
+
+        // 相同的内联序列共用计数器
+        assert_eq!(
+            process(&engine, "{{sequence:1}} {{sequence:1}}", &mut first),
+            "1 2"
+        );
+        assert_eq!(process(&engine, "{{sequence:1}}", &mut second), "3");
+        assert_eq!(
+            process(&engine, "{{sequence:0:step=10:max=25}}", &mut first),
+            "0"
+        );
+        let wrapped: Vec<_> = (0..3)
+            .map(|_| process(&engine, "{{sequence:0:step=10:max=25}}", &mut first))
+            .collect();
+        assert_eq!(wrapped, ["10", "20", "0"]);
+
+        assert!(engine.add_variable("x".into(), "sequence:5:max=1").is_err());
+        assert!(engine
+            .add_variable("x".into(), "sequence:1:step=0")
+            .is_err());
+        assert!(engine
+            .add_variable("x".into(), "sequence:1:scope=thread")
+            .is_err());
+    }
+
+    #[test]
+    fn test_request_binding() {
+        let mut engine = TemplateEngine::new();
+        engine.add_variable("n".to_string(), "sequence:1").unwrap();
+        let mut state = TemplateState::new(0);
+
+        // 同一请求中的命名变量只生成一次，fresh: 和内联函数每次重新生成
+        assert_eq!(
+            process(
+                &engine,
+                "/users/{{n}}?again={{n}}&next={{fresh:n}}",
+                &mut state
+            ),
+            "/users/1?again=1&next=2"
+        );
+        assert_eq!(
+            process(&engine, "{\"id\": {{n}}}", &mut state),
+            "{\"id\": 1}"
+        );
+        assert_eq!(
+            process(&engine, "{{sequence:5}} {{sequence:5}}", &mut state),
+            "5 6"
+        );
+        state.next_request();
+        assert_eq!(process(&engine, "{{n}} {{n}}", &mut state), "3 3");
+        assert_eq!(
+            engine.unresolved("{{fresh:n}} {{fresh:m}}").unwrap(),
+            ["fresh:m"]
+        );
+    }
+
+    #[test]
+    fn test_seeded_values() {
+        let engine = TemplateEngine::new();
+        let template = engine
+            .compile("{{random:1-1000000}} {{choice:a,b,c,d}} {{uuid}}")
+            .unwrap();
+        let run = |seed| {
+            let mut state = TemplateState::new(seed);
+            (0..5)
+                .map(|_| template.render(&mut state).into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(42), run(42));
+        assert_ne!(run(42), run(43));
+        let uuid = run(1)[0].split(' ').nth(2).unwrap().to_string();
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4");
+    }
+
+    #[test]
+    fn test_skewed_distributions() {
+        let mut engine = TemplateEngine::new();
+        engine
+            .add_variable("hot".to_string(), "zipf:1-1000:s=1.5")
+            .unwrap();
+        let mut state = TemplateState::new(9);
+        let draw = |text: &str, state: &mut TemplateState| {
+            let template = engine.compile(text).unwrap();
+            (0..2000)
+                .map(|_| {
+                    state.next_request();
+                    template.render(state).parse::<i64>().unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let keys = draw("{{hot}}", &mut state);
+        assert!(keys.iter().all(|k| (1..=1000).contains(k)));
+        let count = |k| keys.iter().filter(|&&v| v == k).count();
+        assert!(count(1) > count(2) && count(2) > count(10));
+        assert!(count(1) > 600, "{}", count(1));
+
+        let normal = draw("{{normal:100,10:max=105}}", &mut state);
+        assert!(normal.iter().all(|&v| v <= 105));
+        let below = normal.iter().filter(|&&v| v < 100).count();
+        assert!((800..1200).contains(&below), "{}", below);
+
+        let exponential = draw("{{exponential:50:min=1}}", &mut state);
+        let mean = exponential.iter().sum::<i64>() as f64 / exponential.len() as f64;
+        assert!(exponential.iter().all(|&v| v >= 1));
+        assert!((45.0..56.0).contains(&mean), "{}", mean);
+
+        let weighted = engine.compile("{{weighted:a=90,b=10,c=0}}").unwrap();
+        let a = (0..2000)
+            .filter(|_| weighted.render(&mut state) == "a")
+            .count();
+        assert!((1700..1900).contains(&a), "{}", a);
+        assert!((0..200).all(|_| weighted.render(&mut state) != "c"));
+        // choice 的选项原样使用，冒号和数字不是权重
+        assert_eq!(
+            process(&engine, "{{choice:http://x}}", &mut state),
+            "http://x"
+        );
+        let hosts: Vec<_> = (0..50)
+            .map(|_| process(&engine, "{{choice:api:8080,api:8081}}", &mut state))
+            .collect();
+        assert!(hosts.iter().all(|h| h == "api:8080" || h == "api:8081"));
+        assert_eq!(
+            process(&engine, "{{weighted:a=b=1}} {{weighted:x:1=1}}", &mut state),
+            "a=b x:1"
+        );
+
+        for bad in [
+            "zipf:10-1",
+            "zipf:1-10:s=-1",
+            "zipf:1-10:min=2",
+            "normal:5",
+            "normal:5,-1",
+            "normal:5,1:min=3:max=2",
+            "exponential:0",
+            "weighted:a=0,b=0",
+            "weighted:a=1,b",
+            "weighted:a:1,b:1",
+        ] {
+            assert!(engine.parse_variable_definition(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_function_library() {
+        let mut engine = TemplateEngine::new();
+        engine.add_static("word".to_string(), "abc".to_string());
+        engine
+            .add_variable("id".to_string(), "random:1-1000000")
+            .unwrap();
+        engine
+            .add_variable("who".to_string(), "fake:email")
+            .unwrap();
+        let mut state = TemplateState::new(3);
+
+        let text = process(
+            &engine,
+            "{{string:16}}|{{hex:8}}|{{payload:2k}}",
+            &mut state,
+        );
+        let parts: Vec<&str> = text.split('|').collect();
+        assert_eq!(parts[0].len(), 16);
+        assert!(parts[0].bytes().all(|b| b.is_ascii_alphanumeric()));
+        assert_eq!(parts[1].len(), 8);
+        assert!(parts[1].bytes().all(|b| b.is_ascii_hexdigit()));
+        assert_eq!(parts[2].len(), 2048);
+
+        // 长度溢出或超过上限时报错
+        assert!(engine.parse_variable_definition("payload:64m").is_ok());
+        for (def, err) in [
+            (
+                format!("payload:{}k", usize::MAX),
+                format!("Size too large: {}k", usize::MAX),
+            ),
+            (
+                "payload:65m".to_string(),
+                "Random text of 68157440 bytes exceeds the maximum of 64m".to_string(),
+            ),
+            (
+                "string:100000000".to_string(),
+                "Random text of 100000000 bytes exceeds the maximum of 64m".to_string(),
+            ),
+        ] {
+            match engine.parse_variable_definition(&def) {
+                Err(e) => assert_eq!(e.to_string(), err),
+                Ok(_) => panic!("{} should be rejected", def),
+            }
+        }
+
+        let email = process(&engine, "{{who}}", &mut state);
+        assert!(email.contains("@example."), "{}", email);
+        assert_eq!(process(&engine, "{{who}}", &mut state), email);
+        assert!(process(&engine, "{{fake:ipv4}}", &mut state)
+            .parse::<Ipv4Addr>()
+            .is_ok());
+        assert!(process(&engine, "{{fake:ipv6}}", &mut state)
+            .parse::<Ipv6Addr>()
+            .is_ok());
+        assert_eq!(
+            process(&engine, "{{fake:name}}", &mut state)
+                .split(' ')
+                .count(),
+            2
+        );
+
+        std::env::set_var("QUICKURL_TEST_TOKEN", "from-env");
+        let path = std::env::temp_dir().join(format!("quickurl-include-{}", std::process::id()));
+        std::fs::write(&path, "from-file\n").unwrap();
+        let include = format!(
+            "{{{{env:QUICKURL_TEST_TOKEN}}}} {{{{file:{}}}}}",
+            path.display()
+        );
+        assert_eq!(process(&engine, &include, &mut state), "from-env from-file");
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            process(
+                &engine,
+                "{{base64:user:pass}} {{urlencode:a b&c}}",
+                &mut state
+            ),
+            "dXNlcjpwYXNz a%20b%26c"
+        );
+        assert_eq!(
+            process(&engine, "{{md5:abc}}", &mut state),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        // 参数是变量时取它的值
+        assert_eq!(
+            process(&engine, "{{sha256:word}}", &mut state),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            process(
+                &engine,
+                "{{hmac:key:The quick brown fox jumps over the lazy dog}}",
+                &mut state
+            ),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+        let signed = process(&engine, "{{id}} {{md5:id}}", &mut state);
+        let (id, digest) = signed.split_once(' ').unwrap();
+        assert_eq!(digest, to_hex(&Md5::digest(id)));
+        state.locals.insert("tok".to_string(), "abc".to_string());
+        assert_eq!(
+            process(&engine, "{{base64:tok}}", &mut state),
+            base64_encode("abc")
+        );
+
+        assert!(engine.parse_variable_definition("base64:x").is_err());
+        assert!(engine
+            .parse_variable_definition("env:QUICKURL_SURELY_UNSET")
+            .is_err());
+        assert_eq!(
+            engine
+                .unresolved("{{env:QUICKURL_SURELY_UNSET}} {{hmac:nokey}} {{fake:bogus}} {{hex:x}}")
+                .unwrap(),
+            [
+                "env:QUICKURL_SURELY_UNSET",
+                "hmac:nokey",
+                "fake:bogus",
+                "hex:x"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compiled_commands() {
+        let mut engine = TemplateEngine::new();
+        engine.add_static("token".to_string(), "secret".to_string());
+        let mut state = TemplateState::new(0);
+
+        let mut cmd = CurlCommand::new("https://example.com/items".to_string());
+        cmd.method = "{{choice:PUT}}".to_string();
+        cmd.headers.append("Authorization", "Bearer {{token}}");
+        cmd.headers.remove("User-Agent");
+        cmd.body = Some("{\"n\": {{random:7-7}}}".to_string());
+        let template = engine.compile_command(&cmd).unwrap();
+        assert_eq!(template.method.render(&mut state), "PUT");
+        assert!(matches!(template.url.render(&mut state), Cow::Borrowed(_)));
+        let headers = template.headers(&mut state);
+        assert_eq!(headers.get("Authorization"), Some("Bearer secret"));
+        assert!(headers.is_removed("User-Agent"));
+        assert_eq!(
+            template.body.as_ref().unwrap().render(&mut state),
+            "{\"n\": 7}"
+        );
+
+        let plain = engine
+            .compile_command(&CurlCommand::new("https://example.com".to_string()))
+            .unwrap();
+        assert!(matches!(plain.headers(&mut state), Cow::Borrowed(_)));
+        assert_eq!(process(&engine, "", &mut state), "");
+        assert_eq!(
+            process(&engine, "{{token}}{{token}}", &mut state),
+            "secretsecret"
+        );
+
+        // 场景中提取的变量（locals）优先
+        state.locals.insert("tokn".to_string(), "x".to_string());
+        assert_eq!(process(&engine, "a{{tokn}}b", &mut state), "axb");
+
+        assert_eq!(
+            engine
+                .unresolved(
+                    "{{token}} {{tokn}} {{uuid}} {{random:1-x}} {{sequence:1:step=2}} {{$guid}}"
+                )
+                .unwrap(),
+            ["tokn", "random:1-x", "$guid"]
+        );
+    }
+
+    #[test]
+    fn test_feeder_rows() {
+        let path =
+            std::env::temp_dir().join(format!("quickurl-template-{}.csv", std::process::id()));
+        std::fs::write(&path, "id,token\n1,a\n2,b\n").unwrap();
+        let mut engine = TemplateEngine::new();
+        engine
+            .add_variable(
+                "user".to_string(),
+                &format!("csv:{}:order=sequential:exhausted=stop", path.display()),
+            )
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut state = TemplateState::new(0);
+
+        // 同一请求中的字段来自同一行
+        assert_eq!(
+            process(&engine, "/u/{{user.id}}?t={{user.token}}", &mut state),
+            "/u/1?t=a"
+        );
+        assert_eq!(process(&engine, "{{user}}", &mut state), "1");
+        state.next_request();
+        assert_eq!(
+            process(&engine, "/u/{{user.id}}?t={{user.token}}", &mut state),
+            "/u/2?t=b"
+        );
+        assert_eq!(
+            process(&engine, "{{user.missing}}", &mut state),
+            "{{user.missing}}"
+        );
+        assert!(!state.exhausted);
+
+        state.next_request();
+        process(&engine, "{{user.id}}", &mut state);
+        assert!(state.exhausted);
+    }
 }
@@ -0,0 +1,122 @@
+use crate::curl_parser::Transport;
+use anyhow::{anyhow, Result};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use std::path::Path;
+use std::sync::Arc;
+
+/// 按 curl 的 TLS 参数构建客户端配置
+///
+/// - `--cacert`：只信任该文件中的 CA（替代系统根证书）
+/// - `-E/--cert`、`--key`：客户端证书认证，未指定 `--key` 时从证书文件中读取私钥
+/// - `-k/--insecure`：不校验服务端证书
+///
+/// ALPN 由 HttpsConnectorBuilder 根据启用的 HTTP 版本设置。
+pub fn client_config(transport: &Transport) -> Result<ClientConfig> {
+    // 初始化 rustls crypto provider（只需要初始化一次）
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let mut roots = RootCertStore::empty();
+    match &transport.cacert {
+        Some(path) => {
+            for cert in load_certs(path)? {
+                roots
+                    .add(cert)
+                    .map_err(|e| anyhow!("Invalid CA certificate in {}: {}", path.display(), e))?;
+            }
+        }
+        None => {
+            let certs = rustls_native_certs::load_native_certs()
+                .map_err(|e| anyhow!("Failed to load native certs: {}", e))?;
+            // 与 hyper-rustls 一致：跳过无法解析的系统证书
+            roots.add_parsable_certificates(certs);
+        }
+    }
+
+    let builder = ClientConfig::builder().with_root_certificates(roots);
+    let mut config = match &transport.cert {
+        Some(cert_path) => {
+            let certs = load_certs(cert_path)?;
+            let key_path = transport.key.as_deref().unwrap_or(cert_path);
+            let key = PrivateKeyDer::from_pem_file(key_path)
+                .map_err(|e| anyhow!("Failed to load private key {}: {}", key_path.display(), e))?;
+            builder
+                .with_client_auth_cert(certs, key)
+                .map_err(|e| anyhow!("Invalid client certificate: {}", e))?
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    if transport.insecure {
+        let provider = config.crypto_provider().clone();
+        config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(NoVerifier(provider)));
+    }
+
+    Ok(config)
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| anyhow!("Failed to load certificates from {}: {}", path.display(), e))?;
+
+    if certs.is_empty() {
+        return Err(anyhow!("No certificates found in {}", path.display()));
+    }
+
+    Ok(certs)
+}
+
+/// -k：接受任意服务端证书，但仍然校验握手签名
+#[derive(Debug)]
+struct NoVerifier(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
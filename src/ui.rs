@@ -123,14 +123,12 @@ pub struct LiveStats {
 #[derive(Clone, Debug)]
 pub struct EndpointLiveStats {
     pub url: String,
-    pub requests: u64,
     pub requests_per_sec: f64,
     pub avg_latency_ms: f64,
     pub min_latency_ms: f64,
     pub max_latency_ms: f64,
     pub errors: u64,
     pub error_rate: f64,
-    pub status_codes: HashMap<u16, u64>,
 }
 
 impl LiveStats {
@@ -179,14 +177,12 @@ impl LiveStats {
                 url.clone(),
                 EndpointLiveStats {
                     url: ep_snapshot.url.clone(),
-                    requests: ep_snapshot.requests,
                     requests_per_sec: ep_rps,
                     avg_latency_ms: ep_snapshot.avg_latency_ms,
                     min_latency_ms: ep_snapshot.min_latency_ms,
                     max_latency_ms: ep_snapshot.max_latency_ms,
                     errors: ep_snapshot.errors,
                     error_rate: ep_error_rate,
-                    status_codes: ep_snapshot.status_codes.clone(),
                 },
             );
         }
@@ -658,7 +654,7 @@ impl LiveUI {
     fn render_latency_histogram(&self, f: &mut Frame, area: Rect, stats: &LiveStats) {
         let max_latency = stats.max_latency_ms.max(1.0);
 
-        let data = vec![
+        let data = [
             ("P50", stats.p50_latency_ms / max_latency * 100.0),
             ("P75", stats.p75_latency_ms / max_latency * 100.0),
             ("P90", stats.p90_latency_ms / max_latency * 100.0),
@@ -0,0 +1,282 @@
+use crate::curl_parser::CurlCommand;
+use crate::engine::{
+    run_connections, with_timeout, BenchmarkConfig, EndpointPicker, ReconnectBackoff,
+};
+use crate::headers::Headers;
+use crate::stats::{RequestResult, SharedStats, Statistics};
+use crate::template::{CommandTemplate, Template, TemplateEngine};
+use anyhow::{anyhow, Result};
+use futures::{SinkExt, StreamExt};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// 未指定 --ws-message 且命令没有请求体时发送的消息
+const DEFAULT_MESSAGE: &str = "ping";
+
+/// 判断 URL 是否为 WebSocket 地址（ws:// 或 wss://）
+pub fn is_websocket_url(url: &str) -> bool {
+    let url = url.trim_start();
+    url.starts_with("ws://") || url.starts_with("wss://")
+}
+
+/// WebSocket 压测：每个连接循环发送消息并等待回显，记录往返延迟
+///
+/// # 参数
+/// - `commands`: 目标端点（URL 与握手请求头）
+/// - `message`: 消息模板，未指定时使用命令的请求体
+/// - `config`: 压测参数
+/// - `template_engine`: 模板引擎
+/// - `shared_stats`: Live-UI 使用的共享统计
+pub fn run_workers(
+    commands: Vec<CurlCommand>,
+    message: Option<String>,
+    config: &BenchmarkConfig,
+    template_engine: &TemplateEngine,
+    shared_stats: Option<SharedStats>,
+) -> Result<Statistics> {
+    // 初始化 rustls crypto provider（wss:// 需要）
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    // 模板在启动时编译一次：URL 与握手请求头，以及每个端点的消息
+    let templates: Arc<Vec<CommandTemplate>> = Arc::new(
+        commands
+            .iter()
+            .map(|cmd| template_engine.compile_command(cmd))
+            .collect::<Result<_>>()?,
+    );
+    let messages: Arc<Vec<Template>> = Arc::new(
+        commands
+            .iter()
+            .map(|cmd| {
+                template_engine.compile(
+                    message
+                        .as_deref()
+                        .or(cmd.body.as_deref())
+                        .unwrap_or(DEFAULT_MESSAGE),
+                )
+            })
+            .collect::<Result<_>>()?,
+    );
+    let commands = Arc::new(commands);
+    let picker = EndpointPicker::new(&commands, &config.load_strategy)?;
+    let rate = config.rate;
+    let timeout = config.timeout;
+
+    run_connections(config, shared_stats, move |ctx| {
+        let commands = commands.clone();
+        let templates = templates.clone();
+        let messages = messages.clone();
+        let (mut endpoints, mut template_state) = picker.connection(&ctx);
+
+        async move {
+            // 每个任务固定使用一个端点，断线后重连到同一个端点
+            let (idx, _) = endpoints.next();
+            let cmd = &commands[idx];
+            let endpoint = if commands.len() > 1 {
+                Some(cmd.label().to_string())
+            } else {
+                None
+            };
+            let template = &templates[idx];
+            let message = &messages[idx];
+
+            let mut stream: Option<WsStream> = None;
+            let mut backoff = ReconnectBackoff::default();
+
+            while Instant::now() < ctx.end_time {
+                template_state.next_request();
+                let ws = match stream.as_mut() {
+                    Some(ws) => ws,
+                    None => {
+                        let url = template.url.render(&mut template_state);
+                        let headers = template.headers(&mut template_state);
+                        let start = Instant::now();
+                        match with_timeout(timeout, connect(&url, &headers)).await {
+                            Ok(ws) => {
+                                backoff.reset();
+                                stream.insert(ws)
+                            }
+                            Err(e) => {
+                                let _ = ctx.tx.send(RequestResult {
+                                    duration: start.elapsed(),
+                                    error: Some(e.to_string()),
+                                    endpoint: endpoint.clone(),
+                                    connect_error: true,
+                                    ..Default::default()
+                                });
+                                backoff.wait(ctx.end_time).await;
+                                continue;
+                            }
+                        }
+                    }
+                };
+
+                let payload = message.render(&mut template_state).into_owned();
+                if template_state.exhausted {
+                    break;
+                }
+                let start = Instant::now();
+                let result = with_timeout(timeout, round_trip(ws, payload)).await;
+                let duration = start.elapsed();
+
+                let failed = result.is_err();
+                let _ = ctx.tx.send(RequestResult {
+                    duration,
+                    bytes_read: result.as_ref().copied().unwrap_or(0),
+                    error: result.err().map(|e| e.to_string()),
+                    endpoint: endpoint.clone(),
+                    ..Default::default()
+                });
+
+                // 出错后丢弃连接，下一轮重连
+                if failed {
+                    stream = None;
+                }
+
+                // Rate limiting
+                if rate > 0 {
+                    let delay = Duration::from_secs_f64(1.0 / rate as f64);
+                    tokio::time::sleep(delay).await;
+                }
+            }
+
+            if let Some(mut ws) = stream {
+                let _ = ws.close(None).await;
+            }
+        }
+    })
+}
+
+/// 建立 WebSocket 连接，握手时附带命令中的请求头
+async fn connect(url: &str, headers: &Headers) -> Result<WsStream> {
+    let mut request = url
+        .into_client_request()
+        .map_err(|e| anyhow!("Invalid WebSocket URL: {}", e))?;
+
+    for (key, value) in headers {
+        let name = HeaderName::from_bytes(key.as_bytes())
+            .map_err(|e| anyhow!("Invalid header name {}: {}", key, e))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| anyhow!("Invalid header value for {}: {}", key, e))?;
+        request.headers_mut().append(name, value);
+    }
+
+    let (stream, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|e| anyhow!("WebSocket connect failed: {}", e))?;
+
+    Ok(stream)
+}
+
+/// 发送一条消息并等待服务端回复，返回回复的字节数
+async fn round_trip(ws: &mut WsStream, payload: String) -> Result<usize> {
+    ws.send(Message::Text(payload))
+        .await
+        .map_err(|e| anyhow!("WebSocket send failed: {}", e))?;
+
+    // 跳过控制帧，等待第一条数据消息
+    while let Some(msg) = ws.next().await {
+        match msg.map_err(|e| anyhow!("WebSocket receive failed: {}", e))? {
+            Message::Text(text) => return Ok(text.len()),
+            Message::Binary(data) => return Ok(data.len()),
+            Message::Close(_) => return Err(anyhow!("WebSocket closed by server")),
+            Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {}
+        }
+    }
+
+    Err(anyhow!("WebSocket closed by server"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_websocket_url() {
+        assert!(is_websocket_url("ws://localhost:8080/echo"));
+        assert!(is_websocket_url("wss://example.com/socket"));
+        assert!(!is_websocket_url("http://localhost:8080/"));
+        assert!(!is_websocket_url("https://example.com/ws"));
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_and_reconnect() {
+        let addr = crate::mock_server::spawn_local().await.unwrap();
+        let url = format!("ws://{}/echo", addr);
+        let mut headers = Headers::new();
+        headers.append("X-Token", "abc");
+
+        let mut ws = connect(&url, &headers).await.unwrap();
+        assert_eq!(round_trip(&mut ws, "hello".to_string()).await.unwrap(), 5);
+        assert_eq!(round_trip(&mut ws, "{}".to_string()).await.unwrap(), 2);
+
+        // 连接关闭后往返失败，工作循环丢弃连接并重新连接
+        ws.close(None).await.unwrap();
+        assert!(round_trip(&mut ws, "again".to_string()).await.is_err());
+        let mut ws = connect(&url, &headers).await.unwrap();
+        assert_eq!(round_trip(&mut ws, "again".to_string()).await.unwrap(), 5);
+
+        let mut invalid = Headers::new();
+        invalid.append("Bad Header", "x");
+        assert!(connect(&url, &invalid).await.is_err());
+    }
+
+    #[test]
+    fn test_reconnect_backoff() {
+        // 关闭的端口：每次连接都被拒绝
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = BenchmarkConfig::for_test(2, Duration::from_millis(500));
+        let commands = vec![CurlCommand::new(format!("ws://127.0.0.1:{}/", port))];
+        let stats = run_workers(commands, None, &config, &TemplateEngine::new(), None).unwrap();
+
+        // 10ms 起翻倍的间隔下，500ms 内每个连接最多重试 7 次左右
+        assert!(stats.connect_errors > 0);
+        assert!(stats.connect_errors <= 20, "{}", stats.connect_errors);
+    }
+
+    #[tokio::test]
+    async fn test_handshake_headers() {
+        use tokio::io::AsyncReadExt;
+
+        // 只读取升级请求，不回应握手
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            String::from_utf8(request).unwrap().to_lowercase()
+        });
+
+        let mut headers = Headers::new();
+        headers.append("X-Token", "abc");
+        let url = format!("ws://{}/socket", addr);
+        assert!(connect(&url, &headers).await.is_err());
+        let request = server.await.unwrap();
+        assert!(request.starts_with("get /socket http/1.1"), "{}", request);
+        assert!(request.contains("\r\nx-token: abc\r\n"), "{}", request);
+        assert!(
+            request.contains("\r\nupgrade: websocket\r\n"),
+            "{}",
+            request
+        );
+    }
+}